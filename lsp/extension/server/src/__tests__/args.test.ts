@@ -0,0 +1,51 @@
+import { describe, expect, test } from 'bun:test';
+import { parseServerArgs } from '../args';
+import { DEFAULT_TCP_PORT } from '../transport';
+
+describe('parseServerArgs', () => {
+  test('talks over stdio when no transport is given', () => {
+    expect(parseServerArgs([]).transport).toEqual({ kind: 'stdio' });
+    expect(parseServerArgs(['--stdio', '--clientProcessId=42']).transport).toEqual({ kind: 'stdio' });
+  });
+
+  test('leaves --node-ipc and --socket to the connection library', () => {
+    expect(parseServerArgs(['--node-ipc']).transport).toEqual({ kind: 'library' });
+    expect(parseServerArgs(['--socket=5000']).transport).toEqual({ kind: 'library' });
+  });
+
+  test('listens on TCP with --listen or --tcp', () => {
+    expect(parseServerArgs(['--listen', '3000']).transport).toEqual({ kind: 'tcp', host: '127.0.0.1', port: 3000 });
+    expect(parseServerArgs(['--listen=3000']).transport).toEqual({ kind: 'tcp', host: '127.0.0.1', port: 3000 });
+    expect(parseServerArgs(['--tcp']).transport).toEqual({ kind: 'tcp', host: '127.0.0.1', port: DEFAULT_TCP_PORT });
+  });
+
+  test('connects to a named pipe with --pipe', () => {
+    expect(parseServerArgs(['--pipe', 'noolang-lsp']).transport).toEqual({ kind: 'pipe', name: 'noolang-lsp' });
+  });
+
+  test('rejects a port that is not one', () => {
+    expect(() => parseServerArgs(['--listen', 'http'])).toThrow("--listen expects a port number, got 'http'");
+    expect(() => parseServerArgs(['--listen', '70000'])).toThrow('--listen expects a port number');
+  });
+
+  test('rejects a flag missing its value', () => {
+    expect(() => parseServerArgs(['--log-file'])).toThrow('--log-file expects a value');
+    expect(() => parseServerArgs(['--log-file', '--stdio'])).toThrow('--log-file expects a value');
+  });
+
+  test('reads a default log level with per-module overrides', () => {
+    expect(parseServerArgs([]).logLevels).toEqual({ default: 'info', modules: {} });
+    expect(parseServerArgs(['--log-level', 'WARN,bridge=debug']).logLevels).toEqual({
+      default: 'warn',
+      modules: { bridge: 'debug' },
+    });
+    expect(() => parseServerArgs(['--log-level', 'loud'])).toThrow("--log-level expects one of");
+  });
+
+  test('reads --version, --help and --log-file', () => {
+    const args = parseServerArgs(['-V', '--help', '--log-file=/tmp/lsp.log']);
+    expect(args.version).toBe(true);
+    expect(args.help).toBe(true);
+    expect(args.logFile).toBe('/tmp/lsp.log');
+  });
+});
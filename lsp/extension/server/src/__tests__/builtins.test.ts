@@ -0,0 +1,40 @@
+import { describe, expect, test } from 'bun:test';
+import { createLocation, createPosition, type Type } from '../../../../../src/ast';
+import { initializeBuiltins } from '../../../../../src/typer/builtins';
+import { typeToString } from '../../../../../src/typer/helpers';
+import { typeVariableExpr } from '../../../../../src/typer/type-inference';
+import { createTypeState, loadStdlib } from '../../../../../src/typer/type-operations';
+import { BUILTINS } from '../builtins';
+
+// The compiler's environment: its builtins and the standard library
+const state = loadStdlib(initializeBuiltins(createTypeState()));
+const here = createLocation(createPosition(1, 1), createPosition(1, 1));
+
+// A function of several parameters as one taking them one at a time, the way
+// the table writes it: effects on the innermost function, constraints on the
+// outermost
+function curried(type: Type): Type {
+  if (type.kind !== 'function' || type.params.length < 2) return type;
+  const [first, ...rest] = type.params;
+  return {
+    ...type,
+    params: [first],
+    return: curried({ kind: 'function', params: rest, return: type.return, effects: type.effects }),
+    effects: new Set(),
+  };
+}
+
+// The type the compiler gives a name, printed the way the table writes it
+function compilerSignature(name: string): string {
+  const typed = typeVariableExpr({ kind: 'variable', name, location: here }, state);
+  return typeToString(curried(typed.type), typed.state.substitution);
+}
+
+describe('BUILTINS', () => {
+  // A hand-written table drifts unless something holds it to the compiler
+  test('gives each entry the type the compiler does', () => {
+    const names = Object.keys(BUILTINS);
+    const table = Object.fromEntries(names.map((name) => [name, BUILTINS[name].signature]));
+    expect(table).toEqual(Object.fromEntries(names.map((name) => [name, compilerSignature(name)])));
+  });
+});
@@ -0,0 +1,60 @@
+import { describe, expect, test } from 'bun:test';
+import { createLruCache } from '../cache';
+
+const keys = (cache: { sizes(): { key: string }[] }) => cache.sizes().map((e) => e.key);
+
+describe('createLruCache', () => {
+  test('evicts the least recently used entry past the entry limit', () => {
+    const cache = createLruCache<number>({ maxEntries: 2, maxBytes: 100 });
+    cache.set('a', 1, 1);
+    cache.set('b', 2, 1);
+    expect(cache.get('a')).toBe(1);
+    cache.set('c', 3, 1);
+    expect(keys(cache)).toEqual(['a', 'c']);
+    expect(cache.get('b')).toBeUndefined();
+  });
+
+  test('evicts until the entries fit the byte limit', () => {
+    const cache = createLruCache<string>({ maxEntries: 10, maxBytes: 10 });
+    cache.set('a', 'a', 4);
+    cache.set('b', 'b', 4);
+    cache.set('c', 'c', 4);
+    expect(keys(cache)).toEqual(['b', 'c']);
+    expect(cache.stats()).toMatchObject({ entries: 2, bytes: 8, evictions: 1 });
+  });
+
+  test('skips a value bigger than the whole budget', () => {
+    const cache = createLruCache<string>({ maxEntries: 10, maxBytes: 10 });
+    cache.set('a', 'a', 4);
+    cache.set('huge', 'huge', 11);
+    expect(keys(cache)).toEqual(['a']);
+  });
+
+  test('replacing a key replaces its size', () => {
+    const cache = createLruCache<string>({ maxEntries: 10, maxBytes: 10 });
+    cache.set('a', 'old', 6);
+    cache.set('a', 'new', 2);
+    expect(cache.get('a')).toBe('new');
+    expect(cache.stats().bytes).toBe(2);
+  });
+
+  test('counts hits and misses', () => {
+    const cache = createLruCache<number>({ maxEntries: 10, maxBytes: 100 });
+    expect(cache.stats().hitRate).toBe(0);
+    cache.set('a', 1, 1);
+    cache.get('a');
+    cache.get('a');
+    cache.get('b');
+    expect(cache.stats()).toMatchObject({ hits: 2, misses: 1 });
+    expect(cache.stats().hitRate).toBeCloseTo(2 / 3);
+  });
+
+  test('tighter limits evict straight away', () => {
+    const cache = createLruCache<number>({ maxEntries: 10, maxBytes: 100 });
+    for (const key of ['a', 'b', 'c']) cache.set(key, 0, 1);
+    cache.setLimits({ maxEntries: 1, maxBytes: 100 });
+    expect(keys(cache)).toEqual(['c']);
+    cache.clear();
+    expect(cache.stats()).toMatchObject({ entries: 0, bytes: 0 });
+  });
+});
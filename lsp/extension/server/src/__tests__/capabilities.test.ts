@@ -0,0 +1,38 @@
+import { describe, expect, test } from 'bun:test';
+import { BRIDGE_FEATURES, legacyCapabilities, missingFeatures, parseCapabilities } from '../capabilities';
+
+describe('parseCapabilities', () => {
+  test('reads the JSON line after the Capabilities: header', () => {
+    const stdout = 'Noolang\nCapabilities:\n{"version":"0.2.0","flags":["--types-file","--json",3]}\n';
+    const capabilities = parseCapabilities(stdout);
+    expect(capabilities?.version).toBe('0.2.0');
+    expect([...(capabilities?.flags ?? [])]).toEqual(['--types-file', '--json']);
+  });
+
+  test('reads the lexer\'s keywords and operators when the CLI reports them', () => {
+    const stdout = 'Capabilities:\n{"version":"0.2.0","flags":[],"keywords":["fn"],"operators":["=>"]}';
+    expect(parseCapabilities(stdout)?.lexicon).toEqual({ keywords: ['fn'], operators: ['=>'] });
+    expect(parseCapabilities('Capabilities:\n{"version":"0.2.0","flags":[]}')?.lexicon).toBeUndefined();
+  });
+
+  test('is undefined for a CLI without --capabilities', () => {
+    expect(parseCapabilities('Unknown option --capabilities')).toBeUndefined();
+    expect(parseCapabilities('Capabilities:\nnot json')).toBeUndefined();
+    expect(parseCapabilities('Capabilities:\n{"version":"0.2.0"}')).toBeUndefined();
+  });
+});
+
+describe('missingFeatures', () => {
+  test('a CLI with every flag misses nothing', () => {
+    const flags = new Set(BRIDGE_FEATURES.map((f) => f.flag));
+    expect(missingFeatures({ version: null, flags })).toEqual([]);
+  });
+
+  test('features with a fallback flag are not missing', () => {
+    const missing = missingFeatures(legacyCapabilities());
+    expect(missing).not.toContain('navigation in files with syntax errors');
+    expect(missing).not.toContain('batched symbol types');
+    expect(missing).not.toContain('diagnostics');
+    expect(missing).toContain('test lenses');
+  });
+});
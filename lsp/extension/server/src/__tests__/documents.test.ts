@@ -0,0 +1,58 @@
+import { describe, expect, test } from 'bun:test';
+import { createDocumentStore, createRwLock } from '../documents';
+
+const deferred = () => {
+  let resolve = () => {};
+  const promise = new Promise<void>((r) => (resolve = () => r()));
+  return { promise, resolve };
+};
+
+describe('createRwLock', () => {
+  test('reads overlap', async () => {
+    const lock = createRwLock();
+    const gate = deferred();
+    const events: string[] = [];
+    const first = lock.read(async () => {
+      events.push('first in');
+      await gate.promise;
+      events.push('first out');
+    });
+    const second = lock.read(() => events.push('second'));
+    await second;
+    expect(events).toEqual(['first in', 'second']);
+    gate.resolve();
+    await first;
+  });
+
+  test('a write waits for reads, and reads after it wait for the write', async () => {
+    const lock = createRwLock();
+    const gate = deferred();
+    const events: string[] = [];
+    const reading = lock.read(async () => {
+      await gate.promise;
+      events.push('read');
+    });
+    const writing = lock.write(() => events.push('write'));
+    const later = lock.read(() => events.push('later read'));
+    gate.resolve();
+    await Promise.all([reading, writing, later]);
+    expect(events).toEqual(['read', 'write', 'later read']);
+  });
+
+  test('releases the lock when the work throws', async () => {
+    const lock = createRwLock();
+    await expect(lock.write(() => Promise.reject(new Error('failed')))).rejects.toThrow('failed');
+    expect(await lock.write(() => 'next')).toBe('next');
+  });
+});
+
+describe('createDocumentStore', () => {
+  test('update swaps the text and version in', async () => {
+    const documents = createDocumentStore();
+    const entry = documents.open('file:///a.noo', 'x = 1', 1);
+    await documents.update(entry, 'x = 2', 2);
+    expect(documents.get('file:///a.noo')).toMatchObject({ text: 'x = 2', version: 2 });
+    documents.close('file:///a.noo');
+    expect(documents.size).toBe(0);
+  });
+});
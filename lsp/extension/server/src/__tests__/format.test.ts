@@ -0,0 +1,42 @@
+import { describe, expect, test } from 'bun:test';
+import { formatSource } from '../format';
+import { main } from '../noofmt';
+
+describe('formatSource', () => {
+  test('spaces after commas and none inside brackets', () => {
+    expect(formatSource('[1,2 ,3]')).toBe('[1, 2, 3]\n');
+    expect(formatSource('y = f( 2 )')).toBe('y = f(2)\n');
+  });
+
+  test('caps blank lines at one and ends with a single newline', () => {
+    expect(formatSource('x = 1;\n\n\n\ny = 2\n\n\n')).toBe('x = 1;\n\ny = 2\n');
+  });
+
+  test('keeps indentation and trims trailing comment whitespace', () => {
+    expect(formatSource('  x = 1  # note   \n')).toBe('  x = 1  # note\n');
+  });
+
+  test('keeps CRLF line endings', () => {
+    expect(formatSource('x = 1\r\ny = 2')).toBe('x = 1\r\ny = 2\r\n');
+  });
+
+  test('is idempotent', () => {
+    const once = formatSource('f = fn a => a+1;\n\n\n[1,2]') ?? '';
+    expect(formatSource(once)).toBe(once);
+  });
+
+  test('gives up on source that does not lex', () => {
+    expect(formatSource('x = "unterminated')).toBeUndefined();
+  });
+});
+
+describe('noofmt', () => {
+  test('rejects unknown flags before touching files', () => {
+    expect(main(['--chek', 'missing.noo'])).toBe(2);
+    expect(main(['-w', 'missing.noo'])).toBe(2);
+  });
+
+  test('needs a target', () => {
+    expect(main(['--check'])).toBe(2);
+  });
+});
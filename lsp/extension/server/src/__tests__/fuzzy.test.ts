@@ -0,0 +1,39 @@
+import { describe, expect, test } from 'bun:test';
+import { fuzzyScore, rankSymbols } from '../fuzzy';
+
+describe('fuzzyScore', () => {
+  test('matches characters in order, ignoring case', () => {
+    expect(fuzzyScore('gun', 'getUserName')).toBeDefined();
+    expect(fuzzyScore('gUN', 'get_user_name')).toBeDefined();
+    expect(fuzzyScore('nug', 'getUserName')).toBeUndefined();
+    expect(fuzzyScore('', 'anything')).toBe(0);
+  });
+
+  test('word starts score above letters mid-word', () => {
+    expect(fuzzyScore('gun', 'getUserName')!).toBeGreaterThan(fuzzyScore('gun', 'begun')!);
+    expect(fuzzyScore('gun', 'get_user_name')!).toBeGreaterThan(fuzzyScore('gun', 'begun')!);
+  });
+
+  test('an exact name beats a prefix, which beats a match elsewhere', () => {
+    expect(fuzzyScore('map', 'map')!).toBeGreaterThan(fuzzyScore('map', 'mapField')!);
+    expect(fuzzyScore('map', 'mapField')!).toBeGreaterThan(fuzzyScore('map', 'flatMap')!);
+  });
+});
+
+describe('rankSymbols', () => {
+  const symbols = [
+    { name: 'flatMap', kind: 'function' as const },
+    { name: 'mapField', kind: 'field' as const },
+    { name: 'map', kind: 'function' as const },
+    { name: 'Map', kind: 'type' as const },
+    { name: 'filter', kind: 'function' as const },
+  ];
+
+  test('ranks matches best first, types ahead of functions among equals', () => {
+    expect(rankSymbols('map', symbols, 10).map((s) => s.name)).toEqual(['Map', 'map', 'mapField', 'flatMap']);
+  });
+
+  test('caps the results', () => {
+    expect(rankSymbols('map', symbols, 2).map((s) => s.name)).toEqual(['Map', 'map']);
+  });
+});
@@ -0,0 +1,50 @@
+import { describe, expect, test } from 'bun:test';
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import { indexCacheFile, loadIndex, saveIndex } from '../index-cache';
+import { createWorkspaceIndex, indexText } from '../workspace';
+
+const tempFile = () => indexCacheFile(fs.mkdtempSync(path.join(os.tmpdir(), 'noo-index-')), ['/ws']);
+
+describe('saveIndex', () => {
+  test('keeps what was indexed but not the text', () => {
+    const file = tempFile();
+    saveIndex(file, [indexText('/ws/a.noo', 'secret = 1;\nsecret', '100:19')]);
+    expect(fs.readFileSync(file, 'utf8').includes('secret = 1')).toBe(false);
+    const [entry] = loadIndex(file);
+    expect(entry).toMatchObject({ filePath: '/ws/a.noo', stamp: '100:19' });
+    expect(entry.identifiers.get('secret')).toHaveLength(2);
+  });
+
+  test("skips entries indexed from an open document's buffer", () => {
+    const file = tempFile();
+    saveIndex(file, [indexText('/ws/a.noo', 'a = 1', 'v3'), indexText('/ws/b.noo', 'b = 1', '100:5')]);
+    expect(loadIndex(file).map((f) => f.filePath)).toEqual(['/ws/b.noo']);
+  });
+
+  test('a missing or corrupt cache is empty', () => {
+    const file = tempFile();
+    expect(loadIndex(file)).toEqual([]);
+    fs.writeFileSync(file, '{');
+    expect(loadIndex(file)).toEqual([]);
+  });
+});
+
+describe('restoring a saved index', () => {
+  test('reads the text back and reuses an entry whose content matches', () => {
+    const file = tempFile();
+    saveIndex(file, [indexText('/ws/a.noo', 'a = 1', '100:5')]);
+    const [saved] = loadIndex(file);
+    const index = createWorkspaceIndex(() => ({ stamp: '200:5', read: () => 'a = 1' }));
+    index.restore([{ ...saved, declarations: [] }]);
+    expect(index.get('/ws/a.noo')).toMatchObject({ text: 'a = 1', stamp: '200:5', declarations: [] });
+  });
+
+  test('re-indexes a file that changed', () => {
+    const index = createWorkspaceIndex(() => ({ stamp: '200:6', read: () => 'bb = 1' }));
+    const { text: _text, ...saved } = indexText('/ws/a.noo', 'a = 1', '100:5');
+    index.restore([saved]);
+    expect(index.get('/ws/a.noo')?.identifiers.has('bb')).toBe(true);
+  });
+});
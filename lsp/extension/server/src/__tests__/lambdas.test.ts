@@ -0,0 +1,56 @@
+import { describe, expect, test } from 'bun:test';
+import { lambdaAt, lambdaRewrites } from '../lambdas';
+
+const ARITIES: Record<string, number> = { map: 2, print: 1, inc: 1, foo: 2 };
+const rewrites = (source: string, offset: number, arity = (name: string) => ARITIES[name]) => {
+  const lambda = lambdaAt(source, offset);
+  return lambda ? lambdaRewrites(source, lambda, arity).map(({ title, text }) => ({ title, text })) : [];
+};
+
+describe('lambdaAt', () => {
+  test('finds the lambda, its parameters and the name it is bound to', () => {
+    const lambda = lambdaAt('add = fn x y => x + y;', 10);
+    expect(lambda?.span).toEqual({ start: 6, end: 21 });
+    expect(lambda?.params.map((p) => p.text)).toEqual(['x', 'y']);
+    expect(lambda?.binding).toBe('add');
+  });
+
+  test('ends the body at an unmatched closer', () => {
+    const lambda = lambdaAt('k = (fn a => a) 1', 6);
+    expect(lambda?.span).toEqual({ start: 5, end: 14 });
+    expect(lambda?.binding).toBeUndefined();
+  });
+
+  test('picks the innermost lambda', () => {
+    expect(lambdaAt('f = fn x => fn y => x + y;', 14)?.params.map((p) => p.text)).toEqual(['y']);
+  });
+
+  test('is undefined outside any lambda', () => {
+    expect(lambdaAt('x = 1; f = fn a => a', 2)).toBeUndefined();
+  });
+});
+
+describe('lambdaRewrites', () => {
+  test('splits and merges parameters', () => {
+    expect(rewrites('add = fn x y => x + y;', 10)).toEqual([
+      { title: 'Convert to nested lambdas', text: 'fn x => fn y => x + y' },
+    ]);
+    expect(rewrites('f = fn x => fn y => x + y;', 5)).toEqual([
+      { title: 'Merge nested lambdas', text: 'fn x y => x + y' },
+    ]);
+  });
+
+  test('eta-reduces a partial application', () => {
+    expect(rewrites('g = fn x => map inc x;', 5)).toEqual([{ title: 'Eta-reduce to `map inc`', text: 'map inc' }]);
+    expect(rewrites('g = fn x => print x;', 5)).toEqual([{ title: 'Eta-reduce to `print`', text: 'print' }]);
+  });
+
+  test('does not eta-reduce when that would call the function early', () => {
+    expect(rewrites('g = fn x => map inc x;', 5, () => 1)).toEqual([]);
+  });
+
+  test('does not eta-reduce a reused parameter or a function calling itself', () => {
+    expect(rewrites('h = fn x => foo x x;', 5)).toEqual([]);
+    expect(rewrites('g = fn x => g x;', 5)).toEqual([]);
+  });
+});
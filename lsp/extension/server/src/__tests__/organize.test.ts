@@ -0,0 +1,59 @@
+import { describe, expect, test } from 'bun:test';
+import { classifyStatement, organizeDefinitions, splitTopLevelStatements } from '../organize';
+
+describe('splitTopLevelStatements', () => {
+  test('splits at semicolons outside strings, comments and brackets', () => {
+    const statements = splitTopLevelStatements('a = "x;y"; # c;\nb = {1; 2}; c');
+    expect(statements?.map((s) => s.text)).toEqual(['a = "x;y"', ' # c;\nb = {1; 2}', ' c']);
+  });
+
+  test('gives up on unbalanced brackets', () => {
+    expect(splitTopLevelStatements('a = (1; b')).toBeUndefined();
+    expect(splitTopLevelStatements('a = 1); b')).toBeUndefined();
+  });
+});
+
+describe('classifyStatement', () => {
+  test('groups imports, type declarations and values', () => {
+    expect(classifyStatement('m = import "m"')).toBe('import');
+    expect(classifyStatement('{@map} = import "std/list"')).toBe('import');
+    expect(classifyStatement('# doc\nconstraint Show a ( show : a -> String )')).toBe('type');
+    expect(classifyStatement('variant Shape = Circle Float')).toBe('type');
+    expect(classifyStatement('f = fn x => x')).toBe('value');
+  });
+});
+
+describe('organizeDefinitions', () => {
+  test('puts imports, then types, then values first, and keeps the export last', () => {
+    const source = [
+      'x = 1; # one',
+      'variant Shape = Circle Float | Square Float;',
+      '{@map} = import "std/list";',
+      'y = 2;',
+      'x + y',
+      '',
+    ].join('\n');
+    expect(organizeDefinitions(source)).toBe(
+      [
+        '{@map} = import "std/list";',
+        '',
+        'variant Shape = Circle Float | Square Float;',
+        '',
+        'x = 1; # one',
+        'y = 2;',
+        '',
+        'x + y',
+        '',
+      ].join('\n'),
+    );
+  });
+
+  test('drops an import repeated with different spacing', () => {
+    const source = 'm = import "m";\nm =  import "m";\nm';
+    expect(organizeDefinitions(source)).toBe('m = import "m";\n\nm\n');
+  });
+
+  test('leaves a single statement alone', () => {
+    expect(organizeDefinitions('x')).toBeUndefined();
+  });
+});
@@ -0,0 +1,27 @@
+import { describe, expect, test } from 'bun:test';
+import { lineMap } from '../positions';
+
+describe('lineMap', () => {
+  test('lines after an inserted line move down by one', () => {
+    const map = lineMap('a\nnew\nb\nc', 'a\nb\nc');
+    expect([0, 1, 2, 3].map(map.toSaved)).toEqual([0, undefined, 1, 2]);
+    expect([0, 1, 2].map(map.fromSaved)).toEqual([0, 2, 3]);
+  });
+
+  test('lines after a removed line move up by one', () => {
+    const map = lineMap('a\nc', 'a\nb\nc');
+    expect([0, 1].map(map.toSaved)).toEqual([0, 2]);
+    expect([0, 1, 2].map(map.fromSaved)).toEqual([0, undefined, 1]);
+  });
+
+  test('an edited line has no counterpart', () => {
+    const map = lineMap('a\nB\nc', 'a\nb\nc');
+    expect([0, 1, 2].map(map.toSaved)).toEqual([0, undefined, 2]);
+    expect([0, 1, 2].map(map.fromSaved)).toEqual([0, undefined, 2]);
+  });
+
+  test('an unedited buffer maps every line to itself', () => {
+    const map = lineMap('a\r\nb', 'a\nb');
+    expect([0, 1].map(map.toSaved)).toEqual([0, 1]);
+  });
+});
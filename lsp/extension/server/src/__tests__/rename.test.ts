@@ -0,0 +1,55 @@
+import { describe, expect, test } from 'bun:test';
+import { Lexer } from '../../../../../src/lexer/lexer';
+import { invalidRename, parseCliTokens, RenameTarget, syntaxTokens } from '../rename';
+
+const name: RenameTarget = { kind: 'name', name: 'total', span: { start: 0, end: 5 } };
+const field: RenameTarget = { kind: 'field', name: 'total', span: { start: 0, end: 5 } };
+
+// What `noo --tokens @<name>` reports
+const compilerTokens = (newName: string) =>
+  new Lexer(`@${newName}`)
+    .tokenize()
+    .filter((t) => t.type !== 'EOF')
+    .map((t) => ({ type: t.type, value: t.value }));
+
+describe('parseCliTokens', () => {
+  test('reads the tokens of `noo --tokens` and leaves out EOF', () => {
+    const stdout = "Tokens:\n  0: ACCESSOR ('sum')\n  1: OPERATOR ('-')\n  2: EOF ('')\n";
+    expect(parseCliTokens(stdout)).toEqual([
+      { type: 'ACCESSOR', value: 'sum' },
+      { type: 'OPERATOR', value: '-' },
+    ]);
+  });
+});
+
+describe('invalidRename', () => {
+  const check = (target: RenameTarget, newName: string) => invalidRename(target, newName, compilerTokens(newName));
+
+  test('takes names the compiler lexes as one name', () => {
+    expect(check(name, 'sum_2')).toBeUndefined();
+    expect(check(field, 'Sum')).toBeUndefined();
+  });
+
+  test('rejects keywords for names but not for fields', () => {
+    expect(check(name, 'if')).toBe("'if' is a keyword");
+    expect(check(field, 'if')).toBeUndefined();
+  });
+
+  test('rejects what the compiler lexes as more than a name', () => {
+    for (const newName of ['a-b', 'a b', 'x?', 'mut!', 'é']) {
+      expect(check(field, newName)).toBe(`'${newName}' isn't a valid name: use letters, digits and _`);
+    }
+    expect(check(name, '1x')).toBe("'1x' starts with a digit");
+    expect(check(name, '')).toBe('The new name is empty');
+  });
+
+  test("keeps the case of the old name's first letter", () => {
+    expect(check(name, 'Sum')).toBe("'Sum' must start with a lowercase letter or _, like 'total'");
+  });
+
+  test('falls back to the server lexer the same way', () => {
+    for (const newName of ['sum', 'a-b', 'x?', 'é']) {
+      expect(invalidRename(field, newName, syntaxTokens(`@${newName}`))).toBe(check(field, newName));
+    }
+  });
+});
@@ -0,0 +1,38 @@
+import { describe, expect, test } from 'bun:test';
+import { toReplLine } from '../repl';
+
+describe('toReplLine', () => {
+  test('joins lines and drops comments', () => {
+    expect(toReplLine('x = 1; # one\n\ny = 2;  # two\n  x + y\n')).toBe('x = 1; y = 2; x + y');
+  });
+
+  test('keeps a # inside a string of either quote', () => {
+    expect(toReplLine('a = "#1"; # note\nb = \'#2\'')).toBe('a = "#1"; b = \'#2\'');
+    expect(toReplLine('t = `#${a}` # note')).toBe('t = `#${a}`');
+  });
+
+  test('keeps the spacing inside strings', () => {
+    expect(toReplLine('s = "two  spaces\\tand a tab";\n s')).toBe('s = "two  spaces\\tand a tab"; s');
+    expect(toReplLine("s = 'a   b'")).toBe("s = 'a   b'");
+  });
+
+  test('keeps escaped quotes inside a string', () => {
+    expect(toReplLine('s = "say \\"#hi\\""  # done')).toBe('s = "say \\"#hi\\""');
+  });
+
+  test('ends a statement at a line break that starts another', () => {
+    expect(toReplLine('x = f 1\ng x')).toBe('x = f 1; g x');
+    expect(toReplLine('  x = f 1 # one\n  g x\n')).toBe('x = f 1; g x');
+  });
+
+  test('keeps an expression that carries on over lines together', () => {
+    expect(toReplLine('inc = fn x =>\n  x + 1;\ninc 2')).toBe('inc = fn x => x + 1; inc 2');
+    expect(toReplLine('xs = [1, 2]\n|> map inc')).toBe('xs = [1, 2] |> map inc');
+    expect(toReplLine('y = if ok\nthen 1\nelse 2')).toBe('y = if ok then 1 else 2');
+    expect(toReplLine('p = f (\n1\n)')).toBe('p = f ( 1 )');
+  });
+
+  test('is empty for a selection of only comments', () => {
+    expect(toReplLine('# nothing\n  # here')).toBe('');
+  });
+});
@@ -0,0 +1,37 @@
+import { afterAll, describe, expect, test } from 'bun:test';
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import { pathToFileURL } from 'url';
+import { startTestServer } from '../testing';
+
+// The compiler in this repository, run from source
+const REPO = path.resolve(__dirname, '../../../../..');
+const server = startTestServer({ cliPath: path.join(REPO, 'src', 'cli.ts'), runtime: 'bun', workspace: REPO });
+
+afterAll(() => server.shutdown());
+
+// The CLI checks the file on disk, so the document is saved before it's opened
+function savedDocument(text: string): string {
+  const file = path.join(fs.mkdtempSync(path.join(os.tmpdir(), 'noo-lsp-')), 'main.noo');
+  fs.writeFileSync(file, text);
+  return pathToFileURL(file).href;
+}
+
+describe('language server', () => {
+  test('reports a type error and hovers a binding', async () => {
+    await server.initialize();
+    const text = 'double = fn n => n * 2;\nbad = 1 + "a";\ndouble 3\n';
+    const uri = savedDocument(text);
+    await server.open(uri, text);
+
+    const { diagnostics } = await server.diagnostics(uri, (d) => d.diagnostics.length > 0);
+    expect(diagnostics).toHaveLength(1);
+    expect(diagnostics[0].message).toContain('Operator type mismatch');
+    expect(diagnostics[0].range.start.line).toBe(1);
+
+    const hover = await server.hover(uri, { line: 0, character: 2 });
+    const contents = hover?.contents as { value: string } | undefined;
+    expect(contents?.value).toContain('double = fn n => n * 2');
+  }, 30_000); // Each step runs the CLI
+});
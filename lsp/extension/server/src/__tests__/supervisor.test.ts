@@ -0,0 +1,47 @@
+import { describe, expect, test } from 'bun:test';
+import { backoffMs, createSupervisor, INITIAL_BACKOFF_MS, MAX_BACKOFF_MS, NOTIFY_AFTER_FAILURES } from '../supervisor';
+
+describe('backoffMs', () => {
+  test('doubles from the initial backoff up to the cap', () => {
+    expect(backoffMs(0)).toBe(0);
+    expect(backoffMs(1)).toBe(INITIAL_BACKOFF_MS);
+    expect(backoffMs(2)).toBe(INITIAL_BACKOFF_MS * 2);
+    expect(backoffMs(3)).toBe(INITIAL_BACKOFF_MS * 4);
+    expect(backoffMs(20)).toBe(MAX_BACKOFF_MS);
+  });
+});
+
+describe('createSupervisor', () => {
+  test('holds calls off until the backoff has passed', () => {
+    const supervisor = createSupervisor();
+    expect(supervisor.canCall(0)).toBe(true);
+    supervisor.recordFailure(1000);
+    expect(supervisor.canCall(1000 + INITIAL_BACKOFF_MS - 1)).toBe(false);
+    expect(supervisor.canCall(1000 + INITIAL_BACKOFF_MS)).toBe(true);
+  });
+
+  test('asks to notify the client once per failure streak', () => {
+    const supervisor = createSupervisor();
+    const notified = Array.from({ length: NOTIFY_AFTER_FAILURES + 2 }, (_, i) => supervisor.recordFailure(i));
+    expect(notified.filter(Boolean)).toHaveLength(1);
+    expect(notified[NOTIFY_AFTER_FAILURES - 1]).toBe(true);
+  });
+
+  test('a success ends the streak', () => {
+    const supervisor = createSupervisor();
+    for (let i = 0; i < NOTIFY_AFTER_FAILURES; i++) supervisor.recordFailure(0);
+    supervisor.recordSuccess();
+    expect(supervisor.state()).toEqual({ consecutiveFailures: 0, retryAt: 0, notified: false });
+    expect(supervisor.canCall(0)).toBe(true);
+    const notified = Array.from({ length: NOTIFY_AFTER_FAILURES }, () => supervisor.recordFailure(0));
+    expect(notified[NOTIFY_AFTER_FAILURES - 1]).toBe(true);
+  });
+
+  test('reset allows calls straight away', () => {
+    const supervisor = createSupervisor();
+    supervisor.recordFailure(0);
+    supervisor.reset();
+    expect(supervisor.canCall(0)).toBe(true);
+    expect(supervisor.state().consecutiveFailures).toBe(0);
+  });
+});
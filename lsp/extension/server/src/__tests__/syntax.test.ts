@@ -0,0 +1,92 @@
+import { describe, expect, test } from 'bun:test';
+import { KEYWORDS, Lexer, OPERATORS } from '../../../../../src/lexer/lexer';
+import { DEFAULT_LEXICON, isTrivia, lexSyntax, Lexicon, parseSyntax, SyntaxError, useLexicon } from '../syntax';
+
+// The compiler's tokens as the server's: its kinds in lowercase, a template
+// (TEMPLATE_START to TEMPLATE_END, holes included) as one token, and the text
+// each token covers in the source
+function compilerTokens(source: string) {
+  const lineStarts = [0, ...[...source.matchAll(/\n/g)].map((m) => m.index! + 1)];
+  const offset = (p: { line: number; column: number }) => lineStarts[p.line - 1] + p.column - 1;
+  const tokens: { kind: string; text: string }[] = [];
+  let template: { start: number; depth: number } | undefined;
+  for (const t of new Lexer(source).tokenize()) {
+    const [start, end] = [offset(t.location.start), offset(t.location.end)];
+    if (t.type === 'TEMPLATE_START') {
+      if (template) template.depth++;
+      else template = { start, depth: 0 };
+    } else if (t.type === 'TEMPLATE_END' && template) {
+      if (template.depth > 0) template.depth--;
+      else {
+        tokens.push({ kind: 'template', text: source.slice(template.start, end) });
+        template = undefined;
+      }
+    } else if (!template && t.type !== 'EOF') {
+      tokens.push({ kind: t.type.toLowerCase(), text: source.slice(start, end) });
+    }
+  }
+  return tokens;
+}
+
+const serverTokens = (source: string) =>
+  lexSyntax(source)
+    .filter((t) => !isTrivia(t))
+    .map((t) => ({ kind: t.kind, text: t.text }));
+
+const SOURCES = [
+  'x = fn a b => a + b * 2; # adds\nx 1 2',
+  'p = {@name "Ada", @age 36}; p | @name; @age? p',
+  "s = 'single'; t = \"double \\\" quote\"; u = `a ${s} and ${`nested ${t}`} b`",
+  'mut! y = 2.5 |? f |> g <| h; y != 1 && y >= 2 || y <= 3 == True',
+  'variant Shape a = Circle Float | Square a;\nconstraint Show a ( show : a -> String );\n' +
+    'implement Show Float ( show = toString )',
+  'r = match x with ( Some v => v; None => 0 ); l = [1, 2, 3]; _ = ?hole; f $ x % 2',
+  'import "std/list"; Float; Number; List String; Unknown; Unit; if a then b else c',
+];
+
+describe('lexSyntax', () => {
+  test('tokenizes as the compiler does', () => {
+    for (const source of SOURCES) expect(serverTokens(source)).toEqual(compilerTokens(source));
+  });
+
+  test('lexes with the compiler\'s keywords and operators', () => {
+    expect(DEFAULT_LEXICON.keywords).toEqual(KEYWORDS);
+    expect(DEFAULT_LEXICON.operators).toEqual(OPERATORS);
+  });
+
+  test('keeps every character, so the tokens print the source back', () => {
+    for (const source of SOURCES) expect(lexSyntax(source).map((t) => t.text).join('')).toBe(source);
+  });
+
+  test('reports unterminated strings and unknown characters', () => {
+    const errors: SyntaxError[] = [];
+    lexSyntax('x = "open\ny ~ 1', errors);
+    expect(errors.map((e) => e.message)).toEqual(['Unterminated string']);
+    const more: SyntaxError[] = [];
+    lexSyntax('y ~ 1', more);
+    expect(more).toEqual([{ message: "Unexpected character '~'", start: 2, end: 3 }]);
+  });
+
+  test('follows the lexicon a CLI reports', () => {
+    const lexicon: Lexicon = { ...DEFAULT_LEXICON, keywords: [...DEFAULT_LEXICON.keywords, 'where2'] };
+    useLexicon(lexicon);
+    try {
+      expect(lexSyntax('where2')[0].kind).toBe('keyword');
+    } finally {
+      useLexicon(DEFAULT_LEXICON);
+    }
+    expect(lexSyntax('where2')[0].kind).toBe('identifier');
+  });
+});
+
+describe('parseSyntax', () => {
+  test('groups statements, definitions and brackets', () => {
+    const { root, errors } = parseSyntax('x = (1 + 2); x');
+    expect(root.children.map((c) => c.kind)).toEqual(['definition', 'punctuation', 'whitespace', 'statement']);
+    expect(errors).toEqual([]);
+  });
+
+  test('reports an unclosed bracket', () => {
+    expect(parseSyntax('x = (1 + 2; x').errors).toEqual([{ message: "Missing ')'", start: 4, end: 5 }]);
+  });
+});
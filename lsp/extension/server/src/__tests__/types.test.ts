@@ -0,0 +1,73 @@
+import { describe, expect, test } from 'bun:test';
+import { formatTypeString, NoolangType, parseType, renderType, sameType } from '../types';
+
+const named = (name: string, ...args: NoolangType[]): NoolangType => ({ kind: 'named', name, args });
+
+describe('parseType', () => {
+  test('reads applications and curried functions', () => {
+    expect(parseType('Option Float')).toEqual(named('Option', named('Float')));
+    expect(parseType('(Float -> Float) -> Float')).toEqual({
+      kind: 'function',
+      param: { kind: 'function', param: named('Float'), result: named('Float'), effects: [] },
+      result: named('Float'),
+      effects: [],
+    });
+  });
+
+  test('reads records, tuples and unit', () => {
+    expect(parseType('{@x Float, @y String}')).toEqual({
+      kind: 'record',
+      fields: [
+        { name: 'x', type: named('Float') },
+        { name: 'y', type: named('String') },
+      ],
+    });
+    expect(parseType('{Float, String}')).toEqual({ kind: 'tuple', elements: [named('Float'), named('String')] });
+    expect(parseType('{}')).toEqual({ kind: 'unit' });
+  });
+
+  test('puts effects on the innermost function', () => {
+    expect(parseType('a -> {} !write')).toEqual({
+      kind: 'function',
+      param: { kind: 'variable', name: 'a' },
+      result: { kind: 'unit' },
+      effects: ['write'],
+    });
+  });
+
+  test('reads constraints', () => {
+    expect(parseType('a -> String given a implements Show')).toMatchObject({
+      kind: 'constrained',
+      constraints: [{ kind: 'implements', variable: 'a', trait: 'Show' }],
+    });
+  });
+
+  test('is undefined for text that is not a type', () => {
+    expect(parseType('Float -> Float -> ->')).toBeUndefined();
+  });
+});
+
+describe('renderType', () => {
+  test('renders what it parses', () => {
+    for (const text of ['List a -> (a -> b) -> List b', 'a -> String given a implements Show', 'a -> {} !write']) {
+      expect(renderType(parseType(text)!)).toBe(text.replace(/->/g, '→'));
+    }
+  });
+
+  test('elides record fields first when a type is too wide', () => {
+    const fields = ['name', 'age', 'email', 'address', 'phone', 'city'].map((f) => `@${f} String`).join(', ');
+    const elided = '{ @name String, @age String, @email String, … } → String';
+    expect(formatTypeString(`{${fields}} -> String`)).toBe(elided);
+  });
+
+  test('leaves text it cannot parse as it is', () => {
+    expect(formatTypeString('not a type ->')).toBe('not a type ->');
+  });
+});
+
+describe('sameType', () => {
+  test('compares structure, not spelling', () => {
+    expect(sameType(parseType('List a')!, parseType('List  a')!)).toBe(true);
+    expect(sameType(parseType('List a')!, parseType('List b')!)).toBe(false);
+  });
+});
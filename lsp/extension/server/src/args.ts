@@ -0,0 +1,89 @@
+// Command-line flags for the server binary. Editor clients pass their own
+// flags (vscode-languageclient adds --clientProcessId=<pid>, others pass
+// --stdio explicitly), so anything unrecognized is ignored rather than fatal.
+import { DEFAULT_TCP_PORT, Transport } from './transport';
+
+export const LOG_LEVELS = ['error', 'warn', 'info', 'debug', 'trace'] as const;
+export type LogLevel = (typeof LOG_LEVELS)[number];
+
+// A default level plus per-module overrides, e.g. `info,bridge=debug`
+export type LogLevels = { default: LogLevel; modules: Record<string, LogLevel> };
+
+export type ServerArgs = {
+  transport: Transport;
+  version: boolean;
+  help: boolean;
+  logFile?: string;
+  logLevels: LogLevels;
+};
+
+export const USAGE = `Usage: noolang-lsp [options]
+
+Options:
+  --stdio               Communicate over stdin/stdout (default)
+  --listen <port>       Listen for one client on a TCP port
+  --tcp                 Listen on the default TCP port (${DEFAULT_TCP_PORT})
+  --pipe <name>         Connect to a named pipe (a socket path outside Windows)
+                        the client is listening on
+  --log-file <path>     Append server logs to a file
+  --log-level <spec>    One of ${LOG_LEVELS.join(', ')} (default: info), optionally
+                        with per-module overrides: info,bridge=debug
+  --version             Print the server version and exit
+  --help                Print this message and exit`;
+
+// Reads `--flag value` and `--flag=value`; undefined when the flag is absent.
+function flagValue(argv: string[], flag: string): string | undefined {
+  const inline = argv.find((a) => a.startsWith(flag + '='));
+  if (inline) return inline.slice(flag.length + 1);
+  const i = argv.indexOf(flag);
+  if (i < 0) return undefined;
+  const value = argv[i + 1];
+  if (value === undefined || value.startsWith('--')) throw new Error(`${flag} expects a value`);
+  return value;
+}
+
+function parsePort(raw: string): number {
+  const port = Number(raw);
+  if (!Number.isInteger(port) || port < 0 || port > 65535) {
+    throw new Error(`--listen expects a port number, got '${raw}'`);
+  }
+  return port;
+}
+
+function parseLogLevel(raw: string): LogLevel {
+  const level = LOG_LEVELS.find((l) => l === raw.toLowerCase());
+  if (!level) throw new Error(`--log-level expects one of ${LOG_LEVELS.join(', ')}, got '${raw}'`);
+  return level;
+}
+
+function parseLogLevels(raw: string | undefined): LogLevels {
+  const levels: LogLevels = { default: 'info', modules: {} };
+  for (const part of (raw ?? '').split(',').filter(Boolean)) {
+    const eq = part.indexOf('=');
+    if (eq < 0) levels.default = parseLogLevel(part);
+    else levels.modules[part.slice(0, eq)] = parseLogLevel(part.slice(eq + 1));
+  }
+  return levels;
+}
+
+export function parseServerArgs(argv: string[]): ServerArgs {
+  const listen = flagValue(argv, '--listen');
+  const pipe = flagValue(argv, '--pipe');
+  const transport: Transport =
+    listen !== undefined
+      ? { kind: 'tcp', host: '127.0.0.1', port: parsePort(listen) }
+      : argv.includes('--tcp')
+        ? { kind: 'tcp', host: '127.0.0.1', port: DEFAULT_TCP_PORT }
+        : pipe !== undefined
+          ? { kind: 'pipe', name: pipe }
+          : argv.some((a) => a === '--node-ipc' || a.startsWith('--socket='))
+            ? { kind: 'library' }
+            : { kind: 'stdio' };
+  return {
+    transport,
+    version: argv.includes('--version') || argv.includes('-V'),
+    help: argv.includes('--help') || argv.includes('-h'),
+    logFile: flagValue(argv, '--log-file'),
+    logLevels: parseLogLevels(flagValue(argv, '--log-level')),
+  };
+}
@@ -0,0 +1,207 @@
+// The compiler's AST as the CLI serializes it (--ast-partial), typed after
+// src/ast.ts. Only what navigation reads is spelled out; types carried on
+// nodes stay opaque. `forEachNode` is the one traversal: it knows each
+// kind's children, so features only say what they look for.
+
+export type SourcePosition = { line: number; column: number };
+
+// 1-based, as the compiler counts; `end` is the last column's position
+export type SourceLocation = { start: SourcePosition; end: SourcePosition };
+
+type Node<K extends string, Fields = {}> = { kind: K; location: SourceLocation } & Fields;
+
+export type Expression =
+  | Node<'literal', { value: number | string | boolean | Expression[] | null }>
+  | Node<'variable', { name: string }>
+  | Node<'function', { params: string[]; body: Expression }>
+  | Node<'application', { func: Expression; args: Expression[] }>
+  | Node<'pipeline', { steps: Expression[]; operators: string[] }>
+  | Node<'binary', { operator: string; left: Expression; right: Expression }>
+  | Node<'if', { condition: Expression; then: Expression; else: Expression }>
+  | Node<'definition', { name: string; value: Expression }>
+  | Node<'mutable-definition', { name: string; value: Expression }>
+  | Node<'mutation', { target: string; value: Expression }>
+  | Node<'tuple-destructuring', { pattern: DestructuringPattern; value: Expression }>
+  | Node<'record-destructuring', { pattern: DestructuringPattern; value: Expression }>
+  | Node<'import', { path: string }>
+  | Node<'record', { fields: { name: string; value: Expression }[] }>
+  | Node<'tuple', { elements: Expression[] }>
+  | Node<'list', { elements: Expression[] }>
+  | Node<'unit'>
+  | Node<'accessor', { field: string; optional?: boolean }>
+  | Node<'ffi', { module: string; functionName: string }>
+  | Node<'typed', { expression: Expression }>
+  | Node<'constrained', { expression: Expression }>
+  | Node<'where', { main: Expression; definitions: Expression[] }>
+  | Node<'match', { expression: Expression; cases: MatchCase[] }>
+  | Node<'type-definition', { name: string; typeParams: string[]; constructors: ConstructorDefinition[] }>
+  // `definition` and `typeExpr` are the compiler's types (see fromCompilerType in types.ts)
+  | Node<'user-defined-type', { name: string; typeParams: string[]; definition: unknown }>
+  | Node<'constraint-definition', { name: string; typeParams: string[]; functions: NamedMember[] }>
+  | Node<'implement-definition', { constraintName: string; typeExpr: unknown; implementations: Implementation[] }>;
+
+export type ConstructorDefinition = { name: string; location: SourceLocation };
+export type NamedMember = { name: string; type?: unknown; location: SourceLocation };
+export type Implementation = { name: string; value: Expression; location: SourceLocation };
+
+export type MatchCase = { pattern: Pattern; expression: Expression; location: SourceLocation };
+
+export type Pattern =
+  | Node<'constructor', { name: string; args: Pattern[] }>
+  | Node<'variable', { name: string }>
+  | Node<'literal', { value: number | string | boolean }>
+  | Node<'wildcard'>
+  | Node<'tuple', { elements: Pattern[] }>
+  | Node<'record', { fields: { fieldName: string; pattern: Pattern; location: SourceLocation }[] }>;
+
+// `{a, b} = …` and `{@name} = …` bindings, which aren't match patterns
+export type DestructuringPattern =
+  | Node<'tuple-destructuring-pattern', { elements: DestructuringElement[] }>
+  | Node<'record-destructuring-pattern', { fields: DestructuringElement[] }>;
+
+export type DestructuringElement =
+  | Node<'variable', { name: string }>
+  | Node<'shorthand', { fieldName: string }>
+  | Node<'rename', { fieldName: string; localName: string }>
+  | Node<'nested-tuple', { fieldName?: string; pattern: DestructuringPattern }>
+  | Node<'nested-record', { fieldName?: string; pattern: DestructuringPattern }>;
+
+export type AstNode = Expression | Pattern | DestructuringPattern | DestructuringElement;
+
+export type AstProgram = { statements: Expression[]; errors: { message: string }[] };
+
+// Children are listed per kind rather than found by walking every property,
+// so type annotations (which have `variable` nodes of their own) and other
+// non-syntax data are never mistaken for code. Kinds shared by expressions
+// and patterns (tuple, record, literal, variable) are told apart by shape.
+function children(node: AstNode): AstNode[] {
+  switch (node.kind) {
+    case 'literal':
+      return Array.isArray(node.value) ? node.value : [];
+    case 'function':
+      return [node.body];
+    case 'application':
+      return [node.func, ...node.args];
+    case 'pipeline':
+      return node.steps;
+    case 'binary':
+      return [node.left, node.right];
+    case 'if':
+      return [node.condition, node.then, node.else];
+    case 'definition':
+    case 'mutable-definition':
+    case 'mutation':
+      return [node.value];
+    case 'tuple-destructuring':
+    case 'record-destructuring':
+      return [node.pattern, node.value];
+    case 'record':
+      // Record expressions have field values, record patterns field patterns
+      return (node.fields as { value?: Expression; pattern?: Pattern }[]).flatMap((f) => f.value ?? f.pattern ?? []);
+    case 'tuple':
+    case 'list':
+      return node.elements;
+    case 'typed':
+    case 'constrained':
+      return [node.expression];
+    case 'where':
+      return [node.main, ...node.definitions];
+    case 'match':
+      return [node.expression, ...node.cases.flatMap((c) => [c.pattern, c.expression])];
+    case 'implement-definition':
+      return node.implementations.map((i) => i.value);
+    case 'constructor':
+      return node.args;
+    case 'tuple-destructuring-pattern':
+      return node.elements;
+    case 'record-destructuring-pattern':
+      return node.fields;
+    case 'nested-tuple':
+    case 'nested-record':
+      return [node.pattern];
+    default:
+      return [];
+  }
+}
+
+// Pre-order over the nodes under `roots`, stopping at the first node `visit`
+// returns a value for. Visitors that only collect return undefined.
+export function forEachNode<T>(roots: AstNode[], visit: (node: AstNode) => T | undefined): T | undefined {
+  for (const node of roots) {
+    if (!node || typeof node !== 'object') continue;
+    const found = visit(node) ?? forEachNode(children(node), visit);
+    if (found !== undefined) return found;
+  }
+  return undefined;
+}
+
+// The nodes under `roots` that `match`, leaving out those nested inside
+// another match: a function's own definitions, not its definitions' locals
+export function outermost(roots: AstNode[], match: (node: AstNode) => boolean): AstNode[] {
+  return roots.flatMap((node) => {
+    if (!node || typeof node !== 'object') return [];
+    return match(node) ? [node] : outermost(children(node), match);
+  });
+}
+
+// The program's statements with `a; b` sequences taken apart: the parser
+// nests a file's top level as one chain of `;` nodes
+export function topLevelStatements(program: AstProgram): Expression[] {
+  const flatten = (node: Expression): Expression[] =>
+    node.kind === 'binary' && node.operator === ';' ? [...flatten(node.left), ...flatten(node.right)] : [node];
+  return program.statements.flatMap(flatten);
+}
+
+// Nodes that name something, with the name: definitions, variables
+// (references, match bindings and destructured names alike) and constructor
+// patterns. Constructors applied in expressions are variables.
+export function nodeName(node: AstNode): string | undefined {
+  if (node.kind === 'definition' || node.kind === 'mutable-definition' || node.kind === 'variable') {
+    return node.name;
+  }
+  if (node.kind === 'constructor') return node.name;
+  return undefined;
+}
+
+// Where a named node's name is. A constructor pattern's location covers its
+// arguments too, which have names of their own.
+export function nameLocation(node: AstNode): SourceLocation | undefined {
+  if (node.kind !== 'constructor' || !node.location) return node.location;
+  const { start } = node.location;
+  return { start, end: { line: start.line, column: start.column + node.name.length } };
+}
+
+export function containsPosition(location: SourceLocation | undefined, line: number, column: number): boolean {
+  if (!location) return false;
+  const { start, end } = location;
+  if (line < start.line || line > end.line) return false;
+  if (line === start.line && column < start.column) return false;
+  if (line === end.line && column > end.column) return false;
+  return true;
+}
+
+// What a node's line in a syntax tree says besides its kind: the name it
+// binds or uses, its operator, literal value or import path
+function nodeDetail(node: AstNode): string {
+  const n = node as Record<string, unknown>;
+  if (node.kind === 'function') return `fn ${node.params.join(' ')}`;
+  if (node.kind === 'literal' && !Array.isArray(node.value)) return JSON.stringify(node.value);
+  if (node.kind === 'rename') return `${node.fieldName} → ${node.localName}`;
+  const detail = [n.name, n.operator, n.path, n.field, n.target, n.constraintName, n.fieldName].find(
+    (d) => typeof d === 'string',
+  );
+  return typeof detail === 'string' ? detail : '';
+}
+
+const showLocation = ({ start, end }: SourceLocation) => `${start.line}:${start.column}–${end.line}:${end.column}`;
+
+// The nodes under `roots` as an indented outline, a line per node:
+// `binary +  1:5–1:10`, with its children below it
+export function syntaxTree(roots: AstNode[], depth = 0): string[] {
+  return roots.flatMap((node) => {
+    if (!node || typeof node !== 'object') return [];
+    const label = [node.kind, nodeDetail(node)].filter((part) => part !== '').join(' ');
+    const where = node.location ? `  ${showLocation(node.location)}` : '';
+    return [`${'  '.repeat(depth)}${label}${where}`, ...syntaxTree(children(node), depth + 1)];
+  });
+}
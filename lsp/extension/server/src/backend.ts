@@ -0,0 +1,117 @@
+// Where analysis requests run. The spawn backend starts a CLI process per
+// call; the in-process backend loads the compiler's query module (dist/
+// queries.js, built next to dist/cli.js) into the server itself, so type
+// checks need no separate runtime and no process startup. Both answer with
+// the CLI's output text, so callers parse results the same way either way.
+import { ChildProcess, spawn } from 'child_process';
+import * as fs from 'fs';
+import * as path from 'path';
+import { cliArgv } from './runtime';
+
+export type CliResult = {
+  status: number | null;
+  signal: NodeJS.Signals | null;
+  stdout: string;
+  stderr: string;
+  error?: Error;
+  timedOut: boolean;
+};
+
+export type BackendKind = 'spawn' | 'in-process';
+
+export type AnalysisBackend = {
+  kind: BackendKind;
+  run(cliArgs: string[], timeoutMs: number): Promise<CliResult>;
+  // Drops anything held from earlier calls (restartBridge)
+  reset(): void;
+  // At shutdown: ends the CLI processes still running, and refuses calls
+  // from then on
+  stop(): void;
+};
+
+// Runs the CLI without blocking the event loop, so requests on other
+// documents keep being served while a slow check is in flight. The backend
+// owns the processes it starts until they close, so none outlive the server.
+export function createSpawnBackend(runtime: string, cliPath: string): AnalysisBackend {
+  const running = new Set<ChildProcess>();
+  let stopped = false;
+  return {
+    kind: 'spawn',
+    run: (cliArgs, timeoutMs) =>
+      new Promise((resolve) => {
+        if (stopped) {
+          const error = new Error('the language server is shutting down');
+          resolve({ status: null, signal: null, stdout: '', stderr: '', error, timedOut: false });
+          return;
+        }
+        const child = spawn(runtime, cliArgv(runtime, cliPath, cliArgs));
+        running.add(child);
+        let stdout = '';
+        let stderr = '';
+        let error: Error | undefined;
+        let timedOut = false;
+        child.stdout.setEncoding('utf8').on('data', (chunk: string) => (stdout += chunk));
+        child.stderr.setEncoding('utf8').on('data', (chunk: string) => (stderr += chunk));
+        const timer = setTimeout(() => {
+          timedOut = true;
+          error = new Error(`timed out after ${timeoutMs}ms`);
+          child.kill('SIGKILL');
+        }, timeoutMs);
+        child.on('error', (e) => (error = e));
+        // 'close' also follows a failed spawn, so this always settles
+        child.on('close', (status, signal) => {
+          clearTimeout(timer);
+          running.delete(child);
+          resolve({ status, signal, stdout, stderr, error, timedOut });
+        });
+      }),
+    reset: () => {},
+    stop() {
+      stopped = true;
+      for (const child of running) child.kill('SIGKILL');
+    },
+  };
+}
+
+type QueryResult = { stdout: string; stderr: string; status: number };
+
+type QueryModule = { runQuery(args: string[]): QueryResult | undefined };
+
+// Only a built compiler can be loaded; live .ts sources need bun (spawned).
+export function queriesModulePath(cliPath: string): string | undefined {
+  if (path.extname(cliPath) !== '.js') return undefined;
+  const modulePath = path.join(path.dirname(cliPath), 'queries.js');
+  return fs.existsSync(modulePath) ? modulePath : undefined;
+}
+
+// Calls run synchronously on the server's thread: they can't be timed out
+// and hold up other requests while they run. Anything runQuery doesn't
+// answer (evaluation, which runs user code) still goes to `fallback`.
+export function createInProcessBackend(modulePath: string, fallback: AnalysisBackend): AnalysisBackend {
+  const distDir = path.dirname(modulePath);
+  let loaded: QueryModule | undefined;
+  return {
+    kind: 'in-process',
+    async run(cliArgs, timeoutMs) {
+      let result: QueryResult | undefined;
+      try {
+        loaded ??= require(modulePath) as QueryModule;
+        result = loaded.runQuery(cliArgs);
+      } catch (e) {
+        const error = e instanceof Error ? e : new Error(String(e));
+        return { status: null, signal: null, stdout: '', stderr: '', error, timedOut: false };
+      }
+      if (!result) return fallback.run(cliArgs, timeoutMs);
+      return { ...result, signal: null, timedOut: false };
+    },
+    // Unload the compiler so a rebuilt dist is picked up on the next call
+    reset() {
+      loaded = undefined;
+      for (const key of Object.keys(require.cache)) {
+        if (key.startsWith(distDir + path.sep)) delete require.cache[key];
+      }
+      fallback.reset();
+    },
+    stop: () => fallback.stop(),
+  };
+}
@@ -0,0 +1,48 @@
+// `noo --benchmark <file>` timings, read from its report and rendered as
+// markdown for noolang.benchmarkFile. The CLI prints (in gray):
+//
+//   Performance (246.3ms total, 5 lines, 78 chars):
+//     Read: 0.0ms
+//     Type:  165.7ms
+//     ...
+//     Slowest: Type (67.3%)
+
+export type BenchmarkPhase = { name: string; ms: number };
+
+export type BenchmarkResult = { totalMs: number; lines: number; chars: number; phases: BenchmarkPhase[] };
+
+const ANSI = /\x1b\[[0-9;]*m/g;
+const HEADER = /^Performance \(([\d.]+)ms total, (\d+) lines, (\d+) chars\):$/;
+const PHASE = /^(\w+):\s+([\d.]+)ms$/;
+
+export function parseBenchmark(stdout: string): BenchmarkResult | undefined {
+  const lines = stdout.replace(ANSI, '').split(/\r?\n/).map((l) => l.trim());
+  const start = lines.findIndex((l) => HEADER.test(l));
+  if (start < 0) return undefined;
+  const [, total, lineCount, chars] = HEADER.exec(lines[start]) ?? [];
+  const phases: BenchmarkPhase[] = [];
+  for (const line of lines.slice(start + 1)) {
+    const match = PHASE.exec(line);
+    if (!match) break;
+    phases.push({ name: match[1], ms: Number(match[2]) });
+  }
+  return { totalMs: Number(total), lines: Number(lineCount), chars: Number(chars), phases };
+}
+
+export function benchmarkReport(fileName: string, result: BenchmarkResult): string {
+  const share = (ms: number) => (result.totalMs > 0 ? `${((ms / result.totalMs) * 100).toFixed(1)}%` : '–');
+  const slowest = result.phases.reduce<BenchmarkPhase | undefined>((a, b) => (a && a.ms >= b.ms ? a : b), undefined);
+  return [
+    `# Benchmark: ${fileName}`,
+    '',
+    `${result.lines} lines, ${result.chars} chars — **${result.totalMs.toFixed(1)} ms** total`,
+    '',
+    '| Phase | Time (ms) | Share |',
+    '| --- | ---: | ---: |',
+    ...result.phases.map((p) => `| ${p.name} | ${p.ms.toFixed(1)} | ${share(p.ms)} |`),
+    '',
+    ...(slowest ? [`Slowest phase: **${slowest.name}**`, ''] : []),
+    `*Run ${new Date().toISOString()}. The file was evaluated, so its effects ran.*`,
+    '',
+  ].join('\n');
+}
@@ -0,0 +1,147 @@
+// What the server knows about builtins and the standard library without
+// asking the CLI: each one's type, as the CLI prints it with its parameters
+// curried, a line of documentation and an example. Completion shows these
+// as detail, hover adds the documentation to the type, and signature help
+// reads the parameters from the type. Types match src/typer/builtins.ts and
+// stdlib.noo (the tests check each against the compiler); the CLI's answer
+// still wins wherever it has one.
+import { DEFAULT_RENDER, NoolangType, parseType, renderType } from './types';
+
+export type BuiltinInfo = { signature: string; doc: string; example?: string };
+
+export const BUILTINS: Record<string, BuiltinInfo> = {
+  // Lists
+  head: {
+    signature: 'List a -> Option a',
+    doc: 'The first element, or `None` for an empty list.',
+    example: 'head [1, 2, 3] # Some 1',
+  },
+  tail: { signature: 'List a -> List a', doc: 'Every element but the first.', example: 'tail [1, 2, 3] # [2, 3]' },
+  map: {
+    signature: '(a -> b) -> c a -> c b given c implements Functor',
+    doc: 'Applies a function inside a functor: to each element of a list, to the value of a `Some` or `Ok`.',
+    example: 'map (fn x => x * 2) [1, 2, 3] # [2, 4, 6]',
+  },
+  filter: {
+    signature: '(a -> Bool) -> List a -> List a',
+    doc: 'The elements the predicate holds for, in order.',
+    example: 'filter (fn x => x > 1) [1, 2, 3] # [2, 3]',
+  },
+  reduce: {
+    signature: '(a -> b -> a) -> a -> List b -> a',
+    doc: 'Folds a list from the left, starting from the initial value.',
+    example: 'reduce (fn acc x => acc + x) 0 [1, 2, 3] # 6',
+  },
+  length: { signature: 'List a -> Float', doc: 'The number of elements.', example: 'length [1, 2, 3] # 3' },
+  isEmpty: { signature: 'List a -> Bool', doc: 'Whether the list has no elements.' },
+  append: {
+    signature: 'List a -> List a -> List a',
+    doc: 'The first list followed by the second.',
+    example: 'append [1] [2, 3] # [1, 2, 3]',
+  },
+  cons: {
+    signature: 'a -> List a -> List a',
+    doc: 'The list with an element put in front.',
+    example: 'cons 1 [2, 3] # [1, 2, 3]',
+  },
+  at: {
+    signature: 'Float -> List a -> Option a',
+    doc: 'The element at a zero-based index, or `None` past the end.',
+    example: 'at 1 [10, 20] # Some 20',
+  },
+  sort: { signature: 'List a -> List a', doc: 'The list in ascending order.' },
+  sort_by: { signature: '(a -> a -> Bool) -> List a -> List a', doc: 'The list ordered by a less-than function.' },
+  join: {
+    signature: 'String -> List String -> String',
+    doc: 'The strings joined with a separator between each.',
+    example: 'join ", " ["a", "b"] # "a, b"',
+  },
+  list_any: { signature: '(a -> Bool) -> List a -> Bool', doc: 'Whether the predicate holds for any element.' },
+  list_find: { signature: '(a -> Bool) -> List a -> Option a', doc: 'The first element the predicate holds for.' },
+  // Strings
+  toString: { signature: 'a -> String', doc: 'Any value as a string.', example: 'toString 42 # "42"' },
+  concat: { signature: 'String -> String -> String', doc: 'Two strings joined.', example: 'concat "ab" "cd" # "abcd"' },
+  split: {
+    signature: 'String -> String -> List String',
+    doc: 'The parts of the second string between separators (the first).',
+    example: 'split "," "a,b" # ["a", "b"]',
+  },
+  chars: { signature: 'String -> List String', doc: 'The characters of a string, each a string.' },
+  trim: { signature: 'String -> String', doc: 'The string without leading or trailing whitespace.' },
+  toUpper: { signature: 'String -> String', doc: 'The string in upper case.' },
+  toLower: { signature: 'String -> String', doc: 'The string in lower case.' },
+  indexOf: { signature: 'String -> String -> Option Float', doc: 'Where the first string first occurs in the second.' },
+  startsWith: { signature: 'String -> String -> Bool', doc: 'Whether the second string starts with the first.' },
+  endsWith: { signature: 'String -> String -> Bool', doc: 'Whether the second string ends with the first.' },
+  replace: {
+    signature: 'String -> String -> String -> String',
+    doc: 'The third string with every occurrence of the first replaced by the second.',
+  },
+  substring: {
+    signature: 'Float -> Float -> String -> String',
+    doc: 'The characters from the start index up to, not including, the end index.',
+  },
+  // Numbers
+  abs: { signature: 'Float -> Float', doc: 'The absolute value.' },
+  max: { signature: 'Float -> Float -> Float', doc: 'The larger of two numbers.' },
+  min: { signature: 'Float -> Float -> Float', doc: 'The smaller of two numbers.' },
+  random: { signature: 'Float', doc: 'A random whole number.' },
+  randomRange: {
+    signature: 'Float -> Float -> Float !rand',
+    doc: 'A random whole number from the first bound to the second.',
+  },
+  // Functions and values
+  id: { signature: 'a -> a', doc: 'Its argument, unchanged.' },
+  const: { signature: 'a -> b -> a', doc: 'A function that ignores its argument and returns the first value.' },
+  not: { signature: 'Bool -> Bool', doc: 'Logical negation.' },
+  show: {
+    signature: 'a -> String given a implements Show',
+    doc: 'A value as a string, through its `Show` implementation.',
+  },
+  equals: { signature: 'a -> a -> Bool given a implements Eq', doc: 'Equality through the `Eq` implementation.' },
+  option_get_or: {
+    signature: 'a -> Option a -> a',
+    doc: "The option's value, or the default for `None`.",
+    example: 'option_get_or 0 (Some 5) # 5',
+  },
+  result_get_or: { signature: 'a -> Result a b -> a', doc: "The result's value, or the default for an `Err`." },
+  // Effects
+  print: { signature: 'a -> {} !write', doc: 'Prints a value.', example: 'print "hello"' },
+  println: { signature: 'a -> {} !write', doc: 'Prints a value and a newline.' },
+  log: { signature: 'String -> {} !log', doc: 'Writes a message to the log.' },
+  readFile: {
+    signature: 'String -> Result String ReadError !read',
+    doc: "A file's contents, or why it couldn't be read.",
+  },
+  writeFile: {
+    signature: 'String -> String -> Result {} WriteError !write',
+    doc: 'Writes the second string to the file named by the first.',
+  },
+  argv: { signature: 'List String', doc: 'The command-line arguments of the program.' },
+  exit: { signature: 'Float -> {} !ffi', doc: 'Ends the program with an exit code.' },
+};
+
+// Not anything inherited from Object.prototype
+export const builtinNamed = (name: string): BuiltinInfo | undefined =>
+  Object.prototype.hasOwnProperty.call(BUILTINS, name) ? BUILTINS[name] : undefined;
+
+// The parameters of a builtin's curried type and what's left after them
+export type BuiltinParameters = { parameters: string[]; result: string; constraints?: string };
+
+export function builtinParameters(signature: string): BuiltinParameters | undefined {
+  const parsed = parseType(signature);
+  if (!parsed) return undefined;
+  const given = signature.indexOf(' given ');
+  let type: NoolangType = parsed.kind === 'constrained' ? parsed.type : parsed;
+  const options = { ...DEFAULT_RENDER, arrow: '->', maxWidth: Infinity };
+  const parameters: string[] = [];
+  let effects: string[] = [];
+  while (type.kind === 'function') {
+    const text = renderType(type.param, options);
+    parameters.push(type.param.kind === 'function' ? `(${text})` : text);
+    effects = type.effects;
+    type = type.result;
+  }
+  const result = [renderType(type, options), ...effects.map((e) => `!${e}`)].join(' ');
+  return { parameters, result, constraints: given >= 0 ? signature.slice(given + 1) : undefined };
+}
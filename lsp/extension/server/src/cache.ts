@@ -0,0 +1,91 @@
+// Bounded LRU cache for analysis results. Long sessions touch many files, so
+// every cache is capped by entry count and by approximate size in bytes; the
+// least recently used entries go first when either limit is exceeded.
+
+export type CacheLimits = { maxEntries: number; maxBytes: number };
+
+export type CacheStats = {
+  entries: number;
+  bytes: number;
+  hits: number;
+  misses: number;
+  evictions: number;
+  hitRate: number;
+};
+
+export type LruCache<V> = {
+  get(key: string): V | undefined;
+  // `bytes` is the caller's estimate of the value's size
+  set(key: string, value: V, bytes: number): void;
+  clear(): void;
+  setLimits(limits: CacheLimits): void;
+  stats(): CacheStats;
+  // Each entry's key and size, least recently used first
+  sizes(): { key: string; bytes: number }[];
+};
+
+export function createLruCache<V>(initial: CacheLimits): LruCache<V> {
+  // Map iteration order is insertion order, so re-inserting on access keeps
+  // the least recently used entry first.
+  const entries = new Map<string, { value: V; bytes: number }>();
+  let limits = initial;
+  let bytes = 0;
+  let hits = 0;
+  let misses = 0;
+  let evictions = 0;
+
+  function remove(key: string) {
+    const entry = entries.get(key);
+    if (!entry) return;
+    entries.delete(key);
+    bytes -= entry.bytes;
+  }
+
+  function evict() {
+    while (entries.size > limits.maxEntries || bytes > limits.maxBytes) {
+      const oldest = entries.keys().next();
+      if (oldest.done) break;
+      remove(oldest.value);
+      evictions++;
+    }
+  }
+
+  return {
+    get(key) {
+      const entry = entries.get(key);
+      if (!entry) {
+        misses++;
+        return undefined;
+      }
+      hits++;
+      entries.delete(key);
+      entries.set(key, entry);
+      return entry.value;
+    },
+    set(key, value, size) {
+      remove(key);
+      // A value bigger than the whole budget would only flush everything else
+      if (size > limits.maxBytes) return;
+      entries.set(key, { value, bytes: size });
+      bytes += size;
+      evict();
+    },
+    clear() {
+      entries.clear();
+      bytes = 0;
+    },
+    setLimits(next) {
+      limits = next;
+      evict();
+    },
+    stats: () => ({
+      entries: entries.size,
+      bytes,
+      hits,
+      misses,
+      evictions,
+      hitRate: hits + misses > 0 ? hits / (hits + misses) : 0,
+    }),
+    sizes: () => [...entries].map(([key, entry]) => ({ key, bytes: entry.bytes })),
+  };
+}
@@ -0,0 +1,57 @@
+// What the CLI on the other end of the bridge can do, from `--capabilities`
+// at startup. CLIs older than that flag fail the probe and are assumed to
+// answer only the flags the first bridge used. Features whose flag is missing
+// fall back to an older flag where there is one, and are otherwise skipped
+// with a single warning instead of failing on every request. CLIs that
+// report their lexer's keywords and operators have the server lex with them.
+import { Lexicon } from './syntax';
+
+export type CliCapabilities = { version: string | null; flags: Set<string>; lexicon?: Lexicon };
+
+export const LEGACY_FLAGS = ['--types', '--types-file', '--ast-file', '--symbol-type'];
+
+// Flags the server calls, and what the user loses without each
+export const BRIDGE_FEATURES: { flag: string; feature: string; fallback?: string }[] = [
+  { flag: '--types-file', feature: 'diagnostics' },
+  { flag: '--types', feature: 'hover on literals' },
+  { flag: '--ast-partial', feature: 'navigation in files with syntax errors', fallback: '--ast-file' },
+  { flag: '--symbol-types', feature: 'batched symbol types', fallback: '--symbol-type' },
+  { flag: '--expr-type', feature: 'expression types' },
+  { flag: '--eval-expr', feature: 'evaluation' },
+  { flag: '--test-file', feature: 'test lenses' },
+  { flag: '--type-at', feature: 'instantiated types in hover' },
+  { flag: '--pipeline-types', feature: 'pipeline type hints' },
+  { flag: '--holes', feature: 'typed hole hover' },
+  { flag: '--expected-type', feature: 'completion by expected type' },
+  { flag: '--pattern-type', feature: 'constructor completion in patterns' },
+  { flag: '--import-base', feature: 'relative imports in notebooks' },
+];
+
+export function parseCapabilities(stdout: string): CliCapabilities | undefined {
+  const lines = stdout.split(/\r?\n/);
+  const start = lines.findIndex((l) => l.trim() === 'Capabilities:');
+  if (start < 0) return undefined;
+  try {
+    const raw = JSON.parse(lines[start + 1]);
+    if (!Array.isArray(raw.flags)) return undefined;
+    const strings = (list: unknown) =>
+      Array.isArray(list) && list.every((s) => typeof s === 'string') ? (list as string[]) : undefined;
+    const [keywords, operators] = [strings(raw.keywords), strings(raw.operators)];
+    return {
+      version: typeof raw.version === 'string' ? raw.version : null,
+      flags: new Set(raw.flags.filter((f: unknown): f is string => typeof f === 'string')),
+      ...(keywords && operators && { lexicon: { keywords, operators } }),
+    };
+  } catch {
+    return undefined;
+  }
+}
+
+export const legacyCapabilities = (): CliCapabilities => ({ version: null, flags: new Set(LEGACY_FLAGS) });
+
+// Features lost outright; those with a fallback still work, just less well
+export function missingFeatures(capabilities: CliCapabilities): string[] {
+  return BRIDGE_FEATURES.filter(
+    ({ flag, fallback }) => !capabilities.flags.has(flag) && !(fallback && capabilities.flags.has(fallback)),
+  ).map(({ feature }) => feature);
+}
@@ -0,0 +1,100 @@
+// "Destructure into fields": a top-level binding of a record, `p =
+// getPoint ()`, becomes `{@x, @y} = getPoint ()`, and its uses follow:
+// `@x p` and `p | @x` become `x`, and where the whole value is still wanted
+// it's rebuilt, `{@x x, @y y}`. A tuple's elements are named after the
+// binding, `{p1, p2}`. A field whose name the file already uses anywhere is
+// bound as `pX` instead. Not offered when anything else in the file binds
+// the same name, or the binding refers to itself.
+import { AstNode, Expression, forEachNode, SourcePosition } from './ast';
+import { lexSyntax, offsetAt } from './syntax';
+import { NoolangType } from './types';
+import { Span } from './workspace';
+
+export type Destructuring = { title: string; edits: { span: Span; text: string }[] };
+
+type Definition = Extract<Expression, { kind: 'definition' }>;
+
+const capitalized = (name: string) => name.charAt(0).toUpperCase() + name.slice(1);
+
+// Whether a node other than `definition` binds `name`: another definition,
+// a parameter, a destructured name or a variable in a match pattern
+function rebinds(statements: Expression[], definition: Definition, name: string): boolean {
+  const patternBinds = (roots: AstNode[]) =>
+    forEachNode(roots, (n) => (n.kind === 'variable' && n.name === name ? true : undefined)) ?? false;
+  return (
+    forEachNode(statements, (node) => {
+      if (node === definition) return undefined;
+      if ((node.kind === 'definition' || node.kind === 'mutable-definition') && node.name === name) return true;
+      if (node.kind === 'function' && node.params.includes(name)) return true;
+      if (node.kind === 'rename' && node.localName === name) return true;
+      if (node.kind === 'tuple-destructuring' || node.kind === 'record-destructuring') {
+        return patternBinds([node.pattern]) || undefined;
+      }
+      if (node.kind === 'match') return patternBinds(node.cases.map((c) => c.pattern)) || undefined;
+      return undefined;
+    }) ?? false
+  );
+}
+
+export function destructureBinding(
+  source: string,
+  statements: Expression[],
+  definition: Definition,
+  type: NoolangType,
+): Destructuring | undefined {
+  const name = definition.name;
+  const offset = (p: SourcePosition) => offsetAt(source, { line: p.line - 1, character: p.column - 1 });
+  const spanOf = (node: AstNode): Span => ({ start: offset(node.location.start), end: offset(node.location.end) });
+  if (type.kind !== 'record' && type.kind !== 'tuple') return undefined;
+  if (rebinds(statements, definition, name)) return undefined;
+  const isUse = (n: AstNode | undefined) => n?.kind === 'variable' && n.name === name;
+  if (forEachNode([definition.value], (n) => (isUse(n) ? true : undefined))) return undefined;
+
+  // Every use, with the field it reads when it's an accessor applied to it
+  const uses: { span: Span; field?: string }[] = [];
+  const accessed = new Set<AstNode>();
+  forEachNode(statements, (node) => {
+    if (node === definition) return undefined;
+    if (node.kind === 'application' && node.func.kind === 'accessor' && !node.func.optional) {
+      const [arg] = node.args;
+      if (node.args.length === 1 && isUse(arg)) {
+        uses.push({ span: { start: spanOf(node.func).start, end: spanOf(arg).end }, field: node.func.field });
+        accessed.add(arg);
+      }
+    }
+    if (node.kind === 'binary' && node.operator === '|' && isUse(node.left) && node.right.kind === 'accessor') {
+      if (!node.right.optional) {
+        uses.push({ span: { start: spanOf(node.left).start, end: spanOf(node.right).end }, field: node.right.field });
+        accessed.add(node.left);
+      }
+    }
+    if (isUse(node) && !accessed.has(node)) uses.push({ span: spanOf(node) });
+    return undefined;
+  });
+
+  const taken = new Set(lexSyntax(source).flatMap((t) => (t.kind === 'identifier' ? [t.text] : [])));
+  const localFor = (field: string) => (taken.has(field) ? `${name}${capitalized(field)}` : field);
+  const rebuilt = uses.some((u) => u.field === undefined) || uses.length === 0;
+  let pattern: string;
+  let whole: string;
+  let locals: string[];
+  if (type.kind === 'record') {
+    const read = new Set(uses.flatMap((u) => u.field ?? []));
+    const fields = type.fields.map((f) => f.name).filter((f) => rebuilt || read.has(f));
+    locals = fields.map(localFor);
+    pattern = `{${fields.map((f, i) => (locals[i] === f ? `@${f}` : `@${f} ${locals[i]}`)).join(', ')}}`;
+    whole = `{${fields.map((f, i) => `@${f} ${locals[i]}`).join(', ')}}`;
+  } else {
+    if (uses.some((u) => u.field !== undefined)) return undefined;
+    locals = type.elements.map((_, i) => `${name}${i + 1}`);
+    pattern = `{${locals.join(', ')}}`;
+    whole = pattern;
+  }
+  if (locals.some((local) => taken.has(local)) || new Set(locals).size !== locals.length) return undefined;
+  const nameSpan = { start: offset(definition.location.start), end: offset(definition.location.start) + name.length };
+  const edits = [
+    { span: nameSpan, text: pattern },
+    ...uses.map((u) => ({ span: u.span, text: u.field !== undefined ? localFor(u.field) : whole })),
+  ];
+  return { title: type.kind === 'record' ? 'Destructure into fields' : 'Destructure into elements', edits };
+}
@@ -0,0 +1,85 @@
+// Per-document state. Every open document has its own entry and its own
+// read/write lock, so work on one file never waits on another: edits take
+// the write lock, analysis takes the read lock and may overlap other reads.
+
+export type RwLock = {
+  read<T>(fn: () => T | Promise<T>): Promise<T>;
+  write<T>(fn: () => T | Promise<T>): Promise<T>;
+};
+
+type Waiter = { write: boolean; wake: () => void };
+
+// Waiters are admitted in arrival order, so a pending edit holds back reads
+// that arrive after it and analysis always sees the newest text.
+export function createRwLock(): RwLock {
+  let readers = 0;
+  let writing = false;
+  const queue: Waiter[] = [];
+
+  const canEnter = (write: boolean) => !writing && (!write || readers === 0);
+
+  function enter(write: boolean) {
+    if (write) writing = true;
+    else readers++;
+  }
+
+  function release(write: boolean) {
+    if (write) writing = false;
+    else readers--;
+    while (queue.length > 0 && canEnter(queue[0].write)) {
+      const next = queue.shift()!;
+      enter(next.write);
+      next.wake();
+    }
+  }
+
+  function acquire(write: boolean): Promise<void> {
+    if (queue.length === 0 && canEnter(write)) {
+      enter(write);
+      return Promise.resolve();
+    }
+    return new Promise((wake) => queue.push({ write, wake }));
+  }
+
+  async function locked<T>(write: boolean, fn: () => T | Promise<T>): Promise<T> {
+    await acquire(write);
+    try {
+      return await fn();
+    } finally {
+      release(write);
+    }
+  }
+
+  return {
+    read: (fn) => locked(false, fn),
+    write: (fn) => locked(true, fn),
+  };
+}
+
+export type DocumentEntry = { uri: string; text: string; version: number; lock: RwLock };
+
+export function createDocumentStore() {
+  const entries = new Map<string, DocumentEntry>();
+  return {
+    get: (uri: string) => entries.get(uri),
+    open(uri: string, text: string, version: number): DocumentEntry {
+      const entry = { uri, text, version, lock: createRwLock() };
+      entries.set(uri, entry);
+      return entry;
+    },
+    // Waits for in-flight reads of this document, then swaps the text in.
+    update(entry: DocumentEntry, text: string, version: number): Promise<void> {
+      return entry.lock.write(() => {
+        entry.text = text;
+        entry.version = version;
+      });
+    },
+    close: (uri: string) => entries.delete(uri),
+    all: () => [...entries.values()],
+    get size() {
+      return entries.size;
+    },
+  };
+}
+
+export type DocumentStore = ReturnType<typeof createDocumentStore>;
@@ -0,0 +1,63 @@
+// Effect violations, pinned to the calls that cause them. The type checker
+// rejects an annotation that omits an effect its expression performs
+// ("Type annotation omits effect !write performed by the expression") and
+// locates the error at the start of that expression; the calls performing
+// the omitted effects, and the annotation itself, are found in its tokens.
+import { CLOSERS, isPunctuation, isTrivia, lexSyntax, OPENERS, SyntaxToken } from './syntax';
+import { callEffects } from './types';
+import { Span } from './workspace';
+
+export type EffectfulCall = Span & { name: string; effects: string[] };
+
+// The effects an error message says were left undeclared, if it's one of these
+export function omittedEffects(message: string): string[] | undefined {
+  const m = /Type annotation omits effects? ((?:!\w+ ?)+) performed by the expression/.exec(message);
+  return m ? m[1].trim().split(/\s+/).map((e) => e.slice(1)) : undefined;
+}
+
+// The annotated expression starting at `offset` and its `: Type`, which runs
+// to the `;` or closing bracket that ends the expression
+export function annotatedExpression(
+  source: string,
+  offset: number,
+): { expression: Span; annotation: Span; tokens: SyntaxToken[] } | undefined {
+  const tokens = lexSyntax(source).filter((t) => !isTrivia(t) && t.end > offset);
+  let depth = 0;
+  let colon: number | undefined;
+  let end = tokens.length;
+  for (let i = 0; i < tokens.length; i++) {
+    const t = tokens[i];
+    if (isPunctuation(t, OPENERS)) depth++;
+    else if (isPunctuation(t, CLOSERS) && depth-- === 0) end = i;
+    else if (depth === 0 && isPunctuation(t, ';')) end = i;
+    else if (depth === 0 && colon === undefined && isPunctuation(t, ':')) colon = i;
+    if (end === i) break;
+  }
+  if (colon === undefined || colon === 0 || colon + 1 >= end) return undefined;
+  return {
+    expression: { start: tokens[0].start, end: tokens[colon - 1].end },
+    annotation: { start: tokens[colon + 1].start, end: tokens[end - 1].end },
+    tokens: tokens.slice(0, colon),
+  };
+}
+
+// References in the expression to functions performing any of `effects`,
+// going by `types` (names to the type strings the CLI prints). Parameters
+// are skipped: their types are the expression's own.
+export function effectfulCalls(
+  tokens: SyntaxToken[],
+  effects: string[],
+  types: Record<string, string | null>,
+): EffectfulCall[] {
+  const calls: EffectfulCall[] = [];
+  let inParams = false;
+  for (const t of tokens) {
+    if (t.kind === 'keyword' && t.text === 'fn') inParams = true;
+    if (t.kind === 'operator' && t.text === '=>') inParams = false;
+    if (t.kind !== 'identifier' || inParams) continue;
+    const type = types[t.text];
+    const performed = type ? callEffects(type).filter((e) => effects.includes(e)) : [];
+    if (performed.length > 0) calls.push({ name: t.text, start: t.start, end: t.end, effects: performed });
+  }
+  return calls;
+}
@@ -0,0 +1,29 @@
+// Startup checks for what the bridge needs: a JS runtime that runs, a recent
+// enough node, and a CLI entry point that exists. Each problem comes back as
+// a message the user can act on, rather than every feature quietly
+// returning nothing.
+
+export const MIN_NODE_MAJOR = 20;
+
+export type EnvironmentProbe = {
+  // `<runtime> --version` output, or undefined if the runtime can't be run
+  runtimeVersion(runtime: string): string | undefined;
+  exists(path: string): boolean;
+};
+
+export function checkEnvironment(runtime: string, cliPath: string, probe: EnvironmentProbe): string[] {
+  const problems: string[] = [];
+  const version = probe.runtimeVersion(runtime);
+  if (version === undefined) {
+    problems.push(`Cannot run '${runtime}' to start the Noolang CLI; install it or make sure it is on PATH.`);
+  } else if (/(^|[\\/])node(\.exe)?$/.test(runtime)) {
+    const major = Number(/^v?(\d+)/.exec(version.trim())?.[1]);
+    if (!(major >= MIN_NODE_MAJOR)) {
+      problems.push(`Noolang needs node ${MIN_NODE_MAJOR} or newer, found ${version.trim()}.`);
+    }
+  }
+  if (!cliPath || !probe.exists(cliPath)) {
+    problems.push(`Noolang CLI not found at ${cliPath || '(unset)'}; set noolang.cliPath.`);
+  }
+  return problems;
+}
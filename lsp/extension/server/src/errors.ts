@@ -0,0 +1,45 @@
+// How requests fail. Analysis throws an LspError, carrying what it was doing
+// when it failed, and the request handler answers with the JSON-RPC error its
+// kind maps to. Outcomes that are normal for code being edited (a file that
+// doesn't parse, a name without a type) aren't errors: they're empty answers.
+import { ErrorCodes, LSPErrorCodes, ResponseError } from 'vscode-languageserver/node';
+
+export type LspErrorKind =
+  // A URI that isn't a file, a position or argument the request can't use
+  | 'invalid-params'
+  // The CLI doesn't have the query the request needs
+  | 'unsupported'
+  // The CLI couldn't be run, was killed, or is being held off after crashes
+  | 'bridge'
+  | 'timeout'
+  // The CLI answered, but not in the shape responses.ts expects
+  | 'unreadable-output';
+
+const CODES: Record<LspErrorKind, number> = {
+  'invalid-params': ErrorCodes.InvalidParams,
+  unsupported: LSPErrorCodes.RequestFailed,
+  bridge: LSPErrorCodes.RequestFailed,
+  timeout: LSPErrorCodes.RequestFailed,
+  'unreadable-output': ErrorCodes.InternalError,
+};
+
+export class LspError extends Error {
+  readonly kind: LspErrorKind;
+  // What was being done, e.g. "--ast-partial main.noo"
+  readonly context?: string;
+
+  constructor(kind: LspErrorKind, message: string, context?: string) {
+    super(context ? `${context}: ${message}` : message);
+    this.name = 'LspError';
+    this.kind = kind;
+    this.context = context;
+  }
+
+  get code(): number {
+    return CODES[this.kind];
+  }
+
+  toResponseError(): ResponseError<void> {
+    return new ResponseError(this.code, this.message);
+  }
+}
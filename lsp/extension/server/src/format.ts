@@ -0,0 +1,44 @@
+// The formatter behind textDocument/formatting and the noofmt binary, so the
+// editor and the command line can't disagree. It only rewrites whitespace
+// between tokens of the lossless lexer (syntax.ts), never the tokens, so a
+// formatted file parses to the same program. Indentation is left as written.
+import { isPunctuation, lexSyntax, SyntaxError, SyntaxToken } from './syntax';
+
+// The whitespace between two tokens on one line
+function inlineSpace(prev: SyntaxToken, next: SyntaxToken, written: string): string {
+  // Comments keep their alignment
+  if (next.kind === 'comment') return written;
+  if (isPunctuation(next, ',;') || isPunctuation(prev, '([') || isPunctuation(next, ')]')) return '';
+  return ' ';
+}
+
+// Undefined when the source doesn't lex (an unterminated string): where its
+// tokens end would be a guess.
+export function formatSource(source: string): string | undefined {
+  const errors: SyntaxError[] = [];
+  const tokens = lexSyntax(source, errors);
+  if (errors.length > 0) return undefined;
+  const eol = source.includes('\r\n') ? '\r\n' : '\n';
+  let out = '';
+  tokens.forEach((token, i) => {
+    const prev = tokens[i - 1];
+    const next = tokens[i + 1];
+    if (token.kind !== 'whitespace') {
+      out += token.kind === 'comment' ? token.text.trimEnd() : token.text;
+      // `a,b` and `a;b` get their space; whitespace tokens handle the rest
+      if (isPunctuation(token, ',;') && next && next.kind !== 'whitespace' && !isPunctuation(next, ',;)]}')) {
+        out += ' ';
+      }
+      return;
+    }
+    const newlines = token.text.split('\n').length - 1;
+    const indent = token.text.slice(token.text.lastIndexOf('\n') + 1);
+    // Leading blank lines go, the first line's indentation stays; trailing
+    // whitespace is replaced by a single newline below
+    if (!prev) out += indent;
+    else if (!next) return;
+    else if (newlines > 0) out += eol.repeat(Math.min(newlines, 2)) + indent;
+    else out += inlineSpace(prev, next, token.text);
+  });
+  return out ? out + eol : out;
+}
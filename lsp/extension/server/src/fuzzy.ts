@@ -0,0 +1,84 @@
+// Fuzzy matching of names for workspace symbols. A query matches a name when
+// its characters appear in it in order, ignoring case; matches at the start
+// of a word (the name's start, after `_`, an uppercase letter after a
+// lowercase one, a digit after a letter) and runs of consecutive matches
+// score higher, characters skipped over lower. So `gun` and `gUN` both find
+// `getUserName` and `get_user_name`, ahead of `begun`.
+import { OutlineKind } from './symbols';
+
+const WORD_START = 8;
+const CONSECUTIVE = 5;
+const EXACT = 100;
+const PREFIX = 20;
+
+// Declarations people search for first; members and implement blocks last
+const KIND_BONUS: Record<OutlineKind, number> = {
+  type: 3,
+  alias: 3,
+  constraint: 3,
+  function: 2,
+  value: 2,
+  constructor: 2,
+  method: 1,
+  field: 0,
+  implementation: 0,
+};
+
+function wordStarts(name: string): boolean[] {
+  return [...name].map((c, i) => {
+    const before = name[i - 1];
+    if (i === 0 || before === '_') return true;
+    if (/[A-Z]/.test(c) && /[a-z0-9]/.test(before)) return true;
+    return /[0-9]/.test(c) && /[A-Za-z]/.test(before);
+  });
+}
+
+// How well `query` matches `name`, higher is better; undefined when it doesn't
+export function fuzzyScore(query: string, name: string): number | undefined {
+  const q = query.toLowerCase();
+  const lower = name.toLowerCase();
+  if (q === '') return 0;
+  const starts = wordStarts(name);
+  // best[j]: the best score with the query so far matched, its last
+  // character at j
+  let best: (number | undefined)[] = [...lower].map((c, j) => {
+    if (c !== q[0]) return undefined;
+    return (starts[j] ? WORD_START : 1) - j;
+  });
+  for (let i = 1; i < q.length; i++) {
+    best = [...lower].map((c, j) => {
+      if (c !== q[i]) return undefined;
+      let score: number | undefined;
+      for (let k = 0; k < j; k++) {
+        const previous = best[k];
+        if (previous === undefined) continue;
+        const step = k === j - 1 ? CONSECUTIVE : -(j - k - 1);
+        score = Math.max(score ?? -Infinity, previous + step);
+      }
+      return score === undefined ? undefined : score + (starts[j] ? WORD_START : 1);
+    });
+  }
+  const found = best.filter((s): s is number => s !== undefined);
+  if (found.length === 0) return undefined;
+  const bonus = lower === q ? EXACT : lower.startsWith(q) ? PREFIX : 0;
+  return Math.max(...found) + bonus;
+}
+
+type Ranked<T> = { item: T; score: number };
+
+// The items matching the query, best first (shorter names first among
+// equals), at most `limit` of them
+export function rankSymbols<T extends { name: string; kind: OutlineKind }>(
+  query: string,
+  items: T[],
+  limit: number,
+): T[] {
+  const ranked: Ranked<T>[] = items.flatMap((item) => {
+    const score = fuzzyScore(query, item.name);
+    return score === undefined ? [] : [{ item, score: score + KIND_BONUS[item.kind] }];
+  });
+  ranked.sort(
+    (a, b) => b.score - a.score || a.item.name.length - b.item.name.length || a.item.name.localeCompare(b.item.name),
+  );
+  return ranked.slice(0, limit).map((r) => r.item);
+}
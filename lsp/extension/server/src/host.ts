@@ -0,0 +1,70 @@
+// Running the language server inside another program (a playground, an
+// editor embedding it, a test runner) instead of as its own process. The
+// host hands over the streams to talk on and what the command line and the
+// extension's environment would otherwise give: the CLI to analyse with,
+// settings, and where logs go. The server keeps its state in its module, so
+// a process hosts one server.
+//
+//   serverBuilder()
+//     .cli('/w/dist/cli.js', 'node')
+//     .settings({ timeouts: { hoverMs: 1000 } })
+//     .logger((record) => console.log(record.message))
+//     .serve(input, output);
+import { LogLevels } from './args';
+import { LogRecord } from './logger';
+import { Streams } from './transport';
+
+export type HostOptions = {
+  // The CLI and the runtime to run it with ('node', 'bun' or 'deno'), picked
+  // as for the extension when left out
+  cliPath?: string;
+  runtime?: string;
+  // The workspace the CLI is in, where its stdlib.noo is found
+  workspace?: string;
+  // As a client sends them (settings.ts); its initializationOptions still
+  // apply on top
+  settings?: unknown;
+  logLevels?: LogLevels;
+  // Each log record, instead of writing them to stderr
+  log?: (record: LogRecord) => void;
+};
+
+export type HostedServer = Streams & HostOptions;
+
+let hosted: HostedServer | undefined;
+
+export const hostedServer = (): HostedServer | undefined => hosted;
+
+// Starts the server on the streams. The connection library ends the process
+// when `input` ends or an `exit` notification arrives, as it would the
+// server's own, so a host that carries on keeps its input open and stops at
+// `shutdown`.
+export function serveWithIo(
+  input: NodeJS.ReadableStream,
+  output: NodeJS.WritableStream,
+  options: HostOptions = {},
+): void {
+  if (hosted) throw new Error('This process already hosts a Noolang language server');
+  hosted = { input, output, ...options };
+  require('./server');
+}
+
+export type ServerBuilder = {
+  cli(cliPath: string, runtime?: string): ServerBuilder;
+  workspace(workspace: string): ServerBuilder;
+  settings(settings: unknown): ServerBuilder;
+  logger(log: (record: LogRecord) => void, levels?: LogLevels): ServerBuilder;
+  serve(input: NodeJS.ReadableStream, output: NodeJS.WritableStream): void;
+};
+
+// serveWithIo's options a step at a time
+export function serverBuilder(options: HostOptions = {}): ServerBuilder {
+  const next = (more: HostOptions) => serverBuilder({ ...options, ...more });
+  return {
+    cli: (cliPath, runtime) => next({ cliPath, runtime }),
+    workspace: (workspace) => next({ workspace }),
+    settings: (settings) => next({ settings }),
+    logger: (log, logLevels) => next({ log, logLevels: logLevels ?? options.logLevels }),
+    serve: (input, output) => serveWithIo(input, output, options),
+  };
+}
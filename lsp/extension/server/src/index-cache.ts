@@ -0,0 +1,72 @@
+// The workspace index (workspace.ts) saved between sessions: written to a
+// cache directory on shutdown and read back on startup, one file per set of
+// workspace folders. Entries carry their file's stamp and content hash, so
+// the index only re-lexes files that changed in between; anything that
+// can't be read back (a missing or corrupt file, an older format) is an
+// empty cache. Only what was indexed is kept, never the text, and nothing
+// of an open document's unsaved buffer.
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import { createHash } from 'crypto';
+import { IndexedFile, SavedIndexEntry, Span } from './workspace';
+
+// Bumped when IndexedFile changes shape
+const FORMAT = 3;
+
+type SavedFile = Omit<SavedIndexEntry, 'identifiers' | 'fields' | 'shorthands'> & {
+  identifiers: [string, Span[]][];
+  fields: [string, Span[]][];
+  shorthands: string[];
+};
+
+// NOOLANG_INDEX_CACHE when set (the extension's workspace storage), the user
+// cache directory otherwise
+export function indexCacheDir(env: NodeJS.ProcessEnv): string {
+  if (env.NOOLANG_INDEX_CACHE) return env.NOOLANG_INDEX_CACHE;
+  return path.join(env.XDG_CACHE_HOME || path.join(os.homedir(), '.cache'), 'noolang');
+}
+
+export function indexCacheFile(dir: string, roots: string[]): string {
+  const key = createHash('sha1').update([...roots].sort().join('\n')).digest('hex').slice(0, 16);
+  return path.join(dir, `workspace-index-${key}.json`);
+}
+
+// Entries stamped `v<version>` were indexed from an open document's buffer
+// (server.ts), which may not match the file on disk
+export function saveIndex(file: string, entries: IndexedFile[]): void {
+  const saved: SavedFile[] = entries
+    .filter((f) => !f.stamp.startsWith('v'))
+    .map(({ filePath, stamp, hash, identifiers, fields, shorthands, imports, exports, declarations }) => ({
+      filePath,
+      stamp,
+      hash,
+      identifiers: [...identifiers],
+      fields: [...fields],
+      shorthands: [...shorthands],
+      imports,
+      exports,
+      declarations,
+    }));
+  fs.mkdirSync(path.dirname(file), { recursive: true });
+  // Written aside and renamed, so a crash mid-write leaves the old cache
+  const partial = `${file}.${process.pid}.tmp`;
+  fs.writeFileSync(partial, JSON.stringify({ format: FORMAT, files: saved }));
+  fs.renameSync(partial, file);
+}
+
+export function loadIndex(file: string): SavedIndexEntry[] {
+  let data: { format?: unknown; files?: unknown };
+  try {
+    data = JSON.parse(fs.readFileSync(file, 'utf8'));
+  } catch {
+    return [];
+  }
+  if (data?.format !== FORMAT || !Array.isArray(data.files)) return [];
+  return (data.files as SavedFile[]).map((f) => ({
+    ...f,
+    identifiers: new Map(f.identifiers),
+    fields: new Map(f.fields),
+    shorthands: new Set(f.shorthands),
+  }));
+}
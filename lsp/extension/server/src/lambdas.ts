@@ -0,0 +1,91 @@
+// Rewrites of the lambda at a position. Noolang spells a function of several
+// parameters one way, `fn x y => b`, which is sugar for the nested
+// `fn x => fn y => b`: either converts to the other. And eta-reduction,
+// `fn x => g x` to just `g`, when that can't change when effects happen: the
+// lambda's body must apply a name to plain values and its own parameters,
+// and what's left after dropping them must not call anything. Found with the
+// lexer, so a rewrite is exactly the text it replaces.
+import { CLOSERS, isPunctuation, isTrivia, lexSyntax, OPENERS, SyntaxToken } from './syntax';
+import { Span } from './workspace';
+
+// `binding` is the name it's defined as, for `name = fn …`
+export type Lambda = { span: Span; params: SyntaxToken[]; body: Span; binding?: string };
+
+// A replacement of the lambda's text
+export type LambdaRewrite = { title: string; span: Span; text: string };
+
+// The lambda starting at tokens[at] (an `fn`): its body runs to the `;`,
+// `,`, `:` or unmatched closer that ends it
+function lambdaFrom(tokens: SyntaxToken[], at: number): Lambda | undefined {
+  const arrow = tokens.findIndex((t, i) => i > at && t.text === '=>');
+  const params = tokens.slice(at + 1, arrow);
+  if (arrow < 0 || arrow + 1 >= tokens.length || !params.every((t) => t.kind === 'identifier')) return undefined;
+  let depth = 0;
+  let end = tokens[arrow + 1].end;
+  for (const t of tokens.slice(arrow + 1)) {
+    if (isPunctuation(t, OPENERS)) depth++;
+    if (isPunctuation(t, CLOSERS) && --depth < 0) break;
+    if (depth === 0 && (isPunctuation(t, ';,:') || (t.kind === 'keyword' && t.text === 'where'))) break;
+    end = t.end;
+  }
+  const [named, equals] = [tokens[at - 2], tokens[at - 1]];
+  const binding = named?.kind === 'identifier' && equals?.text === '=' ? named.text : undefined;
+  return { span: { start: tokens[at].start, end }, params, body: { start: tokens[arrow + 1].start, end }, binding };
+}
+
+// The innermost lambda whose text contains the offset
+export function lambdaAt(source: string, offset: number): Lambda | undefined {
+  const tokens = lexSyntax(source).filter((t) => !isTrivia(t));
+  let found: Lambda | undefined;
+  tokens.forEach((t, i) => {
+    if (t.kind !== 'keyword' || t.text !== 'fn' || t.start > offset) return;
+    const lambda = lambdaFrom(tokens, i);
+    if (lambda && offset <= lambda.span.end) found = lambda;
+  });
+  return found;
+}
+
+export function lambdaRewrites(
+  source: string,
+  lambda: Lambda,
+  arity: (name: string) => number | undefined,
+): LambdaRewrite[] {
+  const { span, params } = lambda;
+  const body = source.slice(lambda.body.start, lambda.body.end);
+  const rewrites: LambdaRewrite[] = [];
+  if (params.length > 1) {
+    const nested = params.map((p) => `fn ${p.text} => `).join('');
+    rewrites.push({ title: 'Convert to nested lambdas', span, text: `${nested}${body}` });
+  }
+  const inner = lambdaAt(source, lambda.body.start);
+  if (inner && inner.span.start === lambda.body.start && inner.span.end === span.end) {
+    const names = [...params, ...inner.params].map((p) => p.text).join(' ');
+    const text = `fn ${names} => ${source.slice(inner.body.start, inner.body.end)}`;
+    rewrites.push({ title: 'Merge nested lambdas', span, text });
+  }
+  const reduced = etaReduced(source, lambda, arity);
+  if (reduced !== undefined) rewrites.push({ title: `Eta-reduce to \`${reduced}\``, span, text: reduced });
+  return rewrites;
+}
+
+// `fn x y => g a x y` as `g a`: every parameter passed last, in order, and
+// used nowhere else; `g a` must still be short of `g`'s arity, so it only
+// builds a function rather than calling one. Not for a function that calls
+// itself, which would be left defined as itself.
+function etaReduced(
+  source: string,
+  lambda: Lambda,
+  arity: (name: string) => number | undefined,
+): string | undefined {
+  const body = lexSyntax(source.slice(lambda.body.start, lambda.body.end)).filter((t) => !isTrivia(t));
+  const plain = (t: SyntaxToken) => ['identifier', 'number', 'string', 'accessor'].includes(t.kind);
+  if (!body.every(plain) || body[0].kind !== 'identifier') return undefined;
+  const names = lambda.params.map((p) => p.text);
+  const head = body.slice(0, body.length - names.length);
+  const passed = body.slice(head.length).map((t) => t.text);
+  if (names.length === 0 || head.length === 0 || passed.join(' ') !== names.join(' ')) return undefined;
+  if (head.some((t) => names.includes(t.text) || t.text === lambda.binding)) return undefined;
+  const supplied = head.length - 1;
+  if (supplied > 0 && !(supplied < (arity(head[0].text) ?? 0))) return undefined;
+  return head.map((t) => t.text).join(' ');
+}
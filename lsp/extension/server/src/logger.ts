@@ -0,0 +1,73 @@
+// Structured server logging. Each record carries a timestamp, level, module
+// and message (plus optional fields). Records at or above a module's level go
+// to the --log-file as JSON lines, or to stderr without one (or to a hosting
+// program's `write`, see host.ts); warnings and
+// errors are also forwarded to the client as window/logMessage so they show
+// up in the editor's output channel instead of vanishing.
+import * as fs from 'fs';
+import { LOG_LEVELS, LogLevel, LogLevels } from './args';
+
+type ClientSink = {
+  error(message: string): void;
+  warn(message: string): void;
+};
+
+type Fields = Record<string, unknown>;
+
+export type LogRecord = {
+  time: string;
+  level: LogLevel;
+  module: string;
+  message: string;
+  fields?: Fields;
+};
+
+export type Logger = Record<LogLevel, (message: string, fields?: Fields) => void> & {
+  child(module: string): Logger;
+  enabled(level: LogLevel): boolean;
+};
+
+export function formatRecord(r: LogRecord): string {
+  const fields = r.fields ? ' ' + JSON.stringify(r.fields) : '';
+  return `[${r.module}] ${r.message}${fields}`;
+}
+
+export function createLogger(
+  levels: LogLevels,
+  client: ClientSink,
+  logFile?: string,
+  write?: (record: LogRecord) => void,
+): Logger {
+  const emit = (record: LogRecord) => {
+    if (record.level === 'error') client.error(formatRecord(record));
+    else if (record.level === 'warn') client.warn(formatRecord(record));
+    if (write) return write(record);
+    const line = logFile
+      ? JSON.stringify(record)
+      : `${record.time} ${record.level.toUpperCase()} ${formatRecord(record)}`;
+    try {
+      if (logFile) fs.appendFileSync(logFile, line + '\n');
+      else process.stderr.write(line + '\n');
+    } catch {
+      // A bad log path shouldn't take the server down with it
+    }
+  };
+
+  const make = (module: string): Logger => {
+    const threshold = LOG_LEVELS.indexOf(levels.modules[module] ?? levels.default);
+    const enabled = (level: LogLevel) => LOG_LEVELS.indexOf(level) <= threshold;
+    const at = (level: LogLevel) => (message: string, fields?: Fields) => {
+      if (enabled(level)) emit({ time: new Date().toISOString(), level, module, message, fields });
+    };
+    return {
+      error: at('error'),
+      warn: at('warn'),
+      info: at('info'),
+      debug: at('debug'),
+      trace: at('trace'),
+      child: make,
+      enabled,
+    };
+  };
+  return make('server');
+}
@@ -0,0 +1,84 @@
+#!/usr/bin/env node
+// noofmt: formats .noo files from the command line with the same formatter
+// the language server uses for textDocument/formatting (format.ts).
+import * as fs from 'fs';
+import * as path from 'path';
+import { formatSource } from './format';
+
+const USAGE = `Usage: noofmt [--check | --write] <file or directory>...
+
+Without a flag, prints a single formatted file to stdout.
+
+Options:
+  --check   List files that aren't formatted; exit 1 if there are any
+  --write   Format files in place
+  --help    Print this message and exit
+
+Directories are searched for .noo files, skipping node_modules and .git.`;
+
+type Mode = 'print' | 'check' | 'write';
+
+const FLAGS = ['--check', '--write', '--help', '-h'];
+
+function noolangFiles(target: string): string[] {
+  if (!fs.statSync(target).isDirectory()) return [target];
+  return fs.readdirSync(target, { withFileTypes: true }).flatMap((entry) => {
+    if (entry.name === 'node_modules' || entry.name === '.git') return [];
+    const full = path.join(target, entry.name);
+    if (entry.isDirectory()) return noolangFiles(full);
+    return entry.name.endsWith('.noo') ? [full] : [];
+  });
+}
+
+// The exit status: 1 when files aren't formatted or can't be, 2 for a usage
+// error, such as a flag it doesn't know (a typo shouldn't format in place)
+export function main(argv: string[]): number {
+  if (argv.includes('--help') || argv.includes('-h')) {
+    console.log(USAGE);
+    return 0;
+  }
+  const unknown = argv.find((a) => a.startsWith('-') && !FLAGS.includes(a));
+  if (unknown) {
+    console.error(`noofmt: unknown option '${unknown}'\n\n${USAGE}`);
+    return 2;
+  }
+  const mode: Mode = argv.includes('--write') ? 'write' : argv.includes('--check') ? 'check' : 'print';
+  const targets = argv.filter((a) => !a.startsWith('-'));
+  if (targets.length === 0 || (mode === 'check' && argv.includes('--write'))) {
+    console.error(USAGE);
+    return 2;
+  }
+  let files: string[];
+  try {
+    files = targets.flatMap(noolangFiles);
+  } catch (e) {
+    console.error(`noofmt: ${(e as Error).message}`);
+    return 2;
+  }
+  if (mode === 'print' && files.length !== 1) {
+    console.error('noofmt: printing takes one file; use --check or --write for several');
+    return 2;
+  }
+  let failed = false;
+  for (const file of files) {
+    const source = fs.readFileSync(file, 'utf8');
+    const formatted = formatSource(source);
+    if (formatted === undefined) {
+      console.error(`noofmt: ${file}: could not be read as Noolang (unterminated string?); left as is`);
+      failed = true;
+      continue;
+    }
+    if (mode === 'print') process.stdout.write(formatted);
+    else if (formatted === source) continue;
+    else if (mode === 'check') {
+      console.log(file);
+      failed = true;
+    } else {
+      fs.writeFileSync(file, formatted);
+      console.log(`formatted ${file}`);
+    }
+  }
+  return failed ? 1 : 0;
+}
+
+if (require.main === module) process.exitCode = main(process.argv.slice(2));
@@ -0,0 +1,91 @@
+// Notebooks whose cells are Noolang. Cells share one environment, as if the
+// notebook were a single file, so the CLI checks them as one: the code cells
+// are joined into a combined program, and positions are moved between a
+// cell and its place in that program.
+
+// `code` is false for markup cells, which take no part in the program
+export type NotebookCell = { uri: string; code: boolean };
+
+export type NotebookEntry = {
+  uri: string;
+  version: number;
+  // In notebook order, markup cells included
+  cells: NotebookCell[];
+  texts: Map<string, string>;
+};
+
+// Where a code cell's first line lands in the combined program
+export type CellSpan = { uri: string; offset: number; lines: number };
+
+export type CombinedNotebook = { text: string; spans: CellSpan[] };
+
+// Each cell's trailing `;` is dropped and cells are joined with `;` on a line
+// of its own, so every cell keeps its own lines and a trailing comment in one
+// cell can't swallow the next.
+export function combineCells(entry: NotebookEntry): CombinedNotebook {
+  const parts: string[] = [];
+  const spans: CellSpan[] = [];
+  let offset = 0;
+  for (const cell of entry.cells) {
+    if (!cell.code) continue;
+    const text = (entry.texts.get(cell.uri) ?? '').replace(/[\s;]+$/, '');
+    if (!text.trim()) continue;
+    const lines = text.split(/\r?\n/).length;
+    spans.push({ uri: cell.uri, offset, lines });
+    parts.push(text);
+    offset += lines + 1;
+  }
+  return { text: parts.join('\n;\n'), spans };
+}
+
+// The cell a combined-program line belongs to; a separator line belongs to
+// the cell above it
+export function spanAtLine(spans: CellSpan[], line: number): CellSpan | undefined {
+  let found: CellSpan | undefined;
+  for (const span of spans) {
+    if (span.offset > line) break;
+    found = span;
+  }
+  return found;
+}
+
+export function createNotebookStore() {
+  const notebooks = new Map<string, NotebookEntry>();
+  // Cell document URI → its notebook's URI
+  const owners = new Map<string, string>();
+  return {
+    get: (uri: string) => notebooks.get(uri),
+    // The notebook a cell document belongs to
+    ofCell(cellUri: string): NotebookEntry | undefined {
+      const owner = owners.get(cellUri);
+      return owner === undefined ? undefined : notebooks.get(owner);
+    },
+    open(uri: string, version: number, cells: NotebookCell[], texts: { uri: string; text: string }[]): NotebookEntry {
+      const entry: NotebookEntry = { uri, version, cells, texts: new Map(texts.map((t) => [t.uri, t.text])) };
+      notebooks.set(uri, entry);
+      for (const cell of cells) owners.set(cell.uri, uri);
+      return entry;
+    },
+    // Replaces `deleteCount` cells at `start` (a cell array change)
+    splice(entry: NotebookEntry, start: number, deleteCount: number, cells: NotebookCell[]) {
+      const removed = entry.cells.splice(start, deleteCount, ...cells);
+      for (const cell of removed) {
+        owners.delete(cell.uri);
+        entry.texts.delete(cell.uri);
+      }
+      for (const cell of cells) owners.set(cell.uri, entry.uri);
+    },
+    setText(entry: NotebookEntry, cellUri: string, text: string) {
+      entry.texts.set(cellUri, text);
+    },
+    close(uri: string): NotebookEntry | undefined {
+      const entry = notebooks.get(uri);
+      if (!entry) return undefined;
+      notebooks.delete(uri);
+      for (const cell of entry.cells) owners.delete(cell.uri);
+      return entry;
+    },
+  };
+}
+
+export type NotebookStore = ReturnType<typeof createNotebookStore>;
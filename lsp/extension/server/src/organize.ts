@@ -0,0 +1,104 @@
+// Organize top-level definitions: imports first, then type declarations, then
+// values in their original order. Works on source text rather than the CLI
+// AST so comments attached to a statement travel with it.
+
+export type Statement = { text: string; start: number; end: number };
+
+// Splits source at top-level `;` — outside brackets, strings and comments.
+// Returns undefined when brackets don't balance, since any reordering of a
+// file we can't segment reliably would be a guess.
+export function splitTopLevelStatements(source: string): Statement[] | undefined {
+  const statements: Statement[] = [];
+  let depth = 0;
+  let start = 0;
+  let i = 0;
+  while (i < source.length) {
+    const ch = source[i];
+    if (ch === '#') {
+      while (i < source.length && source[i] !== '\n') i++;
+      continue;
+    }
+    if (ch === '"' || ch === "'" || ch === '`') {
+      i++;
+      while (i < source.length && source[i] !== ch) i += source[i] === '\\' ? 2 : 1;
+      i++;
+      continue;
+    }
+    if (ch === '(' || ch === '[' || ch === '{') depth++;
+    else if (ch === ')' || ch === ']' || ch === '}') {
+      depth--;
+      if (depth < 0) return undefined;
+    } else if (ch === ';' && depth === 0) {
+      statements.push({ text: source.slice(start, i), start, end: i });
+      start = i + 1;
+    }
+    i++;
+  }
+  if (depth !== 0) return undefined;
+  if (source.slice(start).trim()) statements.push({ text: source.slice(start), start, end: source.length });
+  return statements.filter((s) => s.text.trim());
+}
+
+type StatementGroup = 'import' | 'type' | 'value';
+
+// The first line of code in a statement, skipping leading comments.
+function firstCode(text: string): string {
+  return (
+    text
+      .split(/\r?\n/)
+      .map((l) => l.trim())
+      .find((l) => l && !l.startsWith('#')) ?? ''
+  );
+}
+
+export function classifyStatement(text: string): StatementGroup {
+  const code = firstCode(text);
+  if (/^(variant|type|constraint)\b/.test(code)) return 'type';
+  if (/^(?:[\w{}@,\s]+=\s*)?import\s/.test(code)) return 'import';
+  return 'value';
+}
+
+const normalize = (text: string) => text.replace(/\s+/g, ' ').trim();
+
+type Entry = { code: string; comment: string; terminated: boolean };
+
+// A `# ...` on the same line after a `;` belongs to the statement before it,
+// not the next one, so peel it off and re-attach it there.
+function toEntries(source: string, statements: Statement[]): Entry[] {
+  const entries: Entry[] = [];
+  for (const s of statements) {
+    const trailing = /^[ \t]*(#[^\n]*)/.exec(s.text);
+    if (trailing && entries.length > 0) entries[entries.length - 1].comment = ' ' + trailing[1];
+    const code = (trailing ? s.text.slice(trailing[0].length) : s.text).trim();
+    if (code) entries.push({ code, comment: '', terminated: source[s.end] === ';' });
+  }
+  return entries;
+}
+
+// Returns the reorganized source, or undefined when the file can't be
+// segmented. The last statement is the module's exported value, so it always
+// stays last.
+export function organizeDefinitions(source: string): string | undefined {
+  const statements = splitTopLevelStatements(source);
+  if (!statements) return undefined;
+  const entries = toEntries(source, statements);
+  if (entries.length < 2) return undefined;
+  const exported = entries[entries.length - 1];
+
+  const seenImports = new Set<string>();
+  const groups: Record<StatementGroup, Entry[]> = { import: [], type: [], value: [] };
+  for (const entry of entries.slice(0, -1)) {
+    const group = classifyStatement(entry.code);
+    if (group === 'import') {
+      if (seenImports.has(normalize(entry.code))) continue;
+      seenImports.add(normalize(entry.code));
+    }
+    groups[group].push(entry);
+  }
+
+  const sections = [groups.import, groups.type, groups.value]
+    .filter((g) => g.length > 0)
+    .map((g) => g.map((e) => `${e.code};${e.comment}`).join('\n'));
+  const last = exported.code + (exported.terminated ? ';' : '') + exported.comment;
+  return [...sections, last].join('\n\n') + '\n';
+}
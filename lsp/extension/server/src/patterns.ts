@@ -0,0 +1,37 @@
+// Whether a position starts a case of a `match x (…)`, where a pattern goes,
+// for constructor completion: right after the match's `(` or a `;` between
+// its cases, with any word being typed there. Found with the lexer, so it
+// works on a buffer that doesn't parse yet.
+import { CLOSERS, isPunctuation, isTrivia, lexSyntax, OPENERS, SyntaxToken } from './syntax';
+
+// The bracket at `at` belongs to a match when the tokens before it, at its
+// depth, lead back to a `match` before anything that ends an expression
+function opensMatch(tokens: SyntaxToken[], at: number): boolean {
+  let depth = 0;
+  for (let i = at - 1; i >= 0; i--) {
+    const t = tokens[i];
+    if (isPunctuation(t, CLOSERS)) depth++;
+    else if (isPunctuation(t, OPENERS) && depth-- === 0) return false;
+    else if (depth > 0) continue;
+    else if (t.kind === 'keyword') return t.text === 'match';
+    else if (isPunctuation(t, ';,') || (t.kind === 'operator' && ['=', '=>'].includes(t.text))) return false;
+  }
+  return false;
+}
+
+export function inPatternPosition(source: string, offset: number): boolean {
+  const tokens = lexSyntax(source).filter((t) => !isTrivia(t) && t.start < offset);
+  const last = tokens[tokens.length - 1];
+  // A word being typed at the position
+  if (last && last.end >= offset && last.kind === 'identifier') tokens.pop();
+  let before = tokens.length - 1;
+  if (!isPunctuation(tokens[before], '(;')) return false;
+  // Back to the `(` of the cases the `;` separates
+  let depth = 0;
+  for (; before >= 0; before--) {
+    const t = tokens[before];
+    if (isPunctuation(t, CLOSERS)) depth++;
+    else if (isPunctuation(t, OPENERS) && depth-- === 0) break;
+  }
+  return before >= 0 && tokens[before].text === '(' && opensMatch(tokens, before);
+}
@@ -0,0 +1,22 @@
+// Where a pipeline's operators are, for inlay hints of the types crossing
+// them. The CLI (--pipeline-types) gives each pipeline's start and a type
+// per operator; the operators themselves are found with the lexer: the
+// pipeline operators from the start at its own bracket depth, up to the `;`,
+// `,` or unmatched closer that ends it.
+import { CLOSERS, isPunctuation, isTrivia, lexSyntax, OPENERS, SyntaxToken } from './syntax';
+
+const PIPE_OPERATORS = new Set(['|', '|?', '|>', '<|']);
+
+// At most `count` operators of the pipeline starting at `start`
+export function pipelineOperators(source: string, start: number, count: number): SyntaxToken[] {
+  const operators: SyntaxToken[] = [];
+  let depth = 0;
+  for (const t of lexSyntax(source).filter((t) => t.start >= start && !isTrivia(t))) {
+    if (operators.length === count) break;
+    if (isPunctuation(t, OPENERS)) depth++;
+    if (isPunctuation(t, CLOSERS) && --depth < 0) break;
+    if (depth === 0 && isPunctuation(t, ';,')) break;
+    if (depth === 0 && t.kind === 'operator' && PIPE_OPERATORS.has(t.text)) operators.push(t);
+  }
+  return operators;
+}
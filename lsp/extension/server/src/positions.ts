@@ -0,0 +1,28 @@
+// Lines of an edited buffer as lines of the file as last saved, and back,
+// for answering requests about the buffer from the CLI's analysis of the
+// saved file. The lines the edits left alone, before the first changed line
+// and after the last, carry over, moved by however many lines were added or
+// removed; changed lines have no counterpart.
+export type LineMap = {
+  toSaved: (line: number) => number | undefined;
+  fromSaved: (line: number) => number | undefined;
+};
+
+export function lineMap(buffer: string, saved: string): LineMap {
+  const [a, b] = [buffer.split(/\r?\n/), saved.split(/\r?\n/)];
+  let prefix = 0;
+  while (prefix < a.length && prefix < b.length && a[prefix] === b[prefix]) prefix++;
+  let suffix = 0;
+  while (
+    suffix < a.length - prefix &&
+    suffix < b.length - prefix &&
+    a[a.length - 1 - suffix] === b[b.length - 1 - suffix]
+  ) {
+    suffix++;
+  }
+  const shift = b.length - a.length;
+  return {
+    toSaved: (line) => (line < prefix ? line : line >= a.length - suffix ? line + shift : undefined),
+    fromSaved: (line) => (line < prefix ? line : line >= b.length - suffix ? line - shift : undefined),
+  };
+}
@@ -0,0 +1,75 @@
+// Quick fixes for an application chain the checker rejected because it
+// parsed differently than meant: application is left-associative, so
+// `add add 1 2 3` applies `add` to `add`, where `add (add 1 2) 3` was likely
+// intended. Each grouping wraps a function among the arguments with the
+// arguments it takes, as many as its arity when that's known and each
+// possible count otherwise. Found with the lexer, like signature help.
+import { CLOSERS, isPunctuation, isTrivia, lexSyntax, OPENERS } from './syntax';
+import { Span } from './workspace';
+
+// A way to parenthesize the chain: `open` and `close` are where the brackets
+// go, `text` the whole chain with them
+export type Grouping = { open: number; close: number; text: string };
+
+// The chain's operands from `offset` on: single tokens and bracketed groups,
+// up to the operator, keyword, separator or unmatched closer that ends it
+export function applicationOperands(source: string, offset: number): Span[] {
+  const tokens = lexSyntax(source).filter((t) => t.start >= offset && !isTrivia(t));
+  const operands: Span[] = [];
+  let depth = 0;
+  let start = 0;
+  for (const t of tokens) {
+    if (isPunctuation(t, OPENERS)) {
+      if (depth++ === 0) start = t.start;
+    } else if (isPunctuation(t, CLOSERS)) {
+      if (depth === 0) break;
+      if (--depth === 0) operands.push({ start, end: t.end });
+    } else if (depth === 0) {
+      const operand = t.kind === 'identifier' || t.kind === 'number' || t.kind === 'string' || t.kind === 'accessor';
+      if (!operand) break;
+      operands.push({ start: t.start, end: t.end });
+    }
+  }
+  return operands;
+}
+
+// How many parameters a top-level `name = fn a b => …` in the source takes
+export function definedArity(source: string, name: string): number | undefined {
+  const tokens = lexSyntax(source).filter((t) => !isTrivia(t));
+  for (let i = 0; i + 2 < tokens.length; i++) {
+    const [named, equals, fn] = tokens.slice(i, i + 3);
+    const defines = named.kind === 'identifier' && named.text === name && equals.text === '=' && fn.text === 'fn';
+    if (!defines || (i > 0 && tokens[i - 1].text !== ';')) continue;
+    const arrow = tokens.findIndex((t, j) => j > i + 2 && t.text === '=>');
+    return arrow < 0 ? undefined : arrow - (i + 3);
+  }
+  return undefined;
+}
+
+// Every grouping of the chain at `offset` that gives a function argument its
+// own arguments; none when the chain has nothing to regroup
+export function applicationGroupings(
+  source: string,
+  offset: number,
+  arity: (name: string) => number | undefined,
+): Grouping[] {
+  const operands = applicationOperands(source, offset);
+  const text = (s: Span) => source.slice(s.start, s.end);
+  const groupings: Grouping[] = [];
+  // The function at 0 already applies to everything after it
+  for (let i = 1; i < operands.length - 1; i++) {
+    if (!/^[a-z_]\w*$/.test(text(operands[i]))) continue;
+    const known = arity(text(operands[i]));
+    const available = operands.length - 1 - i;
+    const counts = known === undefined ? [...Array(available).keys()].map((n) => n + 1) : [known];
+    for (const count of counts.filter((n) => n >= 1 && n <= available)) {
+      const open = operands[i].start;
+      const close = operands[i + count].end;
+      const first = operands[0].start;
+      const last = operands[operands.length - 1].end;
+      const grouped = `${source.slice(first, open)}(${source.slice(open, close)})${source.slice(close, last)}`;
+      groupings.push({ open, close, text: grouped });
+    }
+  }
+  return groupings;
+}
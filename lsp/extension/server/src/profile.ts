@@ -0,0 +1,75 @@
+// Deep profiling of the server itself, on when the server starts with
+// NOO_LSP_PROFILE=1: spans timed on the high-resolution clock and summed by
+// name, finer than telemetry's per-request timings: each CLI run, reading
+// its output, each walk over an AST. The report goes to the log at shutdown
+// and is returned by the noolang.dumpProfile command. Off, every wrapper is
+// a plain call.
+import { performance } from 'perf_hooks';
+
+export type SpanStats = { count: number; totalMs: number; maxMs: number };
+
+export const profilingEnabled = (env: NodeJS.ProcessEnv) => env.NOO_LSP_PROFILE === '1';
+
+const fixed = (ms: number) => ms.toFixed(1);
+
+// A table of spans, the costliest first
+export function profileReport(spans: Map<string, SpanStats>, uptimeMs: number): string {
+  const rows = [...spans].sort(([, a], [, b]) => b.totalMs - a.totalMs);
+  const width = Math.max(4, ...rows.map(([name]) => name.length));
+  const header =
+    `${'span'.padEnd(width)}  ${'calls'.padStart(6)}  ${'total ms'.padStart(10)}  ` +
+    `${'mean ms'.padStart(8)}  ${'max ms'.padStart(8)}`;
+  const lines = rows.map(
+    ([name, s]) =>
+      `${name.padEnd(width)}  ${String(s.count).padStart(6)}  ${fixed(s.totalMs).padStart(10)}  ` +
+      `${fixed(s.totalMs / s.count).padStart(8)}  ${fixed(s.maxMs).padStart(8)}`,
+  );
+  const title = `Noolang LSP profile (NOO_LSP_PROFILE) over ${fixed(uptimeMs / 1000)}s`;
+  return [title, header, ...(lines.length > 0 ? lines : ['(no spans recorded)'])].join('\n');
+}
+
+export function createProfiler(enabled: boolean) {
+  const spans = new Map<string, SpanStats>();
+  const started = performance.now();
+
+  function add(name: string, ms: number) {
+    const s = spans.get(name) ?? { count: 0, totalMs: 0, maxMs: 0 };
+    s.count++;
+    s.totalMs += ms;
+    s.maxMs = Math.max(s.maxMs, ms);
+    spans.set(name, s);
+  }
+
+  function measure<T>(name: string, fn: () => T): T {
+    if (!enabled) return fn();
+    const start = performance.now();
+    try {
+      return fn();
+    } finally {
+      add(name, performance.now() - start);
+    }
+  }
+
+  async function measureAsync<T>(name: string, fn: () => Promise<T>): Promise<T> {
+    if (!enabled) return fn();
+    const start = performance.now();
+    try {
+      return await fn();
+    } finally {
+      add(name, performance.now() - start);
+    }
+  }
+
+  return {
+    enabled,
+    measure,
+    measureAsync,
+    // `fn` with every call measured as `name`
+    profiled<F extends (...args: any[]) => any>(name: string, fn: F): F {
+      return enabled ? ((...args: Parameters<F>) => measure(name, () => fn(...args))) as F : fn;
+    },
+    report: () => profileReport(spans, performance.now() - started),
+  };
+}
+
+export type Profiler = ReturnType<typeof createProfiler>;
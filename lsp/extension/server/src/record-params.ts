@@ -0,0 +1,90 @@
+// "Take parameters as a record": a top-level `f = fn x y => body` becomes
+// `f = fn args => ({@x, @y} = args; body)`, and every call `f a b` becomes
+// `f {@x a, @y b}`. Calls are found from the rename index's references to
+// `f` (rename.ts) and rewritten with the lexer, like signature help: a
+// reference heads an application of at least as many operands as there are
+// parameters, either itself or as `@f m` on a module imported whole. The
+// name where it's defined, imported or exported (`{@f f}`) stays as it is;
+// any other use, passing `f` along or applying it partially, can't follow,
+// so the refactor isn't offered.
+import { lambdaAt } from './lambdas';
+import { applicationOperands } from './precedence';
+import { CLOSERS, isPunctuation, isTrivia, lexSyntax, SyntaxToken } from './syntax';
+import { Span } from './workspace';
+
+export type SpanEdit = { span: Span; text: string };
+
+export type RecordParameter = { name: string; nameSpan: Span; params: string[]; edit: SpanEdit };
+
+// What becomes of a reference: the call's arguments replaced, nothing (the
+// definition, an import or an export), or nothing possible
+export type CallSite = { kind: 'call'; edit: SpanEdit } | { kind: 'unchanged' } | { kind: 'blocked' };
+
+const RECORD_NAMES = ['args', 'params', 'options', 'input'];
+
+// Tokens that end an operand, so a name after one is its argument
+const endsOperand = (t: SyntaxToken | undefined) =>
+  !!t && (['identifier', 'number', 'string', 'accessor'].includes(t.kind) || isPunctuation(t, CLOSERS));
+
+// Whether `name` is bound anywhere but `name = …` at `at`: another
+// definition or a parameter, which the references would mix up with it
+function rebound(tokens: SyntaxToken[], name: string, at: number): boolean {
+  return tokens.some((t, i) => {
+    if (t.kind !== 'identifier' || t.text !== name || t.start === at) return false;
+    if (tokens[i + 1]?.text === '=') return true;
+    const fn = tokens.slice(0, i).reverse().find((u) => u.kind !== 'identifier');
+    return fn?.kind === 'keyword' && fn.text === 'fn';
+  });
+}
+
+// The top-level function of two or more parameters whose lambda holds the
+// offset
+export function recordParameterAt(source: string, offset: number): RecordParameter | undefined {
+  const lambda = lambdaAt(source, offset);
+  if (!lambda?.binding || lambda.params.length < 2) return undefined;
+  const tokens = lexSyntax(source).filter((t) => !isTrivia(t));
+  const fn = tokens.findIndex((t) => t.start === lambda.span.start);
+  const named = tokens[fn - 2];
+  if (fn < 2 || (fn > 2 && !isPunctuation(tokens[fn - 3], ';'))) return undefined;
+  if (rebound(tokens, named.text, named.start)) return undefined;
+  const taken = new Set(tokens.flatMap((t) => (t.kind === 'identifier' ? [t.text] : [])));
+  const record = RECORD_NAMES.find((n) => !taken.has(n));
+  if (!record) return undefined;
+  const params = lambda.params.map((p) => p.text);
+  const body = source.slice(lambda.body.start, lambda.body.end);
+  const pattern = `{${params.map((p) => `@${p}`).join(', ')}}`;
+  const edit = { span: lambda.span, text: `fn ${record} => (${pattern} = ${record}; ${body})` };
+  return { name: named.text, nameSpan: { start: named.start, end: named.end }, params, edit };
+}
+
+// The reference to the function at `span` in `text`
+export function callSiteAt(text: string, span: Span, params: string[]): CallSite {
+  const tokens = lexSyntax(text).filter((t) => !isTrivia(t));
+  const at = tokens.findIndex((t) => t.start <= span.start && span.end <= t.end);
+  const token = tokens[at];
+  const [before, after] = [tokens[at - 1], tokens[at + 1]];
+  if (!token) return { kind: 'blocked' };
+  // Its definition, and imports and exports: `{@f}`, `{@f g}`, `{@f f}`
+  if (token.kind === 'identifier' && after?.text === '=') return { kind: 'unchanged' };
+  const fieldEnds = (i: number) => isPunctuation(tokens[i], ',}');
+  if (token.kind === 'accessor' && isPunctuation(before, '{,')) {
+    if (fieldEnds(at + 1) || (after?.kind === 'identifier' && fieldEnds(at + 2))) return { kind: 'unchanged' };
+  }
+  if (token.kind === 'identifier' && before?.kind === 'accessor' && isPunctuation(tokens[at - 2], '{,')) {
+    if (fieldEnds(at + 1)) return { kind: 'unchanged' };
+  }
+  // The head of the application, and where its arguments start
+  let head = at;
+  let skip = 1;
+  if (token.kind === 'accessor') {
+    const grouped = isPunctuation(before, '(') && after?.kind === 'identifier' && isPunctuation(tokens[at + 2], ')');
+    head = grouped ? at - 1 : at;
+    skip = grouped ? 1 : 2;
+  }
+  if (endsOperand(tokens[head - 1])) return { kind: 'blocked' };
+  const args = applicationOperands(text, tokens[head].start).slice(skip, skip + params.length);
+  if (args.length < params.length) return { kind: 'blocked' };
+  const fields = args.map((a, i) => `@${params[i]} ${text.slice(a.start, a.end)}`);
+  const replaced = { start: args[0].start, end: args[args.length - 1].end };
+  return { kind: 'call', edit: { span: replaced, text: `{${fields.join(', ')}}` } };
+}
@@ -0,0 +1,86 @@
+// Record literals, for completing their fields: the literal a position is
+// in, where a field's name goes next, and which fields it already has. Found
+// with the lexer, like signature help, so an unfinished literal counts. Also
+// the fields of a record type as the CLI prints it, `{ @name String, @age
+// Float }`, and fields given twice in one record.
+import { CLOSERS, isPunctuation, isTrivia, lexSyntax, OPENERS, SyntaxToken } from './syntax';
+import { Span } from './workspace';
+
+// `open` is the `{`, `close` the end of its `}` when there is one; `replace`
+// the accessor being typed at the position (or an empty span there)
+export type RecordEntry = { open: number; close?: number; replace: Span; present: string[] };
+
+// The accessors naming the entries of the record whose `{` is tokens[open]:
+// at its depth, first in the record or after a `,`
+function entryNames(tokens: SyntaxToken[], open: number): { names: SyntaxToken[]; close?: number } {
+  const names: SyntaxToken[] = [];
+  let depth = 0;
+  for (let i = open + 1; i < tokens.length; i++) {
+    const t = tokens[i];
+    if (isPunctuation(t, CLOSERS) && depth-- === 0) return { names, close: i };
+    if (isPunctuation(t, OPENERS)) depth++;
+    if (depth === 0 && t.kind === 'accessor' && isPunctuation(tokens[i - 1], '{,')) names.push(t);
+  }
+  return { names };
+}
+
+// Where the next field of a record literal goes: after its `{` or a `,`,
+// with any accessor being typed there
+export function recordEntryAt(source: string, offset: number): RecordEntry | undefined {
+  const tokens = lexSyntax(source).filter((t) => !isTrivia(t));
+  const typing = tokens.findIndex((t) => t.kind === 'accessor' && t.start < offset && offset <= t.end);
+  const replace: Span =
+    typing >= 0 ? { start: tokens[typing].start, end: tokens[typing].end } : { start: offset, end: offset };
+  const before = (typing >= 0 ? typing : tokens.filter((t) => t.end <= offset).length) - 1;
+  if (!isPunctuation(tokens[before], '{,')) return undefined;
+  let depth = 0;
+  let open = before;
+  for (; open >= 0; open--) {
+    const t = tokens[open];
+    if (isPunctuation(t, CLOSERS)) depth++;
+    else if (isPunctuation(t, OPENERS) && depth-- === 0) break;
+  }
+  if (open < 0 || tokens[open].text !== '{') return undefined;
+  const { names, close } = entryNames(tokens, open);
+  const present = names.filter((t) => t.start !== replace.start).map((t) => t.text.slice(1));
+  return { open: tokens[open].start, close: close === undefined ? undefined : tokens[close].end, replace, present };
+}
+
+// `{ @name String, @age Float }` as its fields; undefined for any other type
+export function recordFields(type: string): { name: string; type: string }[] | undefined {
+  const body = /^\{(.*)\}$/s.exec(type.trim())?.[1];
+  if (body === undefined) return undefined;
+  const entries: string[] = [];
+  let depth = 0;
+  let start = 0;
+  [...body].forEach((c, i) => {
+    if (OPENERS.includes(c)) depth++;
+    if (CLOSERS.includes(c)) depth--;
+    if (c === ',' && depth === 0) {
+      entries.push(body.slice(start, i));
+      start = i + 1;
+    }
+  });
+  entries.push(body.slice(start));
+  const fields = entries.map((e) => /^\s*@(\w+)\s+(.*?)\s*$/s.exec(e));
+  if (fields.some((f) => !f)) return undefined;
+  return fields.map((f) => ({ name: f![1], type: f![2] }));
+}
+
+// Every field named again in the same record (literal, type or pattern),
+// with the span of the repeat
+export function duplicateFields(source: string): { field: string; span: Span }[] {
+  const tokens = lexSyntax(source).filter((t) => !isTrivia(t));
+  return tokens.flatMap((t, i) => {
+    if (!isPunctuation(t, '{')) return [];
+    const seen = new Set<string>();
+    return entryNames(tokens, i).names.flatMap((name) => {
+      const field = name.text.slice(1);
+      if (!seen.has(field)) {
+        seen.add(field);
+        return [];
+      }
+      return [{ field, span: { start: name.start, end: name.end } }];
+    });
+  });
+}
@@ -0,0 +1,204 @@
+// Rename over the workspace reference index (workspace.ts). A name is
+// renamed everywhere in its file; when it is, or stands for, a module's
+// exported field, the field follows across the workspace: in the module's
+// export record, in every import pattern that destructures it (with the
+// local name too, for `{@field}` shorthands) and in `@field` accessors in
+// files that bind the whole module. Any other record field is renamed
+// wherever it's written, by name like its references.
+import { isKeyword, isTrivia, lexSyntax, SyntaxToken } from './syntax';
+import { IndexedFile, Span, WorkspaceIndex } from './workspace';
+
+export type RenameTarget = { kind: 'name' | 'field'; name: string; span: Span };
+
+// Edits per file, each replacing a span whose text is the old name, with the
+// stamp of the text they were planned against
+export type RenamePlan = { oldName: string; edits: Map<string, { stamp: string; spans: Span[] }> };
+
+// The name or `@field` at an offset (a field's span leaves out the `@`)
+export function renameTargetAt(text: string, offset: number): RenameTarget | undefined {
+  const token = lexSyntax(text).find((t: SyntaxToken) => !isTrivia(t) && t.start <= offset && offset <= t.end);
+  if (token?.kind === 'identifier') {
+    return { kind: 'name', name: token.text, span: { start: token.start, end: token.end } };
+  }
+  if (token?.kind === 'accessor') {
+    return { kind: 'field', name: token.text.slice(1), span: { start: token.start + 1, end: token.end } };
+  }
+  return undefined;
+}
+
+// A token as the compiler's lexer reads it (`noo --tokens`), without its EOF
+export type CompilerToken = { type: string; value: string };
+
+// The lines of `noo --tokens` output, `  0: IDENTIFIER ('name')`
+export function parseCliTokens(stdout: string): CompilerToken[] {
+  return stdout.split('\n').flatMap((line) => {
+    const match = /^\s*\d+: (\w+) \('(.*)'\)$/.exec(line);
+    return match && match[1] !== 'EOF' ? [{ type: match[1], value: match[2] }] : [];
+  });
+}
+
+// The server's own reading of `source`, for when the CLI can't lex it
+export function syntaxTokens(source: string): CompilerToken[] {
+  return lexSyntax(source)
+    .filter((t) => !isTrivia(t))
+    .map((t) => ({ type: t.kind === 'accessor' ? 'ACCESSOR' : t.kind, value: t.text.replace(/^@/, '') }));
+}
+
+// Why `newName` can't replace the target, if it can't. `tokens` are the
+// compiler's lexing of `@newName`: the name has to be all of one accessor,
+// so exactly the characters the lexer takes for a name, and not a keyword
+// (the compiler's, see useLexicon). It keeps the case of the old name's
+// first letter, which is what tells a value from a constructor or type.
+export function invalidRename(target: RenameTarget, newName: string, tokens: CompilerToken[]): string | undefined {
+  if (newName === '') return 'The new name is empty';
+  if (target.kind === 'name' && isKeyword(newName)) return `'${newName}' is a keyword`;
+  if (/^\d/.test(newName)) return `'${newName}' starts with a digit`;
+  const [token] = tokens;
+  if (tokens.length !== 1 || token.type !== 'ACCESSOR' || token.value !== newName || newName.endsWith('?')) {
+    return `'${newName}' isn't a valid name: use letters, digits and _`;
+  }
+  const upper = (name: string) => /^[A-Z]/.test(name);
+  if (target.kind === 'name' && upper(newName) !== upper(target.name)) {
+    const wanted = upper(target.name) ? 'an uppercase letter' : 'a lowercase letter or _';
+    return `'${newName}' must start with ${wanted}, like '${target.name}'`;
+  }
+  return undefined;
+}
+
+const within = (span: Span, offset: number) => span.start <= offset && offset <= span.end;
+
+export function planRename(
+  index: WorkspaceIndex,
+  file: IndexedFile,
+  target: RenameTarget,
+  // Open documents outside the workspace folders
+  extra: string[] = [],
+): RenamePlan {
+  const edits = new Map<string, { file: IndexedFile; spans: Map<number, Span> }>();
+  const add = (file: IndexedFile, span: Span) => {
+    const entry = edits.get(file.filePath) ?? { file, spans: new Map<number, Span>() };
+    entry.spans.set(span.start, span);
+    edits.set(file.filePath, entry);
+  };
+  const renameName = (f: IndexedFile, name: string) => f.identifiers.get(name)?.forEach((s) => add(f, s));
+  const renamedFields = new Set<string>();
+
+  function renameField(modulePath: string, field: string) {
+    const key = `${modulePath}\0${field}`;
+    if (renamedFields.has(key)) return;
+    renamedFields.add(key);
+    const module = index.get(modulePath);
+    for (const e of module?.exports ?? []) {
+      if (!module || e.field !== field) continue;
+      add(module, e.fieldSpan);
+      // `{@add add}` keeps its shape
+      if (e.local === field) renameName(module, field);
+    }
+    for (const importer of index.importersOf(modulePath, extra)) {
+      for (const site of importer.imports) {
+        if (site.target !== modulePath) continue;
+        for (const f of site.fields) {
+          if (f.field !== field) continue;
+          add(importer, f.fieldSpan);
+          // `{@add}` binds `add`
+          if (!f.localSpan) renameName(importer, field);
+        }
+        if (site.binding) importer.fields.get(field)?.forEach((s) => add(importer, s));
+      }
+    }
+  }
+
+  // Literals, accessors, patterns and record types; `{@field}` binds the
+  // name too, so it's renamed with the field
+  function renameRecordField(field: string) {
+    for (const f of index.all(extra)) {
+      f.fields.get(field)?.forEach((s) => add(f, s));
+      if (f.shorthands.has(field)) renameName(f, field);
+    }
+  }
+
+  const imported = file.imports.flatMap((site) => site.fields.map((f) => ({ site, f })));
+  if (target.kind === 'name') {
+    renameName(file, target.name);
+    const source = imported.find(({ f }) => f.local === target.name);
+    if (source) {
+      // A local name given with `{@field local}` is the file's own
+      if (!source.f.localSpan && source.site.target) renameField(source.site.target, source.f.field);
+    } else if (file.exports.some((e) => e.field === target.name && e.local === target.name)) {
+      renameField(file.filePath, target.name);
+    }
+  } else {
+    const source = imported.find(({ f }) => within(f.fieldSpan, target.span.start));
+    // `@add math` on a bound module, going by name like the rest
+    const bound = file.imports.find(
+      (site) => site.binding && site.target && index.get(site.target)?.exports.some((e) => e.field === target.name),
+    );
+    if (source?.site.target) renameField(source.site.target, target.name);
+    else if (file.exports.some((e) => within(e.fieldSpan, target.span.start))) renameField(file.filePath, target.name);
+    else if (bound?.target) renameField(bound.target, target.name);
+    else renameRecordField(target.name);
+  }
+  return {
+    oldName: target.name,
+    edits: new Map(
+      [...edits].map(([filePath, { file, spans }]) => [filePath, { stamp: file.stamp, spans: [...spans.values()] }]),
+    ),
+  };
+}
+
+// The dry run before a plan is applied: files whose text has moved on since
+// it was planned (an edit arrived, or the file changed on disk) and would
+// take its edits in the wrong places
+export function conflictingFiles(index: WorkspaceIndex, plan: RenamePlan): string[] {
+  return [...plan.edits]
+    .filter(([filePath, { stamp, spans }]) => {
+      const file = index.get(filePath);
+      if (!file) return true;
+      return file.stamp !== stamp || spans.some((s) => file.text.slice(s.start, s.end) !== plan.oldName);
+    })
+    .map(([filePath]) => filePath);
+}
+
+// Records and record types where a renamed field sits next to one already
+// called `newName`, which would leave them with two: the files they're in
+export function fieldClashes(index: WorkspaceIndex, plan: RenamePlan, newName: string): string[] {
+  return [...plan.edits]
+    .filter(([filePath, { spans }]) => {
+      const file = index.get(filePath);
+      if (!file) return false;
+      const renamed = new Set(spans.map((s) => s.start));
+      // One entry per open `{`: its fields, and whether one is renamed
+      const groups: { fields: Set<string>; renamed: boolean }[] = [];
+      return lexSyntax(file.text).some((t) => {
+        const top = groups[groups.length - 1];
+        if (t.kind === 'punctuation' && t.text === '{') groups.push({ fields: new Set(), renamed: false });
+        if (t.kind === 'accessor' && top) {
+          top.fields.add(t.text.slice(1));
+          top.renamed ||= renamed.has(t.start + 1);
+        }
+        if (t.kind !== 'punctuation' || t.text !== '}') return false;
+        groups.pop();
+        return !!top?.renamed && top.fields.has(newName);
+      });
+    })
+    .map(([filePath]) => filePath);
+}
+
+// Files in a name's rename where `newName` is already taken. `declared`: the
+// file defines or imports it at the top level, so the two would collide.
+// Otherwise it's used there (a local binding, a parameter, a builtin), and
+// the rename would shadow it or be shadowed by it.
+export function nameClashes(
+  index: WorkspaceIndex,
+  plan: RenamePlan,
+  newName: string,
+): { filePath: string; declared: boolean }[] {
+  return [...plan.edits.keys()].flatMap((filePath) => {
+    const file = index.get(filePath);
+    if (!file) return [];
+    const declared =
+      file.declarations.some((d) => d.name === newName && !d.container) ||
+      file.imports.some((site) => site.binding === newName || site.fields.some((f) => f.local === newName));
+    return declared || file.identifiers.has(newName) ? [{ filePath, declared }] : [];
+  });
+}
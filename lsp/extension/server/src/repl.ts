@@ -0,0 +1,118 @@
+// REPL sessions for `noolang.sendToRepl`: one long-lived CLI REPL per
+// workspace folder, so definitions sent earlier stay in scope for later
+// selections. Output streams back as it arrives rather than per selection,
+// since the REPL gives no marker for where one input's output ends.
+import { spawn, ChildProcessWithoutNullStreams } from 'child_process';
+import { cliArgv } from './runtime';
+import { isPunctuation, isToken, isTrivia, lexSyntax, parseSyntax } from './syntax';
+
+export type ReplOutput = { workspace: string; stream: 'stdout' | 'stderr'; text: string };
+
+export type ReplSessions = {
+  send(workspace: string, code: string): void;
+  // Workspaces with a running REPL
+  active(): string[];
+  stop(workspace: string): boolean;
+  stopAll(): void;
+};
+
+const ANSI = /\x1b\[[0-9;]*m/g;
+const PROMPT = /noolang> /g;
+
+// Keywords that carry an expression on from the line before
+const CONTINUING = new Set(['then', 'else', 'in', 'where', 'with', 'given', 'is', 'has', 'implements']);
+
+const column = (source: string, offset: number) => offset - (source.lastIndexOf('\n', offset - 1) + 1);
+
+// The line breaks that end a statement not ended by a `;`: at its top level
+// (parseSyntax), before a line no more indented than the selection's first,
+// with nothing either side that carries the expression on (an operator, a
+// `,` or `:`, or a keyword like `then`). In a file those lines would be one
+// application; sent to the REPL as one line, they're meant as two.
+function statementBreaks(code: string): Set<number> {
+  const { root } = parseSyntax(code);
+  const first = lexSyntax(code).find((t) => !isTrivia(t));
+  const indent = first ? column(code, first.start) : 0;
+  const breaks = new Set<number>();
+  for (const statement of root.children) {
+    if (isToken(statement)) continue;
+    const children = statement.children;
+    children.forEach((ws, i) => {
+      if (!isToken(ws) || ws.kind !== 'whitespace' || !ws.text.includes('\n')) return;
+      const before = children.slice(0, i).reverse().find((c) => !isToken(c) || !isTrivia(c));
+      const after = children.slice(i + 1).find((c) => !isToken(c) || !isTrivia(c));
+      if (!before || !after || column(code, after.start) > indent) return;
+      const carriesOn =
+        (isToken(before) && (['operator', 'keyword'].includes(before.kind) || isPunctuation(before, ',:'))) ||
+        (isToken(after) && (after.kind === 'operator' || (after.kind === 'keyword' && CONTINUING.has(after.text))));
+      if (!carriesOn) breaks.add(ws.start);
+    });
+  }
+  return breaks;
+}
+
+// The REPL reads a line at a time, so a selection is sent as one line.
+// Comments run to the end of their line and would swallow the rest, so
+// they're dropped, and the whitespace between tokens becomes one space, or
+// `; ` where a line break ends a statement (statementBreaks). Strings and
+// templates, of either quote, are lexed as the compiler does and copied as
+// they are, so a `#` or the spacing inside one survives.
+export function toReplLine(code: string): string {
+  const breaks = statementBreaks(code);
+  let line = '';
+  let gap = '';
+  for (const t of lexSyntax(code)) {
+    if (t.kind === 'comment') continue;
+    if (t.kind === 'whitespace') gap = breaks.has(t.start) ? '; ' : gap || ' ';
+    else {
+      line += (line ? gap : '') + t.text;
+      gap = '';
+    }
+  }
+  return line;
+}
+
+export function createReplSessions(
+  runtime: string,
+  cliPath: string,
+  onOutput: (output: ReplOutput) => void,
+): ReplSessions {
+  const sessions = new Map<string, ChildProcessWithoutNullStreams>();
+
+  function start(workspace: string): ChildProcessWithoutNullStreams {
+    const child = spawn(runtime, cliArgv(runtime, cliPath, []), { cwd: workspace || undefined });
+    const forward = (stream: ReplOutput['stream']) => (chunk: string) => {
+      const text = chunk.replace(ANSI, '').replace(PROMPT, '');
+      if (text) onOutput({ workspace, stream, text });
+    };
+    child.stdout.setEncoding('utf8').on('data', forward('stdout'));
+    child.stderr.setEncoding('utf8').on('data', forward('stderr'));
+    child.on('error', (e) => onOutput({ workspace, stream: 'stderr', text: `Could not start the REPL: ${e.message}\n` }));
+    child.on('close', () => {
+      if (sessions.get(workspace) === child) sessions.delete(workspace);
+    });
+    sessions.set(workspace, child);
+    return child;
+  }
+
+  return {
+    send(workspace, code) {
+      const line = toReplLine(code);
+      if (!line) return;
+      const child = sessions.get(workspace) ?? start(workspace);
+      child.stdin.write(line + '\n');
+    },
+    active: () => [...sessions.keys()],
+    stop(workspace) {
+      const child = sessions.get(workspace);
+      if (!child) return false;
+      sessions.delete(workspace);
+      child.kill();
+      return true;
+    },
+    stopAll() {
+      for (const child of sessions.values()) child.kill();
+      sessions.clear();
+    },
+  };
+}
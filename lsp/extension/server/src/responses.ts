@@ -0,0 +1,312 @@
+// Typed models of CLI query responses, and the one place their output is read.
+// CLIs with --json print one JSON document per query, which is checked
+// against the model here; older CLIs print text, read by the legacy parsers
+// below. Either way callers get the same model, or an Error when the output
+// doesn't have the expected shape.
+import { AstProgram } from './ast';
+
+// --types, --types-file: one entry per top-level statement
+export type TypesResponse = { types: (string | null)[] };
+
+// --symbol-types: names that don't resolve map to null
+export type SymbolTypesResponse = { types: Record<string, string | null> };
+
+// --symbol-type
+export type SymbolTypeResponse = { symbol: string; type: string | null };
+
+// --expr-type
+export type ExprTypeResponse = { type: string | null };
+
+// --type-at: the reference at a position, with its type where it's used and
+// the implementations that satisfied its constraints (older CLIs leave
+// `resolutions` out)
+export type TypeAtResponse = {
+  name: string | null;
+  type: string | null;
+  resolutions?: { trait: string; type: string }[];
+};
+
+// --pipeline-types: per pipeline, where it starts (1-based) and the type
+// crossing each of its operators
+export type PipelineTypesResponse = { pipelines: { line: number; column: number; types: (string | null)[] }[] };
+
+// --holes: what each typed hole needs, and the bindings in scope that fit
+export type HolesResponse = {
+  holes: { line: number; column: number; type: string | null; candidates: string[] }[];
+};
+
+// --expected-type: the type needed at a position, the bindings that have
+// it, and (`templates`) the applications to holes that give it
+export type ExpectedTypeResponse = { type: string | null; candidates: string[]; templates: string[] };
+
+// --pattern-type: the type a match at a position matches on, and the
+// constructors (with their argument counts) its patterns can use
+export type PatternTypeResponse = { type: string | null; constructors: { name: string; arity: number }[] };
+
+// --ast-partial, --ast-file (whose output has no errors)
+export type AstResponse = AstProgram;
+
+type SourcePoint = { line: number; column: number };
+
+// What a failed --json query prints on stdout besides stderr's message: the
+// typer's detail, for errors that have one. Older CLIs print nothing.
+export type NonExhaustiveDetail = {
+  kind: 'non-exhaustive-match';
+  typeName: string;
+  missing: string[];
+  location: SourcePoint;
+};
+
+// `location` to `end` is the first argument too many; `call` is where the
+// application starts
+export type OverApplicationDetail = {
+  kind: 'over-application';
+  callee: string | null;
+  arity: number;
+  location: SourcePoint;
+  end: SourcePoint;
+  call: SourcePoint;
+};
+
+export type ErrorDetail = NonExhaustiveDetail | OverApplicationDetail;
+
+export type ErrorResponse = { message: string; detail?: ErrorDetail };
+
+// --eval-expr (text only: evaluation isn't a --json query). `bindings` holds
+// the file's top-level values; older CLIs leave it out.
+export type EvaluationResponse = { value: string; type: string | null; bindings?: Record<string, string> };
+
+const isObject = (v: unknown): v is Record<string, unknown> => typeof v === 'object' && v !== null;
+
+const isString = (v: unknown): v is string => typeof v === 'string';
+
+const isTypeString = (v: unknown): v is string | null => typeof v === 'string' || v === null;
+
+function parseJson(text: string): unknown {
+  try {
+    return JSON.parse(text);
+  } catch (e) {
+    throw new Error(`invalid JSON: ${(e as Error).message}`);
+  }
+}
+
+function checked<T>(ok: boolean, value: T, what: string): T {
+  if (!ok) throw new Error(`expected ${what}`);
+  return value;
+}
+
+// The JSON that follows a `Header:` line in text output
+function afterHeader(stdout: string, header: string): unknown {
+  const lines = stdout.split(/\r?\n/);
+  const start = lines.findIndex((l) => l.trim() === header);
+  if (start < 0) throw new Error(`no '${header}' line`);
+  return parseJson(lines.slice(start + 1).join('\n'));
+}
+
+export function readTypes(stdout: string, json: boolean): TypesResponse {
+  if (json) {
+    const raw = parseJson(stdout);
+    const ok = isObject(raw) && Array.isArray(raw.types) && raw.types.every(isTypeString);
+    return checked(ok, raw as TypesResponse, '{ types: (string | null)[] }');
+  }
+  // Text: a `Types:` header, then `  <index>: <type>` per statement
+  const types: (string | null)[] = [];
+  let inTypes = false;
+  for (const line of stdout.split(/\r?\n/)) {
+    const trimmed = line.trim();
+    if (trimmed === 'Types:') {
+      inTypes = true;
+      continue;
+    }
+    if (!inTypes) continue;
+    if (!trimmed) break;
+    const type = trimmed.slice(trimmed.indexOf(':') + 1).trim();
+    types.push(type && type !== '<no type>' ? type : null);
+  }
+  return { types };
+}
+
+export function readSymbolTypes(stdout: string, json: boolean): SymbolTypesResponse {
+  const raw = json ? parseJson(stdout) : { types: afterHeader(stdout, 'Symbol types:') };
+  const ok = isObject(raw) && isObject(raw.types) && Object.values(raw.types).every(isTypeString);
+  return checked(ok, raw as SymbolTypesResponse, '{ types: Record<string, string | null> }');
+}
+
+export function readSymbolType(stdout: string, json: boolean, symbol: string): SymbolTypeResponse {
+  if (json) {
+    const raw = parseJson(stdout);
+    const ok = isObject(raw) && typeof raw.symbol === 'string' && isTypeString(raw.type);
+    return checked(ok, raw as SymbolTypeResponse, '{ symbol: string, type: string | null }');
+  }
+  const match = /has type: (.*)$/m.exec(stdout);
+  return { symbol, type: match ? match[1].trim() : null };
+}
+
+export function readExprType(stdout: string, json: boolean): ExprTypeResponse {
+  if (json) {
+    const raw = parseJson(stdout);
+    return checked(isObject(raw) && isTypeString(raw.type), raw as ExprTypeResponse, '{ type: string | null }');
+  }
+  const match = /has type: (.*)$/m.exec(stdout);
+  return { type: match ? match[1].trim() : null };
+}
+
+export function readTypeAt(stdout: string, json: boolean): TypeAtResponse {
+  if (json) {
+    const raw = parseJson(stdout);
+    const isResolution = (r: unknown) => isObject(r) && isString(r.trait) && isString(r.type);
+    const ok =
+      isObject(raw) &&
+      isTypeString(raw.name) &&
+      isTypeString(raw.type) &&
+      (raw.resolutions === undefined || (Array.isArray(raw.resolutions) && raw.resolutions.every(isResolution)));
+    return checked(ok, raw as TypeAtResponse, '{ name: string | null, type: string | null, resolutions? }');
+  }
+  const match = /^Type of '(.*)' here: (.*)$/m.exec(stdout);
+  const type = match && match[2].trim() !== '<no type information>' ? match[2].trim() : null;
+  // Then one indented `Trait Type` line per resolution
+  const resolutions = [...stdout.matchAll(/^  (\w+) (.+)$/gm)].map((m) => ({ trait: m[1], type: m[2].trim() }));
+  return { name: match ? match[1] : null, type, resolutions };
+}
+
+// Undefined rather than an Error when there's no error document, or its
+// detail is of a kind this server doesn't know: the message is on stderr
+export function readError(stdout: string): ErrorResponse | undefined {
+  let raw: unknown;
+  try {
+    raw = JSON.parse(stdout);
+  } catch {
+    return undefined;
+  }
+  if (!isObject(raw) || !isObject(raw.error) || !isString(raw.error.message)) return undefined;
+  const detail = raw.error.detail;
+  const isPoint = (p: unknown) => isObject(p) && typeof p.line === 'number' && typeof p.column === 'number';
+  const known =
+    isObject(detail) &&
+    isPoint(detail.location) &&
+    ((detail.kind === 'non-exhaustive-match' &&
+      isString(detail.typeName) &&
+      Array.isArray(detail.missing) &&
+      detail.missing.every(isString)) ||
+      (detail.kind === 'over-application' &&
+        (detail.callee === null || isString(detail.callee)) &&
+        typeof detail.arity === 'number' &&
+        isPoint(detail.end) &&
+        isPoint(detail.call)));
+  return { message: raw.error.message, detail: known ? (detail as ErrorDetail) : undefined };
+}
+
+export function readPipelineTypes(stdout: string, json: boolean): PipelineTypesResponse {
+  if (json) {
+    const raw = parseJson(stdout);
+    const isPipeline = (p: unknown) =>
+      isObject(p) &&
+      typeof p.line === 'number' &&
+      typeof p.column === 'number' &&
+      Array.isArray(p.types) &&
+      p.types.every(isTypeString);
+    const ok = isObject(raw) && Array.isArray(raw.pipelines) && raw.pipelines.every(isPipeline);
+    return checked(ok, raw as PipelineTypesResponse, '{ pipelines: { line, column, types }[] }');
+  }
+  // Text: a `Pipeline types:` header, then `  <line>:<column> <type> | <type>`
+  const pipelines = [...stdout.matchAll(/^  (\d+):(\d+) (.*)$/gm)].map((m) => ({
+    line: Number(m[1]),
+    column: Number(m[2]),
+    types: m[3].split(' | ').map((t) => (t === '?' ? null : t)),
+  }));
+  return { pipelines };
+}
+
+export function readHoles(stdout: string, json: boolean): HolesResponse {
+  if (json) {
+    const raw = parseJson(stdout);
+    const isHole = (h: unknown) =>
+      isObject(h) &&
+      typeof h.line === 'number' &&
+      typeof h.column === 'number' &&
+      isTypeString(h.type) &&
+      Array.isArray(h.candidates) &&
+      h.candidates.every(isString);
+    const ok = isObject(raw) && Array.isArray(raw.holes) && raw.holes.every(isHole);
+    return checked(ok, raw as HolesResponse, '{ holes: { line, column, type, candidates }[] }');
+  }
+  // Text: a `Holes:` header, then `  <line>:<column> <type>`, each followed
+  // by `    fits: a, b` when something does
+  const holes: HolesResponse['holes'] = [];
+  for (const line of stdout.split(/\r?\n/)) {
+    const hole = /^  (\d+):(\d+) (.*)$/.exec(line);
+    const fits = /^    fits: (.*)$/.exec(line);
+    if (hole) {
+      const type = hole[3] === '<no type information>' ? null : hole[3];
+      holes.push({ line: Number(hole[1]), column: Number(hole[2]), type, candidates: [] });
+    } else if (fits && holes.length > 0) holes[holes.length - 1].candidates = fits[1].split(', ');
+  }
+  return { holes };
+}
+
+export function readExpectedType(stdout: string, json: boolean): ExpectedTypeResponse {
+  if (json) {
+    const raw = parseJson(stdout);
+    const ok =
+      isObject(raw) &&
+      isTypeString(raw.type) &&
+      Array.isArray(raw.candidates) &&
+      raw.candidates.every(isString) &&
+      Array.isArray(raw.templates) &&
+      raw.templates.every(isString);
+    return checked(ok, raw as ExpectedTypeResponse, '{ type, candidates, templates }');
+  }
+  // Text: `Expected type: <type>`, then `  fits: a, b` and `  applied: f ?`
+  // when there are any
+  const list = (label: string) => new RegExp(`^  ${label}: (.*)$`, 'm').exec(stdout)?.[1].split(', ') ?? [];
+  const type = /^Expected type: (.*)$/m.exec(stdout)?.[1];
+  if (type === undefined) throw new Error('no expected type in output');
+  return {
+    type: type === '<no type information>' ? null : type,
+    candidates: list('fits'),
+    templates: list('applied'),
+  };
+}
+
+export function readPatternType(stdout: string, json: boolean): PatternTypeResponse {
+  if (json) {
+    const raw = parseJson(stdout);
+    const ok =
+      isObject(raw) &&
+      isTypeString(raw.type) &&
+      Array.isArray(raw.constructors) &&
+      raw.constructors.every((c) => isObject(c) && isString(c.name) && typeof c.arity === 'number');
+    return checked(ok, raw as PatternTypeResponse, '{ type, constructors }');
+  }
+  // Text: `Pattern type: <type>`, then `  <constructor> <arity>` each
+  const type = /^Pattern type: (.*)$/m.exec(stdout)?.[1];
+  if (type === undefined) throw new Error('no pattern type in output');
+  const constructors = [...stdout.matchAll(/^  (\w+) (\d+)$/gm)].map((m) => ({ name: m[1], arity: Number(m[2]) }));
+  return { type: type === '<no type information>' ? null : type, constructors };
+}
+
+export function readAst(stdout: string, json: boolean): AstResponse {
+  let raw: unknown;
+  if (json) {
+    raw = parseJson(stdout);
+  } else {
+    // Text: an `AST:` header, then the program as indented JSON
+    const lines = stdout.split(/\r?\n/);
+    const start = lines.findIndex((l) => l.trim().startsWith('{'));
+    if (start < 0) throw new Error('no AST in output');
+    raw = parseJson(lines.slice(start).join('\n'));
+  }
+  const ok = isObject(raw) && Array.isArray(raw.statements);
+  const ast = checked(ok, raw as AstResponse, '{ statements: [] }');
+  return { ...ast, errors: Array.isArray(ast.errors) ? ast.errors : [] };
+}
+
+export function readEvaluation(stdout: string): EvaluationResponse {
+  const raw = afterHeader(stdout, 'Result:');
+  const ok =
+    isObject(raw) &&
+    typeof raw.value === 'string' &&
+    isTypeString(raw.type) &&
+    (raw.bindings === undefined || (isObject(raw.bindings) && Object.values(raw.bindings).every(isString)));
+  return checked(ok, raw as EvaluationResponse, '{ value: string, type: string | null, bindings?: {} }');
+}
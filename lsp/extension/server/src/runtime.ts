@@ -0,0 +1,55 @@
+// The JS runtime the bridge runs the Noolang CLI with. node, bun and deno all
+// work, each with its own command line; NOOLANG_CLI_RUNTIME (set from the
+// `noolang.runtime` setting) picks one, otherwise the first that runs wins.
+import * as os from 'os';
+import * as path from 'path';
+
+export const RUNTIME_KINDS = ['node', 'bun', 'deno'] as const;
+export type RuntimeKind = (typeof RUNTIME_KINDS)[number];
+
+// Anything that isn't recognizably bun or deno is driven like node
+export function runtimeKind(command: string): RuntimeKind {
+  const name = path.basename(command).replace(/\.exe$/i, '');
+  return name === 'bun' || name === 'deno' ? name : 'node';
+}
+
+// deno is sandboxed: the CLI reads source files and NO_COLOR, nothing more,
+// so evaluated code that needs other permissions fails instead of running.
+// It also needs telling how to load the CLI: the .ts source uses
+// extensionless imports and the built dist is CommonJS.
+export function cliArgv(runtime: string, cliPath: string, cliArgs: string[]): string[] {
+  if (runtimeKind(runtime) !== 'deno') return [cliPath, ...cliArgs];
+  const loader = cliPath.endsWith('.ts') ? '--sloppy-imports' : '--unstable-detect-cjs';
+  return ['run', '--allow-read', '--allow-env', loader, '--quiet', cliPath, ...cliArgs];
+}
+
+// GUI-launched editors often lack the shell PATH, so look in the usual
+// install locations before falling back to the bare name.
+function installLocations(kind: RuntimeKind): string[] {
+  const home = os.homedir();
+  const homeDir = kind === 'node' ? [] : [path.join(home, `.${kind}`, 'bin', kind)];
+  return [...homeDir, `/opt/homebrew/bin/${kind}`, `/usr/local/bin/${kind}`];
+}
+
+export type RuntimeProbe = {
+  exists(path: string): boolean;
+  runs(command: string): boolean;
+};
+
+function locate(kind: RuntimeKind, probe: RuntimeProbe): string {
+  return installLocations(kind).find(probe.exists) ?? kind;
+}
+
+// `configured` is a runtime name (located like auto-detected ones) or a path
+// to an executable (used as is). Unset, the live .ts source prefers runtimes
+// that run TypeScript directly; a built dist prefers node. With nothing
+// runnable the first preference is returned, for the environment check to
+// report.
+export function resolveRuntime(configured: string | undefined, cliPath: string, probe: RuntimeProbe): string {
+  const named = RUNTIME_KINDS.find((k) => k === configured);
+  if (named) return locate(named, probe);
+  if (configured) return configured;
+  const order: RuntimeKind[] = cliPath.endsWith('.ts') ? ['bun', 'deno'] : ['node', 'bun', 'deno'];
+  const candidates = order.map((kind) => locate(kind, probe));
+  return candidates.find(probe.runs) ?? candidates[0];
+}
@@ -0,0 +1,108 @@
+// Semantic tokens for names, on top of the TextMate grammar (which already
+// colors keywords, literals and comments). Tokens come from the lossless
+// lexer, so classifying is offline and instant; the CLI's types for the
+// file's names decide what only types can tell: which bindings are
+// functions, and which functions perform effects. Scoping is by name, not by
+// binding: a name declared `mut` anywhere is a mutable name everywhere.
+import { isTrivia, lexSyntax, positionAt, SyntaxToken } from './syntax';
+import { callEffects, parseType } from './types';
+
+export const TOKEN_TYPES = ['variable', 'function', 'parameter', 'type', 'typeParameter', 'enumMember', 'property'];
+
+// `effectful` isn't a standard modifier; package.json declares it for VS Code
+export const TOKEN_MODIFIERS = ['declaration', 'modification', 'effectful'];
+
+export const SEMANTIC_LEGEND = { tokenTypes: TOKEN_TYPES, tokenModifiers: TOKEN_MODIFIERS };
+
+// A token before encoding: 0-based line and UTF-16 character
+export type SemanticToken = { line: number; character: number; length: number; type: string; modifiers: string[] };
+
+const isOp = (t: SyntaxToken | undefined, text: string) => t?.kind === 'operator' && t.text === text;
+const isKeyword = (t: SyntaxToken | undefined, ...texts: string[]) => t?.kind === 'keyword' && texts.includes(t.text);
+
+// `types` maps names to the type strings the CLI prints (--symbol-types)
+export function classifySemanticTokens(source: string, types: Record<string, string | null>): SemanticToken[] {
+  const tokens = lexSyntax(source).filter((t) => !isTrivia(t));
+  const mutable = new Set<string>();
+  const parameters = new Set<string>();
+  const typeNames = new Set<string>();
+  const syntacticFunctions = new Set<string>();
+  tokens.forEach((t, i) => {
+    const next = tokens[i + 1];
+    if (next?.kind !== 'identifier') return;
+    if (isKeyword(t, 'mut', 'mut!')) mutable.add(next.text);
+    if (isKeyword(t, 'variant', 'type', 'constraint', 'implement')) typeNames.add(next.text);
+    if (t.kind === 'identifier' && isOp(next, '=') && isKeyword(tokens[i + 2], 'fn')) syntacticFunctions.add(t.text);
+  });
+
+  const result: SemanticToken[] = [];
+  let inParams = false;
+  // Bracket depth at which a `: Type` annotation started, while inside one
+  let annotationDepth: number | undefined;
+  let depth = 0;
+  tokens.forEach((t, i) => {
+    const prev = tokens[i - 1];
+    const next = tokens[i + 1];
+    if (t.kind === 'punctuation') {
+      if ('([{'.includes(t.text)) depth++;
+      if (')]}'.includes(t.text)) depth--;
+      // An annotation ends at its closing bracket or the next `,`/`;` beside it
+      const separator = (t.text === ';' || t.text === ',') && depth === annotationDepth;
+      if (annotationDepth !== undefined && (depth < annotationDepth || separator)) annotationDepth = undefined;
+      if (t.text === ':') annotationDepth = depth;
+    }
+    if (isKeyword(t, 'fn')) inParams = true;
+    if (isOp(t, '=>')) inParams = false;
+    if (isOp(t, '=') && annotationDepth !== undefined && depth === annotationDepth) annotationDepth = undefined;
+
+    const position = positionAt(source, t.start);
+    const push = (type: string, modifiers: string[] = []) =>
+      result.push({ ...position, length: t.end - t.start, type, modifiers });
+
+    if (t.kind === 'accessor') return push('property');
+    if (t.kind !== 'identifier') return;
+    const name = t.text;
+    // `mut! x = …` assigns an existing binding
+    const assigned = isOp(next, '=') && !isKeyword(prev, 'mut!');
+    const declaration = assigned || isKeyword(prev, 'variant', 'type', 'constraint');
+    if (/^[A-Z]/.test(name)) {
+      const isType = annotationDepth !== undefined || typeNames.has(name) || isKeyword(prev, 'implement');
+      return push(isType ? 'type' : 'enumMember', declaration ? ['declaration'] : []);
+    }
+    if (annotationDepth !== undefined) return push('typeParameter');
+    if (inParams) {
+      parameters.add(name);
+      return push('parameter', ['declaration']);
+    }
+    const modifiers = declaration ? ['declaration'] : [];
+    if (mutable.has(name)) modifiers.push('modification');
+    const typeText = types[name];
+    const type = typeText ? parseType(typeText) : undefined;
+    if (typeText && callEffects(typeText).length > 0) modifiers.push('effectful');
+    const underlying = type?.kind === 'constrained' ? type.type : type;
+    if (underlying?.kind === 'function' || (!type && syntacticFunctions.has(name))) return push('function', modifiers);
+    if (!(name in types) && parameters.has(name)) return push('parameter', modifiers);
+    return push('variable', modifiers);
+  });
+  return result;
+}
+
+// The LSP's relative encoding: five integers per token
+export function encodeSemanticTokens(tokens: SemanticToken[]): number[] {
+  const data: number[] = [];
+  let line = 0;
+  let character = 0;
+  for (const t of tokens) {
+    const deltaLine = t.line - line;
+    data.push(
+      deltaLine,
+      deltaLine === 0 ? t.character - character : t.character,
+      t.length,
+      TOKEN_TYPES.indexOf(t.type),
+      t.modifiers.reduce((bits, m) => bits | (1 << TOKEN_MODIFIERS.indexOf(m)), 0),
+    );
+    line = t.line;
+    character = t.character;
+  }
+  return data;
+}
@@ -6,8 +6,11 @@ import {
   TextDocumentSyncKind,
   Diagnostic,
   DiagnosticSeverity,
+  DiagnosticTag,
   CompletionItem,
   CompletionItemKind,
+  CompletionItemTag,
+  InsertTextFormat,
   Hover,
   MarkupKind,
   Location,
@@ -15,30 +18,264 @@ import {
   Range,
   SymbolInformation,
   SymbolKind,
+  SymbolTag,
+  DocumentSymbol,
   CompletionParams,
   HoverParams,
   DefinitionParams,
+  ImplementationParams,
   ReferenceParams,
   DocumentSymbolParams,
+  WorkspaceSymbol,
+  WorkspaceSymbolParams,
   DidOpenTextDocumentParams,
   DidChangeTextDocumentParams,
   DidSaveTextDocumentParams,
+  DidCloseTextDocumentParams,
+  DidChangeConfigurationParams,
+  CodeAction,
+  CodeActionKind,
+  CodeActionParams,
+  TextEdit,
+  WorkspaceEdit,
+  ExecuteCommandParams,
+  InlineValue,
+  InlayHint,
+  InlayHintKind,
+  InlayHintParams,
+  InlineValueParams,
+  InlineValueText,
+  InlineValueVariableLookup,
+  NotebookCellKind,
+  NotebookCell as LspNotebookCell,
+  DidOpenNotebookDocumentParams,
+  DidChangeNotebookDocumentParams,
+  DidCloseNotebookDocumentParams,
+  CodeLens,
+  CodeLensParams,
+  CodeLensRefreshRequest,
+  DocumentFormattingParams,
+  SemanticTokens,
+  SemanticTokensParams,
+  PrepareRenameParams,
+  CreateFilesParams,
+  FileOperationPatternKind,
+  DeleteFilesParams,
+  RenameParams,
+  SignatureHelp,
+  SignatureHelpParams,
+  ParameterInformation,
+  SignatureInformation,
+  TextDocumentEdit,
+  AnnotatedTextEdit,
+  ChangeAnnotation,
+  ResponseError,
+  ErrorCodes,
+  LSPErrorCodes,
+  CancellationToken,
+  ResultProgressReporter,
 } from 'vscode-languageserver/node';
 import { URI } from 'vscode-uri';
 import * as fs from 'fs';
+import * as os from 'os';
 import * as path from 'path';
+import { createHash } from 'crypto';
 import { spawnSync } from 'child_process';
+import { organizeDefinitions } from './organize';
+import { formatSource } from './format';
+import { hostedServer } from './host';
+import { connectPipe, listenTcp, pipePath } from './transport';
+import { parseServerArgs, ServerArgs, USAGE } from './args';
+import { createLogger } from './logger';
+import { createSupervisor } from './supervisor';
+import { checkEnvironment } from './environment';
+import { DEFAULT_SETTINGS, mergeSettings, Settings } from './settings';
+import { createDocumentStore, DocumentEntry } from './documents';
+import { createLruCache } from './cache';
+import { isTrivia, lexSyntax, offsetAt, parseSyntax, positionAt, SyntaxToken, useLexicon } from './syntax';
+import { cliArgv, resolveRuntime, runtimeKind } from './runtime';
+import {
+  callEffects,
+  EFFECT_DESCRIPTIONS,
+  formatTypeString,
+  fromCompilerType,
+  parseType,
+  RenderOptions,
+  TypeAlias,
+  typeExpansions,
+} from './types';
+import {
+  AstNode,
+  AstProgram,
+  containsPosition,
+  Expression,
+  forEachNode as walkNodes,
+  nameLocation,
+  nodeName,
+  outermost,
+  SourceLocation,
+  SourcePosition,
+  syntaxTree,
+  topLevelStatements,
+} from './ast';
+import {
+  EvaluationResponse,
+  ExpectedTypeResponse,
+  HolesResponse,
+  NonExhaustiveDetail,
+  OverApplicationDetail,
+  PatternTypeResponse,
+  PipelineTypesResponse,
+  readAst,
+  readError,
+  readEvaluation,
+  readExpectedType,
+  readExprType,
+  readHoles,
+  readPatternType,
+  readPipelineTypes,
+  readSymbolType,
+  readSymbolTypes,
+  readTypeAt,
+  readTypes,
+  TypeAtResponse,
+} from './responses';
+import { CliCapabilities, legacyCapabilities, missingFeatures, parseCapabilities } from './capabilities';
+import { AnalysisBackend, CliResult, createInProcessBackend, createSpawnBackend, queriesModulePath } from './backend';
+import { createReplSessions } from './repl';
+import { benchmarkReport, parseBenchmark } from './benchmark';
+import { destructureBinding } from './destructure';
+import { callSiteAt, recordParameterAt, SpanEdit } from './record-params';
+import { findTestSites, isTestFile, matchResults, parseTestReport, TestReport } from './tests';
+import { parseViewUri, REFRESHED_ON, View, ViewKind, viewUri } from './views';
+import { CellSpan, combineCells, createNotebookStore, NotebookEntry, spanAtLine } from './notebooks';
+import { annotatedExpression, effectfulCalls, omittedEffects } from './effects';
+import { classifySemanticTokens, encodeSemanticTokens, SEMANTIC_LEGEND } from './semantic';
+import { LspError } from './errors';
+import { createTelemetry } from './telemetry';
+import { lineMap, LineMap } from './positions';
+import { createProfiler, profilingEnabled } from './profile';
+import { indexCacheDir, indexCacheFile, loadIndex, saveIndex } from './index-cache';
+import {
+  createWorkspaceIndex,
+  Declaration,
+  deprecatedNames,
+  importAt,
+  importCycles,
+  importSpecifier,
+  implementationsOf,
+  IndexedFile,
+  indexText,
+  isUnder,
+  Span,
+} from './workspace';
+import {
+  CompilerToken,
+  conflictingFiles,
+  fieldClashes,
+  invalidRename,
+  nameClashes,
+  parseCliTokens,
+  planRename,
+  RenamePlan,
+  renameTargetAt,
+  syntaxTokens,
+} from './rename';
+import { documentOutline, flattenOutline, OutlineKind, OutlineSymbol } from './symbols';
+import { BuiltinInfo, builtinNamed, builtinParameters, BUILTINS } from './builtins';
+import { applicationGroupings, definedArity } from './precedence';
+import { lambdaAt, lambdaRewrites } from './lambdas';
+import { pipelineOperators } from './pipelines';
+import { callAt } from './signatures';
+import { rankSymbols } from './fuzzy';
+import { inPatternPosition } from './patterns';
+import { duplicateFields, recordEntryAt, recordFields } from './records';
+
+// The extension's package.json sits two levels above out/server/server.js
+function serverVersion(): string {
+  try {
+    return JSON.parse(fs.readFileSync(path.join(__dirname, '..', '..', 'package.json'), 'utf8')).version;
+  } catch {
+    return '0.0.0';
+  }
+}
+
+const SERVER_VERSION = serverVersion();
+
+function readArgs(): ServerArgs {
+  try {
+    return parseServerArgs(process.argv.slice(2));
+  } catch (e) {
+    process.stderr.write(`${(e as Error).message}\n\n${USAGE}\n`);
+    process.exit(1);
+  }
+}
+
+// A program hosting the server (host.ts) gives what the command line and
+// environment otherwise would
+const hosted = hostedServer();
+const args = hosted
+  ? { ...parseServerArgs([]), ...(hosted.logLevels && { logLevels: hosted.logLevels }) }
+  : readArgs();
+if (args.version) {
+  process.stdout.write(`noolang-lsp ${SERVER_VERSION}\n`);
+  process.exit(0);
+}
+if (args.help) {
+  process.stdout.write(`${USAGE}\n`);
+  process.exit(0);
+}
+
+function createServerConnection() {
+  if (hosted) return createConnection(ProposedFeatures.all, hosted.input, hosted.output);
+  const transport = args.transport;
+  if (transport.kind === 'tcp') {
+    const { input, output } = listenTcp(transport.host, transport.port, (address) =>
+      process.stderr.write(`Noolang LSP listening on ${address}\n`),
+    );
+    return createConnection(ProposedFeatures.all, input, output);
+  }
+  if (transport.kind === 'pipe') {
+    const { input, output } = connectPipe(transport.name, (error) => {
+      process.stderr.write(`Noolang LSP could not connect to ${pipePath(transport.name)}: ${error.message}\n`);
+      process.exit(1);
+    });
+    return createConnection(ProposedFeatures.all, input, output);
+  }
+  if (transport.kind === 'library') return createConnection(ProposedFeatures.all);
+  return createConnection(ProposedFeatures.all, process.stdin, process.stdout);
+}
 
-const connection = createConnection(ProposedFeatures.all);
+const connection = createServerConnection();
+const log = createLogger(args.logLevels, connection.console, args.logFile, hosted?.log);
+const bridgeLog = log.child('bridge');
+// $/logTrace for bridge calls and cache hits; the library follows the level
+// the client sets in initialize and with $/setTrace
+const tracer = connection.tracer;
+const telemetry = createTelemetry();
+const profiler = createProfiler(profilingEnabled(process.env));
+// Every AST walk is a profiled span
+const forEachNode = profiler.profiled('ast walk', walkNodes);
 
-// Very simple in-memory document store (FULL sync)
-const documents = new Map<string, string>(); // key: uri
+let settings: Settings = mergeSettings(DEFAULT_SETTINGS, hosted?.settings);
+
+// Open documents (FULL sync), each with its own lock
+const documents = createDocumentStore();
 
 // Env from client
-const WORKSPACE = process.env.NOOLANG_WORKSPACE || '';
-const CLI_PATH = process.env.NOOLANG_CLI_PATH || path.join(WORKSPACE || '.', 'dist', 'cli.js');
-// bun for live .ts source, node for a built dist bundle (see extension.ts)
-const CLI_RUNTIME = process.env.NOOLANG_CLI_RUNTIME || 'node';
+const WORKSPACE = hosted?.workspace ?? (process.env.NOOLANG_WORKSPACE || '');
+const CLI_PATH = hosted?.cliPath ?? (process.env.NOOLANG_CLI_PATH || path.join(WORKSPACE || '.', 'dist', 'cli.js'));
+
+// `<runtime> --version` output, or undefined if the runtime can't be run
+function runtimeVersion(runtime: string): string | undefined {
+  const res = spawnSync(runtime, ['--version'], { encoding: 'utf8' });
+  return res.error || res.status !== 0 ? undefined : res.stdout;
+}
+
+const CLI_RUNTIME = resolveRuntime(hosted?.runtime ?? (process.env.NOOLANG_CLI_RUNTIME || undefined), CLI_PATH, {
+  exists: fs.existsSync,
+  runs: (command) => runtimeVersion(command) !== undefined,
+});
 
 function uriToFilePath(uri: string): string | undefined {
   try {
@@ -48,33 +285,208 @@ function uriToFilePath(uri: string): string | undefined {
   }
 }
 
-function runNodeCli(args: string[]) {
-  return spawnSync(CLI_RUNTIME, [CLI_PATH, ...args], { encoding: 'utf8' });
+// For requests, which can't do anything with a URI that isn't a file
+function filePathOf(uri: string): string {
+  const filePath = uriToFilePath(uri);
+  if (!filePath) throw new LspError('invalid-params', `not a file URI: ${uri}`);
+  return filePath;
 }
 
-// --- Bridge helpers (ported from Rust logic) ---
-function parseTypesOutput(output: string): string[] {
-  const types: string[] = [];
-  let inTypes = false;
-  for (const line of output.split(/\r?\n/)) {
-    const trimmed = line.trim();
-    if (trimmed === 'Types:') {
-      inTypes = true;
-      continue;
-    }
-    if (inTypes) {
-      if (!trimmed) {
-        inTypes = false;
-        continue;
-      }
-      const idx = trimmed.indexOf(':');
-      if (idx >= 0 && idx + 1 < trimmed.length) {
-        const typeInfo = trimmed.slice(idx + 1).trim();
-        if (typeInfo) types.push(typeInfo);
-      }
+type BridgeError = { message: string; flag: string; time: string };
+
+let lastBridgeError: BridgeError | undefined;
+
+function recordBridgeError(flag: string, message: string) {
+  lastBridgeError = { message, flag, time: new Date().toISOString() };
+}
+
+const supervisor = createSupervisor();
+
+function backingOff(flag: string): CliResult {
+  const error = new Error(`bridge is backing off after ${supervisor.state().consecutiveFailures} failures`);
+  tracer.log(`bridge ${flag} skipped: ${error.message}`);
+  return { stdout: '', stderr: '', status: null, signal: null, error, timedOut: false };
+}
+
+// A non-zero exit is the CLI reporting a parse/type error, which is normal;
+// a spawn error or a kill (signal, timeout) means the bridge itself is broken.
+function onBridgeFailure(flag: string, message: string) {
+  bridgeLog.error(`CLI call failed: ${message}`, { runtime: CLI_RUNTIME, cli: CLI_PATH, flag });
+  recordBridgeError(flag, message);
+  if (supervisor.recordFailure(Date.now())) {
+    connection.window
+      .showErrorMessage(
+        `Noolang CLI keeps failing (${message}). Language features are paused with backoff.`,
+        { title: 'Restart bridge' },
+      )
+      .then((choice) => {
+        if (choice) commands['noolang.restartBridge']([]);
+      });
+  }
+}
+
+type CliOptions = {
+  timeoutMs?: number;
+  // The call runs user code (evaluation), so hitting the timeout says
+  // something about the program, not about the bridge's health.
+  runsUserCode?: boolean;
+};
+
+const spawnBackend = createSpawnBackend(CLI_RUNTIME, CLI_PATH);
+let backend: AnalysisBackend = spawnBackend;
+
+function selectBackend(kind: Settings['backend']) {
+  if (kind === backend.kind) return;
+  if (kind === 'spawn') {
+    backend = spawnBackend;
+  } else {
+    const modulePath = queriesModulePath(CLI_PATH);
+    if (!modulePath) {
+      log.warn(`In-process backend needs a built compiler next to ${CLI_PATH}; using spawn`);
+      return;
     }
+    backend = createInProcessBackend(modulePath, spawnBackend);
+  }
+  // Results are the same either way, but drop them so the switch is visible
+  astCache.clear();
+  typesCache.clear();
+  log.info(`Analysis backend: ${backend.kind}`);
+}
+
+// Unknown until the startup probe answers; until then every flag is tried
+let cliCapabilities: CliCapabilities | undefined;
+
+const supports = (flag: string) => !cliCapabilities || cliCapabilities.flags.has(flag);
+
+async function probeCapabilities() {
+  const res = await runNodeCli(['--capabilities']);
+  // A bridge that can't run at all is reported by the environment check
+  if (res.error || res.signal) return;
+  cliCapabilities = (res.status === 0 ? parseCapabilities(res.stdout) : undefined) ?? legacyCapabilities();
+  bridgeLog.info(`CLI ${cliCapabilities.version ?? '(no --capabilities)'} supports ${[...cliCapabilities.flags].join(' ')}`);
+  if (cliCapabilities.lexicon) useLexicon(cliCapabilities.lexicon);
+  const missing = missingFeatures(cliCapabilities);
+  if (missing.length > 0) {
+    const message = `The Noolang CLI at ${CLI_PATH} is too old for: ${missing.join(', ')}. Update it to enable them.`;
+    log.warn(message);
+    connection.window.showWarningMessage(message);
+  }
+}
+
+// Queries answer with JSON when the CLI has --json (see responses.ts)
+const withJson = (cliArgs: string[]) => (supports('--json') ? ['--json', ...cliArgs] : cliArgs);
+
+const cliFlag = (cliArgs: string[]) => (cliArgs[0] === '--json' ? cliArgs[1] : cliArgs[0]);
+
+// The queries (the CLI's queries.ts) that type a file, following its imports
+const TYPING_QUERIES = new Set([
+  '--types-file',
+  '--symbol-type',
+  '--symbol-types',
+  '--expr-type',
+  '--type-at',
+  '--pipeline-types',
+  '--holes',
+  '--expected-type',
+  '--pattern-type',
+]);
+
+// One of a notebook's combined file resolves imports from the notebook's
+// directory (see Notebooks)
+function withImportBase(cliArgs: string[]): string[] {
+  const flag = cliFlag(cliArgs);
+  const base = notebookImportBases.get(cliArgs[cliArgs.indexOf(flag) + 1]);
+  const typing = TYPING_QUERIES.has(flag) && supports('--import-base');
+  return base && typing ? ['--import-base', base, ...cliArgs] : cliArgs;
+}
+
+async function runNodeCli(queryArgs: string[], options: CliOptions = {}): Promise<CliResult> {
+  const flag = cliFlag(queryArgs);
+  if (!supports(flag)) {
+    const error = new Error(`the CLI does not support ${flag}`);
+    return { stdout: '', stderr: '', status: null, signal: null, error, timedOut: false };
+  }
+  const cliArgs = withImportBase(queryArgs);
+  if (!supervisor.canCall(Date.now())) return backingOff(flag);
+  const started = Date.now();
+  const timeoutMs = options.timeoutMs ?? settings.timeouts.navigationMs;
+  const res = await profiler.measureAsync(`cli ${flag}`, () => backend.run(cliArgs, timeoutMs));
+  const ms = Date.now() - started;
+  telemetry.record('bridge', flag, ms);
+  // A timeout is a miss for this request (a slow file, or user code that runs
+  // long), not a broken bridge, so it doesn't hold off every other request
+  if (res.timedOut) {
+    bridgeLog.info(`${flag} timed out${options.runsUserCode ? ' running user code' : ''}`, { ms });
+  } else if (res.error || res.signal) {
+    onBridgeFailure(flag, res.error?.message ?? `killed by ${res.signal}`);
+  } else {
+    supervisor.recordSuccess();
+    bridgeLog.debug(`${flag} exited ${res.status}`, { ms });
+  }
+  const command = [CLI_RUNTIME, ...cliArgv(CLI_RUNTIME, CLI_PATH, cliArgs)].map((a) => JSON.stringify(a)).join(' ');
+  const outcome = res.error ? 'failed' : `exited ${res.status}`;
+  tracer.log(`bridge ${flag} ${outcome} in ${ms}ms`, `${backend.kind}: ${command}`);
+  return res;
+}
+
+// Whole-file results depend only on the file on disk (plus, for type checks,
+// its imports — see onDidChangeWatchedFiles), so they're cached against the
+// file's mtime and size. Only completed runs are kept; failures and timeouts
+// are retried next time.
+const astCache = createLruCache<CliResult>(settings.cache);
+const typesCache = createLruCache<CliResult>(settings.cache);
+
+function fileStamp(filePath: string): string | undefined {
+  try {
+    const st = fs.statSync(filePath);
+    return `${st.mtimeMs}:${st.size}`;
+  } catch {
+    return undefined;
+  }
+}
+
+// `cliArgs` is a query of `filePath` alone, e.g. ['--types-file', filePath]
+async function runFileCli(
+  cache: typeof astCache,
+  filePath: string,
+  cliArgs: string[],
+  timeoutMs?: number,
+): Promise<CliResult> {
+  const stamp = fileStamp(filePath);
+  const key = `${cliArgs.join('\0')}\0${stamp}`;
+  const hit = stamp ? cache.get(key) : undefined;
+  if (hit) {
+    tracer.log(`cache hit: ${cliFlag(cliArgs)} ${filePath}`);
+    return hit;
+  }
+  const res = await runNodeCli(cliArgs, { timeoutMs });
+  if (stamp && !res.error && !res.signal) {
+    cache.set(key, res, Buffer.byteLength(res.stdout) + Buffer.byteLength(res.stderr));
+  }
+  return res;
+}
+
+// --- Bridge helpers (ported from Rust logic) ---
+
+// The typed response of a successful query; undefined when the CLI rejected
+// the program (a parse or type error). Throws when the query itself failed:
+// the bridge broke, timed out or answered with output that doesn't match the
+// model.
+function readResponse<T>(cliArgs: string[], res: CliResult, read: (stdout: string, json: boolean) => T): T | undefined {
+  const flag = cliFlag(cliArgs);
+  const file = cliArgs.find((a) => a.endsWith('.noo'));
+  const context = file ? `${flag} ${path.basename(file)}` : flag;
+  if (res.timedOut) throw new LspError('timeout', 'the CLI took too long and was stopped', context);
+  if (!supports(flag)) throw new LspError('unsupported', `the CLI at ${CLI_PATH} does not support ${flag}`, context);
+  if (res.error || res.signal) throw new LspError('bridge', res.error?.message ?? `killed by ${res.signal}`, context);
+  if (res.status !== 0) return undefined;
+  try {
+    return profiler.measure(`parse ${flag}`, () => read(res.stdout, cliArgs[0] === '--json'));
+  } catch (e) {
+    bridgeLog.warn(`Unreadable ${flag} output: ${(e as Error).message}`);
+    recordBridgeError(flag, (e as Error).message);
+    throw new LspError('unreadable-output', (e as Error).message, context);
   }
-  return types;
 }
 
 function cleanErrorMessage(text: string): string {
@@ -119,9 +531,26 @@ function extractLineColumn(line: string): { line: number; column: number } | und
   return undefined;
 }
 
-function getDiagnostics(filePath: string): Diagnostic[] {
-  const result = runNodeCli(['--types-file', filePath]);
+async function getDiagnostics(filePath: string): Promise<Diagnostic[]> {
+  const timeoutMs = settings.timeouts.diagnosticsMs;
+  const result = await runFileCli(typesCache, filePath, withJson(['--types-file', filePath]), timeoutMs);
   if (result.status === 0) return []; // clean typecheck — stdout is a Types: dump, not an error
+  if (result.timedOut) {
+    return [
+      {
+        range: Range.create(0, 0, 0, 0),
+        severity: DiagnosticSeverity.Warning,
+        source: 'noolang',
+        message: `Noolang analysis timed out after ${timeoutMs}ms; type errors in this file are not shown.`,
+      },
+    ];
+  }
+  const error = readError(result.stdout || '');
+  const detail = error?.detail;
+  if (detail?.kind === 'non-exhaustive-match') return [nonExhaustiveDiagnostic(filePath, detail)];
+  if (error && detail?.kind === 'over-application') {
+    return [overApplicationDiagnostic(detail, cleanErrorMessage(error.message))];
+  }
   const stdout = result.stdout || '';
   const stderr = result.stderr || '';
   const raw = stderr.trim() ? stderr : stdout;
@@ -148,48 +577,188 @@ function getDiagnostics(filePath: string): Diagnostic[] {
   const l = (loc?.line ?? 1) - 1; // 0-based
   const c = (loc?.column ?? 1) - 1;
 
-  return [
+  const diagnostic: Diagnostic = {
+    range: {
+      start: { line: l, character: c },
+      end: { line: l, character: c + 1 },
+    },
+    severity: sev,
+    source: 'noolang',
+    message: cleanErrorMessage(raw),
+  };
+  const effects = omittedEffects(diagnostic.message);
+  return effects ? optional(effectDiagnostics(filePath, diagnostic, effects), [diagnostic]) : [diagnostic];
+}
+
+// At the `match` keyword, listing the constructors it leaves out, with the
+// variant's declaration as related information when it can be found
+function nonExhaustiveDiagnostic(filePath: string, detail: NonExhaustiveDetail): Diagnostic {
+  const { typeName, missing, location } = detail;
+  const start = { line: location.line - 1, character: location.column - 1 };
+  const declaration = declarationIn([filePath, ...importedModules(filePath)], typeName, 'type');
+  return {
+    range: Range.create(start, { line: start.line, character: start.character + 'match'.length }),
+    severity: DiagnosticSeverity.Error,
+    source: 'noolang',
+    code: 'non-exhaustive-match',
+    message: `Non-exhaustive match on ${typeName}: missing ${missing.map((c) => `\`${c}\``).join(', ')}`,
+    relatedInformation: declaration && [{ location: declaration, message: `${typeName} is declared here` }],
+  };
+}
+
+// At the first argument too many rather than at the call; `data` has where
+// the call starts, for the quick fixes that regroup it
+function overApplicationDiagnostic(detail: OverApplicationDetail, message: string): Diagnostic {
+  const point = ({ line, column }: { line: number; column: number }) => Position.create(line - 1, column - 1);
+  return {
+    range: Range.create(point(detail.location), point(detail.end)),
+    severity: DiagnosticSeverity.Error,
+    source: 'noolang',
+    code: 'over-application',
+    message,
+    data: { call: point(detail.call) },
+  };
+}
+
+const effectList = (effects: string[]) => effects.map((e) => `!${e}`).join(' ');
+
+// An annotation that leaves out effects is reported at each call performing
+// one, with the annotation as related information; when no call is found
+// (an effect from a local function, say) the checker's error stays as is.
+async function effectDiagnostics(filePath: string, error: Diagnostic, effects: string[]): Promise<Diagnostic[]> {
+  const base = { ...error, source: 'noolang-effects', code: 'undeclared-effect' };
+  const source = fs.existsSync(filePath) ? fs.readFileSync(filePath, 'utf8') : '';
+  const found = annotatedExpression(source, offsetAt(source, error.range.start));
+  if (!found) return [base];
+  const uri = URI.file(filePath).toString();
+  const toRange = (span: Span) => Range.create(positionAt(source, span.start), positionAt(source, span.end));
+  const annotation = source.slice(found.annotation.start, found.annotation.end);
+  const relatedInformation = [
     {
-      range: {
-        start: { line: l, character: c },
-        end: { line: l, character: c + 1 },
-      },
-      severity: sev,
-      source: 'noolang',
-      message: cleanErrorMessage(raw),
+      location: { uri, range: toRange(found.annotation) },
+      message: `Annotated as \`${annotation}\`, without ${effectList(effects)}`,
     },
   ];
+  const timeoutMs = settings.timeouts.diagnosticsMs;
+  const names = [...new Set(found.tokens.filter((t) => t.kind === 'identifier').map((t) => t.text))];
+  const types = { ...(await namedTypes(filePath, names, timeoutMs)) };
+  // The file doesn't type check, so its table is usually empty: prelude
+  // functions can still be typed on their own
+  const unknown = names.filter((name) => !types[name]).slice(0, 20);
+  const lookups = await Promise.all(unknown.map((name) => getExpressionTypes(name, timeoutMs)));
+  unknown.forEach((name, i) => (types[name] = lookups[i][0] ?? null));
+  const calls = effectfulCalls(found.tokens, effects, types);
+  if (calls.length === 0) return [{ ...base, relatedInformation }];
+  return calls.map((call) => ({
+    ...base,
+    range: toRange(call),
+    message: `\`${call.name}\` performs ${effectList(call.effects)}, which the type annotation doesn't declare`,
+    relatedInformation,
+  }));
 }
 
-function getTypeInfo(filePath: string): string[] {
-  const res = runNodeCli(['--types-file', filePath]);
-  if (res.status === 0) return parseTypesOutput(res.stdout || '');
-  return [];
+async function getTypeInfo(filePath: string, timeoutMs?: number): Promise<(string | null)[]> {
+  const cliArgs = withJson(['--types-file', filePath]);
+  const res = await runFileCli(typesCache, filePath, cliArgs, timeoutMs);
+  return readResponse(cliArgs, res, readTypes)?.types ?? [];
 }
 
-function getExpressionTypes(expr: string): string[] {
-  const res = runNodeCli(['--types', expr]);
-  if (res.status === 0) return parseTypesOutput(res.stdout || '');
-  return [];
+async function getExpressionTypes(expr: string, timeoutMs?: number): Promise<(string | null)[]> {
+  const cliArgs = withJson(['--types', expr]);
+  const res = await runNodeCli(cliArgs, { timeoutMs });
+  return readResponse(cliArgs, res, readTypes)?.types ?? [];
 }
 
-function getAstFile(filePath: string): any | undefined {
-  const res = runNodeCli(['--ast-file', filePath]);
-  if (res.status !== 0) return undefined;
-  const lines = (res.stdout || '').split(/\r?\n/);
-  const start = lines.findIndex((l) => l.trim().startsWith('{'));
-  if (start >= 0) {
-    try {
-      return JSON.parse(lines.slice(start).join('\n'));
-    } catch {}
+// The type a reference has where it's used: a polymorphic binding's type
+// variables instantiated for that call. Undefined off a reference.
+async function getTypeAt(filePath: string, line1: number, col1: number): Promise<TypeAtResponse | undefined> {
+  if (!supports('--type-at')) return undefined;
+  const cliArgs = withJson(['--type-at', filePath, String(line1), String(col1)]);
+  const res = await runFileCli(typesCache, filePath, cliArgs, settings.timeouts.hoverMs);
+  return readResponse(cliArgs, res, readTypeAt);
+}
+
+// Types an arbitrary expression against the file's top-level scope.
+async function getExpressionTypeInFile(filePath: string, expr: string): Promise<string | undefined> {
+  const cliArgs = withJson(['--expr-type', filePath, expr]);
+  const res = await runNodeCli(cliArgs, { timeoutMs: settings.timeouts.hoverMs });
+  return readResponse(cliArgs, res, readExprType)?.type ?? undefined;
+}
+
+// Types for many symbols in one CLI round trip. With no names, types every
+// top-level definition of the file; that table is cached per file version,
+// so hovering around a file costs one type check rather than one per symbol.
+async function getSymbolTypes(
+  filePath: string,
+  names: string[] = [],
+  timeoutMs?: number,
+): Promise<Record<string, string | null>> {
+  const cliArgs = withJson(['--symbol-types', filePath, ...names]);
+  const res =
+    names.length === 0
+      ? await runFileCli(typesCache, filePath, cliArgs, timeoutMs)
+      : await runNodeCli(cliArgs, { timeoutMs });
+  return readResponse(cliArgs, res, readSymbolTypes)?.types ?? {};
+}
+
+// Types for the names a piece of code uses: the file's top-level table, and
+// one lookup for the rest (prelude and imported functions). Empty without
+// --symbol-types.
+async function namedTypes(
+  filePath: string,
+  names: string[],
+  timeoutMs?: number,
+): Promise<Record<string, string | null>> {
+  if (!supports('--symbol-types')) return {};
+  const types = await getSymbolTypes(filePath, [], timeoutMs);
+  const others = [...new Set(names)].filter((name) => /^[a-z_]/.test(name) && !(name in types)).slice(0, 200);
+  return others.length > 0 ? { ...(await getSymbolTypes(filePath, others, timeoutMs)), ...types } : types;
+}
+
+// A top-level definition comes from the file's cached table; anything else
+// (prelude and trait functions) is looked up on its own.
+async function getSymbolType(filePath: string, name: string, timeoutMs?: number): Promise<string | undefined> {
+  if (!supports('--symbol-types')) return getSymbolTypeLegacy(filePath, name, timeoutMs);
+  const all = await getSymbolTypes(filePath, [], timeoutMs);
+  const type = Object.prototype.hasOwnProperty.call(all, name)
+    ? all[name]
+    : (await getSymbolTypes(filePath, [name], timeoutMs))[name];
+  return type ?? undefined;
+}
+
+// For CLIs without --symbol-types: one call per symbol
+async function getSymbolTypeLegacy(filePath: string, name: string, timeoutMs?: number): Promise<string | undefined> {
+  const cliArgs = withJson(['--symbol-type', filePath, name]);
+  const res = await runNodeCli(cliArgs, { timeoutMs });
+  return readResponse(cliArgs, res, (stdout, json) => readSymbolType(stdout, json, name))?.type ?? undefined;
+}
+
+// Evaluates an expression against the file's top-level scope. The CLI
+// refuses programs that perform effects, and a runaway evaluation is killed
+// after settings.timeouts.evaluateMs.
+async function evaluateExpressionInFile(
+  filePath: string,
+  expr: string,
+): Promise<EvaluationResponse | { error: string }> {
+  const cliArgs = ['--eval-expr', filePath, expr];
+  const timeoutMs = settings.timeouts.evaluateMs;
+  const res = await runNodeCli(cliArgs, { timeoutMs, runsUserCode: true });
+  if (res.timedOut) {
+    return { error: `Evaluation timed out after ${timeoutMs}ms` };
   }
-  return undefined;
+  if (res.status !== 0) {
+    return { error: cleanErrorMessage(res.stderr || res.stdout || 'Evaluation failed') };
+  }
+  return readResponse(cliArgs, res, readEvaluation) ?? { error: 'Could not read evaluation result' };
 }
 
-function simplifyTypeString(typeStr: string): string {
-  if (typeStr.startsWith('{ ') && typeStr.includes(': ') && typeStr.length > 50) return 'Record';
-  if (typeStr.includes(' -> ')) return typeStr.replace(/ -> /g, ' → ');
-  return typeStr;
+// Partial when the file has syntax errors: every top-level statement that
+// parses is kept, so navigation works for the rest of the file.
+async function getAstFile(filePath: string, timeoutMs?: number): Promise<AstProgram | undefined> {
+  // Older CLIs only parse whole files, so a syntax error loses navigation
+  const cliArgs = supports('--ast-partial') ? withJson(['--ast-partial', filePath]) : ['--ast-file', filePath];
+  const res = await runFileCli(astCache, filePath, cliArgs, timeoutMs);
+  return readResponse(cliArgs, res, readAst);
 }
 
 function extractIdentifierAtPosition(line: string, column: number): string | undefined {
@@ -216,178 +785,439 @@ function extractExpressionAtPosition(line: string, column: number): string | und
   return start < end ? chars.slice(start, end).join('') : undefined;
 }
 
-function positionWithinRange(
-  targetLine: number,
-  targetCol: number,
-  startLine: number,
-  startCol: number,
-  endLine: number,
-  endCol: number,
-): boolean {
-  if (targetLine < startLine || targetLine > endLine) return false;
-  if (targetLine === startLine && targetCol < startCol) return false;
-  if (targetLine === endLine && targetCol > endCol) return false;
-  return true;
+// Syntax that isn't a function, like `=>`, has a description but no type
+type OperatorInfo = { signature?: string; description: string };
+
+// Operators have no AST node of their own to look a type up for, so hover
+// reads them from this table. Constraints match src/typer/builtins.ts.
+const OPERATORS: Record<string, OperatorInfo> = {
+  '+': { signature: 'a -> a -> a given a implements Add', description: 'Addition; also concatenates strings.' },
+  '-': { signature: 'a -> a -> a given a implements Numeric', description: 'Subtraction (or unary negation).' },
+  '*': { signature: 'a -> a -> a given a implements Numeric', description: 'Multiplication.' },
+  '/': { signature: 'a -> a -> Option Float given a implements Numeric', description: 'Safe division; `None` when dividing by zero.' },
+  '%': { signature: 'a -> a -> Option Float given a implements Numeric', description: 'Safe modulo; `None` when the divisor is zero.' },
+  '==': { signature: 'a -> a -> Bool given a implements Eq', description: 'Structural equality.' },
+  '!=': { signature: 'a -> a -> Bool given a implements Eq', description: 'Structural inequality.' },
+  '<': { signature: 'a -> a -> Bool given a implements Ord', description: 'Less than.' },
+  '>': { signature: 'a -> a -> Bool given a implements Ord', description: 'Greater than.' },
+  '<=': { signature: 'a -> a -> Bool given a implements Ord', description: 'Less than or equal.' },
+  '>=': { signature: 'a -> a -> Bool given a implements Ord', description: 'Greater than or equal.' },
+  '&&': { signature: 'Bool -> Bool -> Bool', description: 'Logical and (short-circuiting).' },
+  '||': { signature: 'Bool -> Bool -> Bool', description: 'Logical or (short-circuiting).' },
+  '|': { signature: 'a -> (a -> b) -> b', description: 'Pipe: applies the value on the left to the function on the right.' },
+  '|?': { signature: 'm a -> (a -> b) -> m b', description: 'Safe pipe: maps over `Some`/`Ok`, short-circuits on `None`/`Err`.' },
+  '|>': { signature: '(a -> b) -> (b -> c) -> (a -> c)', description: 'Left-to-right function composition.' },
+  '<|': { signature: '(b -> c) -> (a -> b) -> (a -> c)', description: 'Right-to-left function composition.' },
+  '$': { signature: '(a -> b) -> a -> b', description: 'Low-precedence application: `f $ x` is `f x`.' },
+  ';': { signature: 'a -> b -> b', description: 'Sequence: evaluates both sides, yields the right.' },
+  '=>': { description: 'Separates a function\'s parameters from its body: `fn x y => x + y`.' },
+};
+
+const OPERATOR_CHAR = /[+\-*/%=!<>|?$&;]/;
+
+function extractOperatorAtPosition(
+  line: string,
+  column: number,
+): { operator: string; info: OperatorInfo; start: number; end: number } | undefined {
+  const chars = [...line];
+  if (column >= chars.length || !OPERATOR_CHAR.test(chars[column])) return undefined;
+  let start = column;
+  let end = column;
+  while (start > 0 && OPERATOR_CHAR.test(chars[start - 1])) start--;
+  while (end < chars.length && OPERATOR_CHAR.test(chars[end])) end++;
+  const operator = chars.slice(start, end).join('');
+  const info = OPERATORS[operator];
+  return info ? { operator, info, start, end } : undefined;
 }
 
-function extractSymbolAtPosition(ast: any, line: number, column: number): string | undefined {
-  function walk(node: any): string | undefined {
-    if (!node || typeof node !== 'object') return undefined;
-    const loc = node.location;
-    if (loc && loc.start && loc.end) {
-      const sL = Number(loc.start.line);
-      const sC = Number(loc.start.column);
-      const eL = Number(loc.end.line);
-      const eC = Number(loc.end.column);
-      if (positionWithinRange(line, column, sL, sC, eL, eC)) {
-        const kind = node.kind;
-        if (kind === 'variable' || kind === 'definition') {
-          const name = node.name;
-          if (typeof name === 'string') return name;
-        }
-      }
-    }
-    if (Array.isArray(node)) {
-      for (const item of node) {
-        const r = walk(item);
-        if (r) return r;
-      }
-    } else {
-      for (const key of Object.keys(node)) {
-        const r = walk(node[key]);
-        if (r) return r;
-      }
-    }
-    return undefined;
-  }
-  return walk(ast);
+const toRange = (loc: SourceLocation) =>
+  Range.create(
+    Position.create(loc.start.line - 1, loc.start.column - 1),
+    Position.create(loc.end.line - 1, loc.end.column - 1),
+  );
+
+const definitionKind = (node: { value: Expression }) =>
+  node.value.kind === 'function' ? SymbolKind.Function : SymbolKind.Variable;
+
+function extractSymbolAtPosition(ast: AstProgram, line: number, column: number): string | undefined {
+  return forEachNode(ast.statements, (node) =>
+    containsPosition(nameLocation(node), line, column) ? nodeName(node) : undefined,
+  );
 }
 
-function findDefinition(ast: any, symbolName: string): {
+function findDefinition(ast: AstProgram, symbolName: string): {
   name: string;
   kind: SymbolKind;
   range: Range;
 } | undefined {
-  function walk(node: any): ReturnType<typeof findDefinition> {
-    if (!node || typeof node !== 'object') return undefined;
-    if (node.kind === 'definition' && typeof node.name === 'string' && node.name === symbolName) {
-      const loc = node.location;
-      if (loc?.start && loc?.end) {
-        const valueKind = node.value?.kind;
-        const kind = valueKind === 'function' ? SymbolKind.Function : SymbolKind.Variable;
-        return {
-          name: node.name,
-          kind,
-          range: Range.create(
-            Position.create(Number(loc.start.line) - 1, Number(loc.start.column) - 1),
-            Position.create(Number(loc.end.line) - 1, Number(loc.end.column) - 1),
-          ),
-        };
-      }
-    }
-    if (Array.isArray(node)) {
-      for (const item of node) {
-        const r = walk(item);
-        if (r) return r;
-      }
-    } else {
-      for (const key of Object.keys(node)) {
-        const r = walk(node[key]);
-        if (r) return r;
-      }
-    }
-    return undefined;
+  return forEachNode(ast.statements, (node) =>
+    node.kind === 'definition' && node.name === symbolName && node.location
+      ? { name: node.name, kind: definitionKind(node), range: toRange(node.location) }
+      : undefined,
+  );
+}
+
+// Constructors are declared in their variant type: in this file, a module
+// it imports, or the standard library
+function findConstructor(ast: AstProgram, name: string): Range | undefined {
+  return forEachNode(ast.statements, (node) => {
+    if (node.kind !== 'type-definition') return undefined;
+    const found = node.constructors.find((c) => c.name === name && c.location);
+    return found ? toRange(found.location) : undefined;
+  });
+}
+
+// The modules a file imports, then the standard library
+function importedModules(filePath: string): (string | undefined)[] {
+  const imported = (workspace.get(filePath)?.imports ?? []).flatMap((site) => site.target ?? []);
+  return [...imported, stdlibPath()];
+}
+
+const constructorElsewhere = (filePath: string, name: string) =>
+  declarationIn(importedModules(filePath), name, 'constructor');
+
+// The declaration in the first of the modules that declares `name` as `kind`
+function declarationIn(modulePaths: (string | undefined)[], name: string, kind: OutlineKind): Location | undefined {
+  for (const modulePath of modulePaths) {
+    const module = modulePath ? workspace.get(modulePath) : undefined;
+    const declared = module?.declarations.find((d) => d.kind === kind && d.name === name);
+    if (!module || !declared) continue;
+    const range = Range.create(positionAt(module.text, declared.span.start), positionAt(module.text, declared.span.end));
+    return Location.create(URI.file(module.filePath).toString(), range);
   }
-  return walk(ast);
+  return undefined;
 }
 
-function findReferences(ast: any, symbolName: string, uri: string): Location[] {
+// Uses of a name; a constructor's include the patterns that match it. With
+// `includeDeclaration`, where it's defined comes first
+function findReferences(ast: AstProgram, symbolName: string, uri: string, includeDeclaration: boolean): Location[] {
   const refs: Location[] = [];
-  function walk(node: any) {
-    if (!node || typeof node !== 'object') return;
-    if (node.kind === 'variable' && typeof node.name === 'string' && node.name === symbolName) {
-      const loc = node.location;
-      if (loc?.start && loc?.end) {
-        refs.push(Location.create(
-          uri,
-          Range.create(
-            Position.create(Number(loc.start.line) - 1, Number(loc.start.column) - 1),
-            Position.create(Number(loc.end.line) - 1, Number(loc.end.column) - 1),
-          ),
-        ));
-      }
-    }
-    if (Array.isArray(node)) node.forEach(walk);
-    else Object.values(node).forEach(walk);
+  if (includeDeclaration) {
+    const declared = findDefinition(ast, symbolName)?.range ?? findConstructor(ast, symbolName);
+    if (declared) refs.push(Location.create(uri, declared));
   }
-  walk(ast);
+  forEachNode(ast.statements, (node) => {
+    const location = nameLocation(node);
+    if ((node.kind === 'variable' || node.kind === 'constructor') && node.name === symbolName && location) {
+      refs.push(Location.create(uri, toRange(location)));
+    }
+    return undefined;
+  });
   return refs;
 }
 
-function extractAllSymbols(ast: any, uri: string): SymbolInformation[] {
-  const symbols: SymbolInformation[] = [];
-  function walk(node: any) {
-    if (!node || typeof node !== 'object') return;
-    if (node.kind === 'definition' && typeof node.name === 'string') {
-      const loc = node.location;
-      if (loc?.start && loc?.end) {
-        const valueKind = node.value?.kind;
-        const kind = valueKind === 'function' ? SymbolKind.Function : SymbolKind.Variable;
-        symbols.push({
-          name: node.name,
-          kind,
-          location: Location.create(
-            uri,
-            Range.create(
-              Position.create(Number(loc.start.line) - 1, Number(loc.start.column) - 1),
-              Position.create(Number(loc.end.line) - 1, Number(loc.end.column) - 1),
-            ),
-          ),
-        });
-      }
+// Structural `type` aliases declared in the file. Aliases of a single named
+// type (`type Id = Float`) are left out: they'd rename every use of it.
+function documentAliases(ast: AstProgram): TypeAlias[] {
+  const aliases: TypeAlias[] = [];
+  forEachNode(ast.statements, (node) => {
+    if (node.kind !== 'user-defined-type' || node.typeParams.length > 0) return undefined;
+    const type = fromCompilerType(node.definition);
+    if (type && (type.kind === 'record' || type.kind === 'tuple' || type.kind === 'union')) {
+      aliases.push({ name: node.name, type });
+    }
+    return undefined;
+  });
+  return aliases;
+}
+
+function renderOptions(ast: AstProgram | undefined): RenderOptions {
+  const { maxWidth, maxDepth, arrows, expandAliases } = settings.display;
+  return {
+    arrow: arrows === 'ascii' ? '->' : '→',
+    maxWidth,
+    maxDepth,
+    aliases: !expandAliases && ast ? documentAliases(ast) : [],
+  };
+}
+
+// Shows types from the CLI per the user's display settings
+const typeFormatter = (ast: AstProgram | undefined) => {
+  const options = renderOptions(ast);
+  return (type: string) => formatTypeString(type, options);
+};
+
+// The structural aliases a file can name: its own and its imports'
+async function aliasesFor(filePath: string, timeoutMs?: number): Promise<TypeAlias[]> {
+  const modules = [filePath, ...importedModules(filePath)].filter((m): m is string => !!m);
+  const asts = await Promise.all(modules.map((m) => optional(getAstFile(m, timeoutMs), undefined)));
+  return asts.flatMap((ast) => (ast ? documentAliases(ast) : []));
+}
+
+// A CLI type as it's shown in `uri`, and then expanded a step at a time, for
+// the noolang.expandType command; just the one rendering when there's
+// nothing to expand
+async function expandType(uri: string, type: string): Promise<string[]> {
+  const parsed = parseType(type);
+  if (!parsed) return [formatTypeString(type, renderOptions(undefined))];
+  const aliases = await aliasesFor(filePathOf(uri), settings.timeouts.hoverMs);
+  return typeExpansions(parsed, { ...renderOptions(undefined), aliases });
+}
+
+// A hover link to the extension's command that shows `expandType`'s
+// renderings, when there's more than one
+async function expandTypeLink(uri: string, type: string): Promise<string | undefined> {
+  if ((await expandType(uri, type)).length < 2) return undefined;
+  const args = encodeURIComponent(JSON.stringify([uri, type]));
+  return `[Expand type](command:noolang.showExpandedType?${args} "Expand aliases one level at a time")`;
+}
+
+// The formatter for a file, loading its AST only when aliases are wanted
+async function fileTypeFormatter(filePath: string, timeoutMs?: number) {
+  return typeFormatter(settings.display.expandAliases ? undefined : await getAstFile(filePath, timeoutMs));
+}
+
+// Literals have no name to look a type up by. Numbers and strings are typed
+// by their token; lists, tuples and records, hovered on their opening
+// bracket, by typing their source in the file's scope.
+const COLLECTION_KINDS = new Set(['list', 'tuple', 'record']);
+
+async function getLiteralType(
+  filePath: string,
+  text: string,
+  ast: AstProgram,
+  line1: number,
+  col1: number,
+): Promise<string | undefined> {
+  const node = forEachNode(ast.statements, (n) =>
+    (n.kind === 'literal' || n.kind === 'unit' || COLLECTION_KINDS.has(n.kind)) &&
+    containsPosition(n.location, line1, col1)
+      ? n
+      : undefined,
+  );
+  if (!node?.location) return undefined;
+  if (node.kind === 'unit') return '{}';
+  if (node.kind === 'literal' && typeof node.value === 'number') return 'Float';
+  if (node.kind === 'literal' && typeof node.value === 'string') return 'String';
+  if (node.kind === 'literal' && typeof node.value === 'boolean') return 'Bool';
+  const start = offsetAt(text, { line: node.location.start.line - 1, character: node.location.start.column - 1 });
+  let depth = 0;
+  for (const t of lexSyntax(text.slice(start))) {
+    if (t.kind !== 'punctuation') continue;
+    if ('([{'.includes(t.text)) depth++;
+    if (')]}'.includes(t.text) && --depth === 0) {
+      if (!supports('--expr-type')) return undefined;
+      return optional(getExpressionTypeInFile(filePath, text.slice(start, start + t.end)), undefined);
     }
-    if (Array.isArray(node)) node.forEach(walk);
-    else Object.values(node).forEach(walk);
   }
-  walk(ast);
-  return symbols;
+  return undefined;
 }
 
-function getPositionType(filePath: string, line1: number, col1: number): string | undefined {
-  const ast = getAstFile(filePath);
+async function getPositionType(
+  filePath: string,
+  line1: number,
+  col1: number,
+  ast: AstProgram | undefined,
+): Promise<string | undefined> {
+  const timeoutMs = settings.timeouts.hoverMs;
+  const format = typeFormatter(ast);
+  const text = fs.existsSync(filePath) ? fs.readFileSync(filePath, 'utf8') : '';
+  const lines = text.split(/\r?\n/);
+  const line = lines[line1 - 1] ?? '';
+  // Operators sit inside their enclosing definition's range, so check them
+  // before the AST lookup would resolve the position to that definition.
+  const op = extractOperatorAtPosition(line, col1 - 1);
+  if (op) return op.info.signature && format(op.info.signature);
   if (ast) {
+    const literal = await getLiteralType(filePath, text, ast, line1, col1);
+    if (literal) return format(literal);
     const name = extractSymbolAtPosition(ast, line1, col1);
     if (name) {
-      // Try symbol type via CLI --symbol-type
-      const res = runNodeCli(['--symbol-type', filePath, name]);
-      if (res.status === 0) {
-        const out = res.stdout || '';
-        const i = out.indexOf('has type: ');
-        if (i >= 0) return simplifyTypeString(out.slice(i + 10).trim());
-      }
+      const type = await getSymbolType(filePath, name, timeoutMs);
+      if (type) return format(type);
     }
   }
-  const text = fs.existsSync(filePath) ? fs.readFileSync(filePath, 'utf8') : '';
-  const lines = text.split(/\r?\n/);
-  const line = lines[line1 - 1] ?? '';
   const expr = extractExpressionAtPosition(line, col1 - 1);
   if (expr) {
-    const types = getExpressionTypes(expr);
-    if (types[0]) return simplifyTypeString(types[0]);
+    const types = await getExpressionTypes(expr, timeoutMs);
+    if (types[0]) return format(types[0]);
   }
   const id = extractIdentifierAtPosition(line, col1 - 1);
   if (id) {
-    const res = runNodeCli(['--symbol-type', filePath, id]);
-    if (res.status === 0) {
-      const i = (res.stdout || '').indexOf('has type: ');
-      if (i >= 0) return simplifyTypeString((res.stdout || '').slice(i + 10).trim());
-    }
+    const type = await getSymbolType(filePath, id, timeoutMs);
+    if (type) return format(type);
   }
   return undefined;
 }
 
+// --- Constraint index ---
+// Maps a constraint name to the types with an `implement` block for it, from
+// stdlib.noo plus the document being hovered.
+function stdlibPath(): string | undefined {
+  const candidates = [
+    WORKSPACE && path.join(WORKSPACE, 'stdlib.noo'),
+    path.join(path.dirname(CLI_PATH), '..', 'stdlib.noo'),
+  ];
+  return candidates.find((c): c is string => !!c && fs.existsSync(c));
+}
+
+function indexImplementations(text: string, index: Map<string, string[]>) {
+  const re = /^\s*implement\s+([A-Z]\w*)\s+(\([^)]*\)|[A-Z]\w*)/gm;
+  for (const m of text.matchAll(re)) {
+    const [, constraint, type] = m;
+    const typeName = type.replace(/^\(|\)$/g, '');
+    const types = index.get(constraint) ?? [];
+    if (!types.includes(typeName)) types.push(typeName);
+    index.set(constraint, types);
+  }
+}
+
+let stdlibIndex: Map<string, string[]> | undefined;
+
+function constraintIndex(documentSource: string): Map<string, string[]> {
+  if (stdlibIndex) {
+    tracer.log('cache hit: stdlib constraint index');
+  } else {
+    stdlibIndex = new Map();
+    const p = stdlibPath();
+    if (p) indexImplementations(fs.readFileSync(p, 'utf8'), stdlibIndex);
+    tracer.log('cache miss: stdlib constraint index', `indexed ${stdlibIndex?.size ?? 0} constraints from ${p}`);
+  }
+  const index = new Map([...stdlibIndex].map(([k, v]) => [k, [...v]] as [string, string[]]));
+  indexImplementations(documentSource, index);
+  return index;
+}
+
+// Splits `a -> String given a implements Show and b implements Eq` into the
+// base type and its individual constraints.
+function splitConstraints(type: string): { base: string; constraints: string[] } {
+  const i = type.indexOf(' given ');
+  if (i < 0) return { base: type, constraints: [] };
+  const constraints = type
+    .slice(i + ' given '.length)
+    .split(/\s+and\s+|,\s*(?=\w+ (?:implements|has|is) )/)
+    .map((c) => c.replace(/^\(|\)$/g, '').trim())
+    .filter(Boolean);
+  return { base: type.slice(0, i), constraints };
+}
+
+function constraintSection(constraints: string[], index: Map<string, string[]>): string {
+  const lines = constraints.map((c) => {
+    const m = /^\w+ implements (\w+)$/.exec(c);
+    const impls = m ? index.get(m[1]) : undefined;
+    return impls && impls.length > 0
+      ? `- \`${c}\` — implemented by ${impls.map((t) => `\`${t}\``).join(', ')}`
+      : `- \`${c}\``;
+  });
+  return '**Given:**\n' + lines.join('\n');
+}
+
+// The `implement` block a call's constraint resolved to: in the file, a
+// module it imports or the standard library. Implementations are per type
+// constructor (`implement Show (List a)` serves `List Float`), so only the
+// head is matched.
+function chosenImplementation(filePath: string, trait: string, type: string) {
+  const head = (t: string) => t.replace(/^\(/, '').split(/[\s)]/)[0];
+  const modules = [filePath, ...importedModules(filePath)].flatMap((p) => (p && workspace.get(p)) || []);
+  return implementationsOf(modules, trait).find(
+    ({ declaration }) => head(declaration.name.slice(trait.length + 1)) === head(type),
+  );
+}
+
+// The implementations a call resolved its constraints to, linked to their
+// blocks: "resolves to `implement Show Float` (stdlib.noo:58)"
+function resolutionSection(resolutions: { trait: string; type: string }[], filePath: string): string {
+  const lines = resolutions.map(({ trait, type }) => {
+    const chosen = chosenImplementation(filePath, trait, type);
+    if (!chosen) return `- \`${trait} ${type}\` resolves to \`implement ${trait} ${type}\``;
+    const { uri, range } = spanLocation(chosen.file, chosen.declaration.span);
+    const line = range.start.line + 1;
+    const where = `[${path.basename(chosen.file.filePath)}:${line}](${uri}#L${line})`;
+    return `- \`${trait} ${type}\` resolves to \`implement ${chosen.declaration.name}\` (${where})`;
+  });
+  return '**Resolved:**\n' + lines.join('\n');
+}
+
+function effectSection(effects: string[]): string {
+  const lines = effects.map((e) => (EFFECT_DESCRIPTIONS[e] ? `- \`!${e}\` — ${EFFECT_DESCRIPTIONS[e]}` : `- \`!${e}\``));
+  return '**Effects:**\n' + lines.join('\n');
+}
+
+const SNIPPET_MAX_LINES = 3;
+
+function textInRange(text: string, range: Range): string {
+  const lines = text.split(/\r?\n/).slice(range.start.line, range.end.line + 1);
+  if (lines.length === 0) return '';
+  const last = lines.length - 1;
+  lines[last] = lines[last].slice(0, range.end.character);
+  lines[0] = lines[0].slice(range.start.character);
+  return lines.join('\n');
+}
+
+function documentText(uri: string, filePath: string): string {
+  const open = documents.get(uri);
+  if (open) return open.text;
+  return fs.existsSync(filePath) ? fs.readFileSync(filePath, 'utf8') : '';
+}
+
+// First few source lines of a definition, for showing small definitions
+// inline in hover instead of making the user jump to them.
+function definitionSnippet(text: string, range: Range): string | undefined {
+  const lines = text.split(/\r?\n/).slice(range.start.line, range.end.line + 1);
+  if (lines.length === 0) return undefined;
+  lines[0] = lines[0].slice(range.start.character);
+  const shown = lines.slice(0, SNIPPET_MAX_LINES);
+  if (lines.length > SNIPPET_MAX_LINES) shown.push('…');
+  const snippet = shown.join('\n').replace(/;\s*$/, '').trimEnd();
+  return snippet || undefined;
+}
+
+// A builtin's documentation and example, for hover, completion and
+// signature help
+function builtinDoc(builtin: BuiltinInfo): string {
+  return builtin.example ? `${builtin.doc}\n\n\`\`\`noolang\n${builtin.example}\n\`\`\`` : builtin.doc;
+}
+
+function hoverMarkdown(
+  type: string | undefined,
+  definition: { snippet: string; filePath: string; line: number } | undefined,
+  source: string,
+  filePath: string,
+  // The binding's own type, when `type` is an instantiation of it
+  scheme?: string,
+  resolutions: { trait: string; type: string }[] = [],
+  builtin?: BuiltinInfo,
+): string | undefined {
+  const sections: string[] = [];
+  if (type) {
+    const { base, constraints } = splitConstraints(type);
+    sections.push('Type: ' + base);
+    if (scheme) sections.push('*Instantiated from* `' + scheme + '`');
+    const effects = callEffects(base);
+    if (effects.length > 0) sections.push(effectSection(effects));
+    if (constraints.length > 0) sections.push(constraintSection(constraints, constraintIndex(source)));
+  }
+  if (resolutions.length > 0) sections.push(resolutionSection(resolutions, filePath));
+  if (builtin) sections.push(builtinDoc(builtin));
+  if (definition) {
+    sections.push('```noolang\n' + definition.snippet + '\n```');
+    sections.push(`*Defined in ${path.basename(definition.filePath)}:${definition.line + 1}*`);
+  }
+  return sections.length > 0 ? sections.join('\n\n') : undefined;
+}
+
 // --- LSP Handlers ---
-connection.onInitialize((_params: InitializeParams): InitializeResult => {
+// .noo files, and folders (which may hold some)
+const NOOLANG_FILE_OPERATIONS = [
+  { pattern: { glob: '**/*.noo', matches: FileOperationPatternKind.file } },
+  { pattern: { glob: '**/*', matches: FileOperationPatternKind.folder } },
+];
+
+connection.onInitialize((params: InitializeParams): InitializeResult => {
+  applySettings(params.initializationOptions);
+  inlineValueRefresh = params.capabilities.workspace?.inlineValue?.refreshSupport === true;
+  inlayHintRefresh = params.capabilities.workspace?.inlayHint?.refreshSupport === true;
+  codeLensRefresh = params.capabilities.workspace?.codeLens?.refreshSupport === true;
+  documentChanges = params.capabilities.workspace?.workspaceEdit?.documentChanges === true;
+  changeAnnotations = params.capabilities.workspace?.workspaceEdit?.changeAnnotationSupport !== undefined;
+  const documentSymbol = params.capabilities.textDocument?.documentSymbol;
+  hierarchicalSymbols = documentSymbol?.hierarchicalDocumentSymbolSupport === true;
+  const resolvable = params.capabilities.workspace?.symbol?.resolveSupport?.properties ?? [];
+  lazySymbolRanges = resolvable.includes('location.range');
+  const folders = (params.workspaceFolders ?? []).map((f) => uriToFilePath(f.uri));
+  workspaceFolders = folders.filter((f): f is string => !!f);
+  workspace.setRoots(workspaceFolders);
+  restoreWorkspaceIndex();
   return {
     capabilities: {
       textDocumentSync: TextDocumentSyncKind.Full,
@@ -397,131 +1227,2370 @@ connection.onInitialize((_params: InitializeParams): InitializeResult => {
       },
       hoverProvider: true,
       definitionProvider: true,
+      implementationProvider: true,
       referencesProvider: true,
       documentSymbolProvider: true,
-      workspaceSymbolProvider: true,
+      workspaceSymbolProvider: { resolveProvider: true },
+      codeActionProvider: {
+        codeActionKinds: [CodeActionKind.QuickFix, CodeActionKind.RefactorRewrite, ORGANIZE_DEFINITIONS_KIND],
+      },
+      executeCommandProvider: { commands: Object.keys(commands) },
+      inlineValueProvider: true,
+      inlayHintProvider: true,
+      codeLensProvider: { resolveProvider: false },
+      documentFormattingProvider: true,
+      semanticTokensProvider: { legend: SEMANTIC_LEGEND, full: true },
+      renameProvider: { prepareProvider: true },
+      signatureHelpProvider: { triggerCharacters: [' '] },
+      workspace: {
+        fileOperations: {
+          didCreate: { filters: NOOLANG_FILE_OPERATIONS },
+          didDelete: { filters: NOOLANG_FILE_OPERATIONS },
+        },
+      },
+      notebookDocumentSync: { notebookSelector: [{ cells: [{ language: 'noolang' }] }] },
     },
-    serverInfo: { name: 'Noolang Language Server', version: '0.1.0' },
+    serverInfo: { name: 'Noolang Language Server', version: SERVER_VERSION },
   };
 });
 
 connection.onInitialized(() => {
-  connection.console.info('Noolang LSP server initialized (TypeScript)');
+  log.info(`Noolang LSP server ${SERVER_VERSION} initialized (TypeScript)`);
+  const problems = checkEnvironment(CLI_RUNTIME, CLI_PATH, { runtimeVersion, exists: fs.existsSync });
+  for (const problem of problems) {
+    log.error(problem);
+    connection.window.showErrorMessage(problem);
+  }
+  if (problems.length === 0) void probeCapabilities();
 });
 
-connection.onShutdown(() => {
-  // no-op
-});
+function applySettings(raw: unknown) {
+  settings = mergeSettings(settings, raw);
+  astCache.setLimits(settings.cache);
+  typesCache.setLimits(settings.cache);
+  selectBackend(settings.backend);
+  telemetry.setEnabled(settings.telemetry.enabled);
+  clearInterval(histogramTimer);
+  histogramTimer = undefined;
+  if (settings.telemetry.enabled && settings.telemetry.logHistograms) {
+    histogramTimer = setInterval(logHistograms, HISTOGRAM_LOG_INTERVAL_MS);
+    histogramTimer.unref();
+  }
+}
 
-connection.onDidOpenTextDocument((params: DidOpenTextDocumentParams) => {
-  const uri = params.textDocument.uri;
-  const content = params.textDocument.text;
-  documents.set(uri, content);
-  const filePath = uriToFilePath(uri);
-  if (filePath) {
-    const diagnostics = getDiagnostics(filePath);
-    connection.sendDiagnostics({ uri, diagnostics });
+// With noolang.telemetry.logHistograms, the timings so far go to the log
+// every few minutes and at shutdown
+const HISTOGRAM_LOG_INTERVAL_MS = 5 * 60_000;
+
+let histogramTimer: NodeJS.Timeout | undefined;
+
+function logHistograms() {
+  for (const line of telemetry.histograms()) log.info(line);
+}
+
+connection.onDidChangeConfiguration(
+  guarded('workspace/didChangeConfiguration', (params: DidChangeConfigurationParams) => {
+    const hints = settings.inlayHints;
+    applySettings(params.settings?.noolang);
+    // Editors ask for hints again only when told to
+    const changed = JSON.stringify(hints) !== JSON.stringify(settings.inlayHints);
+    if (changed && inlayHintRefresh) void connection.languages.inlayHint.refresh();
+  }),
+);
+
+// Nothing the server started outlives it: pending checks are dropped, CLI
+// calls in flight are killed and later ones refused, REPLs are stopped and
+// workspace searches end at their next chunk; the workspace index is saved
+// for the next session.
+let shuttingDown = false;
+
+function shutDown() {
+  shuttingDown = true;
+  for (const timer of typeCheckTimers.values()) clearTimeout(timer);
+  typeCheckTimers.clear();
+  clearInterval(histogramTimer);
+  if (histogramTimer) logHistograms();
+  if (profiler.enabled) log.info(profiler.report());
+  backend.stop();
+  repls.stopAll();
+  persistWorkspaceIndex();
+  fs.rmSync(NOTEBOOK_DIR, { recursive: true, force: true });
+}
+
+connection.onShutdown(shutDown);
+
+// `exit` ends the process: with 0 after `shutdown`, and with 1 without one,
+// once the cleanup it skipped is done
+connection.onExit(() => {
+  const shutdownReceived = shuttingDown;
+  if (!shutdownReceived) {
+    log.warn('exit without shutdown');
+    shutDown();
   }
+  process.exit(shutdownReceived ? 0 : 1);
 });
 
-connection.onDidChangeTextDocument((params: DidChangeTextDocumentParams) => {
-  const uri = params.textDocument.uri;
-  for (const change of params.contentChanges) {
-    // FULL sync: last change contains full text
-    if (typeof change.text === 'string') {
-      documents.set(uri, change.text);
-    }
-  }
-  const filePath = uriToFilePath(uri);
-  if (filePath) {
-    const diagnostics = getDiagnostics(filePath);
-    connection.sendDiagnostics({ uri, diagnostics });
-  }
+// The connection library also ends the process itself: when the client's
+// connection closes, and when the editor whose process ID came with
+// `initialize` (or --clientProcessId) is gone. Either way the CLI processes
+// and REPLs go with it; this runs synchronously, as `exit` handlers must.
+process.once('exit', () => {
+  if (shuttingDown) return;
+  backend.stop();
+  repls.stopAll();
 });
 
-connection.onDidSaveTextDocument((params: DidSaveTextDocumentParams) => {
-  const uri = params.textDocument.uri;
-  const filePath = uriToFilePath(uri);
-  if (filePath) {
-    const diagnostics = getDiagnostics(filePath);
-    connection.sendDiagnostics({ uri, diagnostics });
+// A request's answer when its work throws. An LspError is the JSON-RPC error
+// its kind maps to (errors.ts). Anything else is a bug in the server: logged
+// with its stack and what it was working on (a document, a command), and
+// answered as an internal error, so the one request fails and the server
+// carries on.
+function failedRequest(e: unknown, context?: string): ResponseError<void> {
+  if (e instanceof LspError) {
+    log.debug(`request failed (${e.kind}): ${e.message}`);
+    return e.toResponseError();
   }
-});
+  const message = reportCrash(e, context);
+  return new ResponseError(ErrorCodes.InternalError, `Noolang server error: ${message}`);
+}
 
-connection.onCompletion((params: CompletionParams): CompletionItem[] => {
-  const items: CompletionItem[] = [];
-  const keywords = ['fn', 'if', 'then', 'else', 'match', 'with', 'variant', 'mut', 'constraint', 'implement'];
-  const ctors = ['True', 'False', 'Some', 'None', 'Ok', 'Err'];
-  const builtins = ['head', 'tail', 'map', 'filter', 'reduce', 'length', 'print', 'toString', 'read', 'write', 'log', 'random'];
-  const mk = (label: string, kind: CompletionItemKind) => ({
-    label,
-    kind,
-    detail: `Noolang ${kind === CompletionItemKind.Constructor ? 'constructor' : keywords.includes(label) ? 'keyword' : 'function'}`,
-    insertText: label,
-  });
-  items.push(...keywords.map((k) => mk(k, CompletionItemKind.Keyword)));
-  items.push(...ctors.map((c) => mk(c, CompletionItemKind.Constructor)));
-  items.push(...builtins.map((b) => mk(b, CompletionItemKind.Function)));
-  return items;
-});
+function reportCrash(e: unknown, context?: string): string {
+  const error = e instanceof Error ? e : new Error(String(e));
+  log.error(`Internal error${context ? ` in ${context}` : ''}: ${error.message}`, { stack: error.stack });
+  return error.message;
+}
 
-connection.onHover((params: HoverParams): Hover | null => {
-  const uri = params.textDocument.uri;
-  const filePath = uriToFilePath(uri);
-  if (!filePath) return null;
+// Runs a request's work and answers it, timing it for telemetry under its method
+function answer<T>(method: string, fn: () => T | Promise<T>, uri?: string): Promise<T | ResponseError<void>> {
+  const started = Date.now();
+  return Promise.resolve()
+    .then(fn)
+    .catch((e) => failedRequest(e, uri ? `${method} for ${uri}` : method))
+    .finally(() => telemetry.record('request', method, Date.now() - started));
+}
+
+// Notifications have no response to fail, and a rejection nobody handles would
+// end the process: bugs in their handlers (and in work they start) are logged
+// with the document concerned, and the server carries on.
+type NotificationParams = { textDocument?: { uri: string }; notebookDocument?: { uri: string } } | undefined;
+
+function guarded<P>(method: string, handler: (params: P) => unknown): (params: P) => void {
+  return (params) => {
+    const subject = params as NotificationParams;
+    const uri = subject?.textDocument?.uri ?? subject?.notebookDocument?.uri;
+    const context = uri ? `${method} for ${uri}` : method;
+    try {
+      Promise.resolve(handler(params)).catch((e) => reportCrash(e, context));
+    } catch (e) {
+      reportCrash(e, context);
+    }
+  };
+}
+
+// Analysis an answer can do without, e.g. types for completions that still
+// offer keywords: `fallback` when it fails
+async function optional<T>(work: Promise<T>, fallback: T): Promise<T> {
+  try {
+    return await work;
+  } catch (e) {
+    if (!(e instanceof LspError)) throw e;
+    log.debug(`going on without ${e.message}`);
+    return fallback;
+  }
+}
+
+// Work nothing waits for (checks started by timers and other work)
+function inBackground(context: string, work: Promise<unknown>) {
+  work.catch((e) => reportCrash(e, context));
+}
+
+// Runs read-only analysis under the document's read lock. Files that aren't
+// open have no in-memory state to guard.
+function underReadLock<T>(uri: string, fn: () => T | Promise<T>): Promise<T> {
+  const entry = documents.get(uri);
+  return entry ? entry.lock.read(fn) : Promise.resolve().then(fn);
+}
+
+// A request on a document: its work under the read lock, answered as above
+const readDocument = <T>(method: string, uri: string, fn: () => T | Promise<T>) =>
+  answer(method, () => underReadLock(uri, fn), uri);
+
+// --- Partial results ---
+// Searches of the whole workspace go through it a chunk of files at a time.
+// A client that passed a partialResultToken gets each chunk's results as
+// they're found, other requests getting in between chunks, and an empty
+// response at the end; the search stops early when it's cancelled.
+const FILES_PER_CHUNK = 50;
+
+async function searchWorkspace<T>(
+  collect: (files: IndexedFile[], wanted: number) => T[],
+  progress: ResultProgressReporter<T[]> | undefined,
+  token: CancellationToken | undefined,
+  limit = Infinity,
+): Promise<T[]> {
+  const paths = workspace.paths([...openDocumentsByPath().keys()]);
+  const found: T[] = [];
+  for (let i = 0; i < paths.length && found.length < limit; i += FILES_PER_CHUNK) {
+    if (token?.isCancellationRequested || shuttingDown) break;
+    const files = paths.slice(i, i + FILES_PER_CHUNK).flatMap((p) => workspace.get(p) ?? []);
+    const chunk = collect(files, limit - found.length).slice(0, limit - found.length);
+    found.push(...chunk);
+    if (!progress) continue;
+    if (chunk.length > 0) progress.report(chunk);
+    await new Promise((resolve) => setImmediate(resolve));
+  }
+  return progress ? [] : found;
+}
+
+// --- Workspace index ---
+// Names, imports and exports of every .noo file in the workspace folders
+// (workspace.ts), read from open documents' buffers and from disk otherwise.
+function openDocumentsByPath(): Map<string, DocumentEntry> {
+  return new Map(
+    documents.all().flatMap((entry): [string, DocumentEntry][] => {
+      const filePath = uriToFilePath(entry.uri);
+      return filePath ? [[filePath, entry]] : [];
+    }),
+  );
+}
+
+const workspace = createWorkspaceIndex((filePath) => {
+  const open = openDocumentsByPath().get(filePath);
+  if (open) return { stamp: `v${open.version}`, read: () => open.text };
+  const stamp = fileStamp(filePath);
+  return stamp ? { stamp, read: () => fs.readFileSync(filePath, 'utf8') } : undefined;
+});
+
+// The index outlives the session (index-cache.ts); without workspace folders
+// there's nothing worth keeping
+const INDEX_CACHE_DIR = indexCacheDir(process.env);
+
+function restoreWorkspaceIndex() {
+  if (workspaceFolders.length === 0) return;
+  const saved = loadIndex(indexCacheFile(INDEX_CACHE_DIR, workspaceFolders));
+  workspace.restore(saved);
+  log.debug(`restored ${saved.length} workspace index entries`);
+}
+
+function persistWorkspaceIndex() {
+  if (workspaceFolders.length === 0) return;
+  try {
+    saveIndex(indexCacheFile(INDEX_CACHE_DIR, workspaceFolders), workspace.saved());
+  } catch (e) {
+    log.warn(`Could not save the workspace index: ${e instanceof Error ? e.message : String(e)}`);
+  }
+}
+
+// --- Diagnostics ---
+// Sources per document, merged on every publish: syntax errors from the
+// in-process parser of the buffer, and fields repeated in a record, on every
+// keystroke; the CLI type check, debounced while typing (or on save, or on
+// request only, per noolang.diagnostics.trigger); failures from the
+// last test run, if any; for files that aren't open, imports of modules
+// deleted from the workspace; uses of `@deprecated` names, from the buffer
+// like syntax errors; and imports that lead back to the file, through the
+// workspace index. Large files also get the compiler's parse errors ahead of
+// the type check, which takes longer. Each publish carries the document
+// version it describes, and results for an older version, than the last
+// published or than the buffer now, are dropped rather than shown over newer
+// ones: a check that finishes after the edit fixing its errors would bring
+// them back until the next check.
+type DiagnosticSources = {
+  syntax: Diagnostic[];
+  parse: Diagnostic[];
+  types: Diagnostic[];
+  tests: Diagnostic[];
+  imports: Diagnostic[];
+  deprecations: Diagnostic[];
+  cycles: Diagnostic[];
+};
+
+const diagnosticSources = new Map<string, DiagnosticSources>();
+
+const TYPE_CHECK_DEBOUNCE_MS = 300;
+
+const typeCheckTimers = new Map<string, NodeJS.Timeout>();
+
+// Files at least this big are type checked in two steps
+const STREAMED_CHECK_BYTES = 32 * 1024;
+
+const publishedVersions = new Map<string, number>();
+
+function publishDiagnostics(
+  uri: string,
+  update: Partial<DiagnosticSources>,
+  version: number | undefined = documents.get(uri)?.version,
+) {
+  const published = publishedVersions.get(uri);
+  const current = documents.get(uri)?.version;
+  if (version !== undefined && published !== undefined && version < published) return;
+  if (version !== undefined && current !== undefined && version < current) return;
+  if (version !== undefined) publishedVersions.set(uri, version);
+  const empty = { syntax: [], parse: [], types: [], tests: [], imports: [], deprecations: [], cycles: [] };
+  const sources = { ...empty, ...diagnosticSources.get(uri), ...update };
+  diagnosticSources.set(uri, sources);
+  const { syntax, parse, types, tests, imports, deprecations, cycles } = sources;
+  const diagnostics = [...syntax, ...parse, ...types, ...tests, ...imports, ...deprecations, ...cycles];
+  connection.sendDiagnostics({ uri, version, diagnostics });
+}
+
+// The compiler's parse errors, from its error-recovering parse
+async function parseDiagnostics(filePath: string): Promise<Diagnostic[]> {
+  if (!supports('--ast-partial')) return [];
+  const ast = await optional(getAstFile(filePath, settings.timeouts.diagnosticsMs), undefined);
+  return (ast?.errors ?? []).map((error) => {
+    const loc = extractLineColumn(error.message);
+    const start = Position.create((loc?.line ?? 1) - 1, (loc?.column ?? 1) - 1);
+    return {
+      range: Range.create(start, Position.create(start.line, start.character + 1)),
+      severity: DiagnosticSeverity.Error,
+      source: 'noolang',
+      message: cleanErrorMessage(error.message),
+    };
+  });
+}
+
+function syntaxDiagnostics(text: string): Diagnostic[] {
+  const errors = parseSyntax(text).errors.map((e) => ({
+    range: Range.create(positionAt(text, e.start), positionAt(text, e.end)),
+    severity: DiagnosticSeverity.Error,
+    source: 'noolang',
+    message: e.message,
+  }));
+  const duplicates = duplicateFields(text).map(({ field, span }) => ({
+    range: Range.create(positionAt(text, span.start), positionAt(text, span.end)),
+    severity: DiagnosticSeverity.Warning,
+    source: 'noolang',
+    message: `Field @${field} is already in this record`,
+  }));
+  return [...errors, ...duplicates];
+}
+
+// Struck through by clients that show the Deprecated tag
+function deprecationDiagnostics(uri: string, text: string): Diagnostic[] {
+  const filePath = uriToFilePath(uri);
+  if (!filePath) return [];
+  const file = indexText(filePath, text, 'buffer');
+  const exported = new Set(file.exports.map((e) => e.localSpan?.start));
+  return [...deprecatedNames(workspace, file)].flatMap(([name, { note, declaration }]) =>
+    (file.identifiers.get(name) ?? [])
+      // Exporting a deprecated name is how importers still get it
+      .filter((span) => span.start !== declaration?.start && !exported.has(span.start))
+      .map((span) => ({
+        range: Range.create(positionAt(text, span.start), positionAt(text, span.end)),
+        severity: DiagnosticSeverity.Hint,
+        tags: [DiagnosticTag.Deprecated],
+        source: 'noolang',
+        message: note ? `'${name}' is deprecated: ${note}` : `'${name}' is deprecated`,
+      })),
+  );
+}
+
+// Each import that leads back to the file, with the whole cycle; cycles
+// otherwise surface as type errors about whatever the loop left untyped
+function cycleDiagnostics(uri: string, text: string): Diagnostic[] {
+  const filePath = uriToFilePath(uri);
+  if (!filePath) return [];
+  const root = workspaceOf(filePath);
+  return importCycles(workspace, indexText(filePath, text, 'buffer')).map(({ site, cycle }) => ({
+    range: Range.create(positionAt(text, site.specifierSpan.start), positionAt(text, site.specifierSpan.end)),
+    severity: DiagnosticSeverity.Error,
+    source: 'noolang',
+    message: `Import cycle: ${cycle.map((p) => path.relative(root, p)).join(' → ')}`,
+  }));
+}
+
+// An edit can close or break a cycle through the other open files
+function refreshCycles(changed: string) {
+  for (const entry of documents.all()) {
+    if (entry.uri === changed) continue;
+    const cycles = cycleDiagnostics(entry.uri, entry.text);
+    const current = diagnosticSources.get(entry.uri)?.cycles ?? [];
+    if (JSON.stringify(cycles) !== JSON.stringify(current)) publishDiagnostics(entry.uri, { cycles });
+  }
+}
+
+// Type checks in flight, per document. Checks falling due meanwhile only
+// mark a rerun, so however many edits arrive during a check, one more check
+// follows it, at the latest version.
+const runningTypeChecks = new Map<string, { rerun: boolean }>();
+
+async function checkTypes(entry: DocumentEntry) {
+  if (shuttingDown) return;
+  const running = runningTypeChecks.get(entry.uri);
+  if (running) {
+    running.rerun = true;
+    return;
+  }
+  const filePath = uriToFilePath(entry.uri);
+  if (!filePath) return;
+  const run = { rerun: false };
+  runningTypeChecks.set(entry.uri, run);
+  try {
+    // The CLI checks the file on disk, not the buffer, so the lock is only
+    // held to read which version this run reports for; an edit arriving
+    // meanwhile doesn't wait for the check. Its diagnostics are moved to
+    // that version's lines.
+    const { version, text } = await entry.lock.read(() => ({ version: entry.version, text: entry.text }));
+    const streamed = Buffer.byteLength(text) >= STREAMED_CHECK_BYTES;
+    if (streamed) {
+      const parse = onBufferLines(text, filePath, await parseDiagnostics(filePath));
+      if (documents.get(entry.uri) === entry) publishDiagnostics(entry.uri, { parse }, version);
+    }
+    const diagnostics = onBufferLines(text, filePath, await getDiagnostics(filePath));
+    // A newer edit (or a close) has superseded this run; the rerun reports
+    if (documents.get(entry.uri) === entry && entry.version === version) {
+      // The type check stops at a parse error, which is already shown
+      const types = streamed ? diagnostics.filter((d) => !/Parse error/.test(d.message)) : diagnostics;
+      publishDiagnostics(entry.uri, streamed ? { types } : { types, parse: [] }, version);
+      inBackground(`views of ${entry.uri}`, refreshViews(entry.uri, 'change'));
+    }
+  } finally {
+    runningTypeChecks.delete(entry.uri);
+  }
+  if (run.rerun && documents.get(entry.uri) === entry) inBackground(`type check of ${entry.uri}`, checkTypes(entry));
+}
+
+// --- Large files ---
+// A document of noolang.largeFiles.thresholdBytes or more (generated code,
+// say) gets no inlay hints or semantic tokens, and its diagnostics wait for
+// saves: the ones from the buffer aren't redone on every keystroke, and the
+// type check runs as with the onSave trigger. The user is told the first time
+// a document is found to be that large.
+const largeFilesNoticed = new Set<string>();
+
+function largeFile(uri: string, text: string): boolean {
+  const bytes = Buffer.byteLength(text);
+  if (bytes < settings.largeFiles.thresholdBytes) return false;
+  if (!largeFilesNoticed.has(uri)) {
+    largeFilesNoticed.add(uri);
+    const name = path.basename(uriToFilePath(uri) ?? uri);
+    const off = 'inlay hints and semantic highlighting are off for it, and its diagnostics update on save';
+    void connection.window.showInformationMessage(`${name} is large (${Math.round(bytes / 1024)} KB): ${off}`);
+  }
+  return true;
+}
+
+// Whether noolang.diagnostics.trigger has files checked on `event`: opening
+// or saving one, or any change to it
+const checksOn = (event: 'change' | 'save') =>
+  settings.diagnostics.trigger === 'onChange' || (event === 'save' && settings.diagnostics.trigger === 'onSave');
+
+// noolang.checkFile, whatever the trigger
+async function checkDocument(uri: string): Promise<boolean> {
+  const entry = documents.get(uri);
+  if (!entry) return false;
+  clearTimeout(typeCheckTimers.get(uri));
+  typeCheckTimers.delete(uri);
+  await checkTypes(entry);
+  return true;
+}
+
+function scheduleTypeCheck(entry: DocumentEntry, delayMs: number) {
+  clearTimeout(typeCheckTimers.get(entry.uri));
+  typeCheckTimers.set(
+    entry.uri,
+    setTimeout(() => {
+      typeCheckTimers.delete(entry.uri);
+      inBackground(`type check of ${entry.uri}`, checkTypes(entry));
+    }, delayMs),
+  );
+}
+
+// What the buffer alone shows, without the CLI
+const bufferDiagnostics = (uri: string, text: string) => ({
+  syntax: syntaxDiagnostics(text),
+  deprecations: deprecationDiagnostics(uri, text),
+  cycles: cycleDiagnostics(uri, text),
+});
+
+connection.onDidOpenTextDocument(
+  guarded('textDocument/didOpen', (params: DidOpenTextDocumentParams) => {
+    const { uri, text, version } = params.textDocument;
+    largeFile(uri, text);
+    // Broken imports come from the type check once the file is open
+    publishDiagnostics(uri, { ...bufferDiagnostics(uri, text), imports: [] }, version);
+    const entry = documents.open(uri, text, version);
+    if (checksOn('save')) inBackground(`type check of ${uri}`, checkTypes(entry));
+  }),
+);
+
+connection.onDidChangeTextDocument(
+  guarded('textDocument/didChange', async (params: DidChangeTextDocumentParams) => {
+    const entry = documents.get(params.textDocument.uri);
+    if (!entry) return;
+    let text = entry.text;
+    for (const change of params.contentChanges) {
+      // FULL sync: last change contains full text
+      if (typeof change.text === 'string') text = change.text;
+    }
+    // The buffer's syntax doesn't need the lock, so report it before waiting;
+    // a test run's results no longer line up with the edited buffer. A large
+    // file's wait for a save.
+    const stale = testRuns.delete(entry.uri);
+    const large = largeFile(entry.uri, text);
+    const update = { ...(large ? {} : bufferDiagnostics(entry.uri, text)), ...(stale ? { tests: [] } : {}) };
+    if (!large || stale) publishDiagnostics(entry.uri, update, params.textDocument.version);
+    await documents.update(entry, text, params.textDocument.version);
+    if (large) return;
+    refreshCycles(entry.uri);
+    if (checksOn('change')) scheduleTypeCheck(entry, TYPE_CHECK_DEBOUNCE_MS);
+  }),
+);
+
+connection.onDidSaveTextDocument(
+  guarded('textDocument/didSave', (params: DidSaveTextDocumentParams) => {
+    const entry = documents.get(params.textDocument.uri);
+    // A large file's buffer diagnostics were left for now
+    if (entry && largeFile(entry.uri, entry.text)) {
+      publishDiagnostics(entry.uri, bufferDiagnostics(entry.uri, entry.text));
+    }
+    if (entry && checksOn('save')) scheduleTypeCheck(entry, 0);
+    inBackground(`views of ${params.textDocument.uri}`, refreshViews(params.textDocument.uri, 'save'));
+  }),
+);
+
+connection.onDidCloseTextDocument(
+  guarded('textDocument/didClose', (params: DidCloseTextDocumentParams) => {
+    const uri = params.textDocument.uri;
+    clearTimeout(typeCheckTimers.get(uri));
+    typeCheckTimers.delete(uri);
+    diagnosticSources.delete(uri);
+    publishedVersions.delete(uri);
+    lineMaps.delete(uri);
+    largeFilesNoticed.delete(uri);
+    evaluatedBindings.delete(uri);
+    testRuns.delete(uri);
+    documents.close(uri);
+  }),
+);
+
+// --- File operations ---
+// Files created or deleted in the editor reach the index right away. A
+// deleted file's diagnostics go with it, and files importing it are
+// rechecked: open ones by the type checker, the rest from the index.
+function importDiagnostics(file: IndexedFile): Diagnostic[] {
+  const { text } = file;
+  return file.imports
+    .filter((site) => site.target && !fs.existsSync(site.target))
+    .map((site) => ({
+      range: Range.create(positionAt(text, site.specifierSpan.start), positionAt(text, site.specifierSpan.end)),
+      severity: DiagnosticSeverity.Error,
+      source: 'noolang',
+      message: `Failed to import '${site.specifier}': ${path.basename(site.target ?? '')} doesn't exist`,
+    }));
+}
+
+function recheckImporters(paths: string[]) {
+  // Cached type checks of importers saw the old module
+  typesCache.clear();
+  const open = openDocumentsByPath();
+  for (const file of workspace.all([...open.keys()])) {
+    if (!file.imports.some((site) => site.target && isUnder(site.target, paths))) continue;
+    const entry = open.get(file.filePath);
+    if (entry && checksOn('save')) scheduleTypeCheck(entry, 0);
+    else publishDiagnostics(URI.file(file.filePath).toString(), { imports: importDiagnostics(file) });
+  }
+}
+
+const operationPaths = (files: { uri: string }[]) =>
+  files.map((f) => uriToFilePath(f.uri)).filter((p): p is string => !!p);
+
+connection.workspace.onDidCreateFiles(
+  guarded('workspace/didCreateFiles', (params: CreateFilesParams) => {
+    const paths = operationPaths(params.files);
+    workspace.created(paths);
+    recheckImporters(paths);
+  }),
+);
+
+connection.workspace.onDidDeleteFiles(
+  guarded('workspace/didDeleteFiles', (params: DeleteFilesParams) => {
+    const paths = operationPaths(params.files);
+    workspace.deleted(paths);
+    for (const uri of [...diagnosticSources.keys()]) {
+      const filePath = uriToFilePath(uri);
+      if (!filePath || !isUnder(filePath, paths)) continue;
+      diagnosticSources.delete(uri);
+      testRuns.delete(uri);
+      connection.sendDiagnostics({ uri, diagnostics: [] });
+    }
+    recheckImporters(paths);
+  }),
+);
+
+// --- Notebooks ---
+// Each notebook's code cells are checked as one program, written to a file
+// of its own under the temp directory; diagnostics are split back per cell,
+// and hover and completion on a cell are answered against that file. Queries
+// of it resolve relative imports from the notebook's directory (the CLI's
+// --import-base), as the notebook's own code would.
+const notebooks = createNotebookStore();
+
+const NOTEBOOK_DIR = path.join(os.tmpdir(), `noolang-notebooks-${process.pid}`);
+
+// Combined files of notebooks on disk, with the notebook's directory
+const notebookImportBases = new Map<string, string>();
+
+function notebookFile(entry: NotebookEntry): string {
+  const id = createHash('sha1').update(entry.uri).digest('hex').slice(0, 12);
+  const name = path.basename(uriToFilePath(entry.uri) ?? 'notebook');
+  return path.join(NOTEBOOK_DIR, id, `${name}.noo`);
+}
+
+function writeNotebook(entry: NotebookEntry): CellSpan[] {
+  const { text, spans } = combineCells(entry);
+  const file = notebookFile(entry);
+  fs.mkdirSync(path.dirname(file), { recursive: true });
+  fs.writeFileSync(file, text);
+  const notebookPath = uriToFilePath(entry.uri);
+  if (notebookPath) notebookImportBases.set(file, path.dirname(notebookPath));
+  return spans;
+}
+
+const shiftRange = (range: Range, lines: number) =>
+  Range.create(range.start.line + lines, range.start.character, range.end.line + lines, range.end.character);
+
+// A position in a notebook cell, as the request on the combined file needs it
+function notebookTarget(cellUri: string, position: Position) {
+  const entry = notebooks.ofCell(cellUri);
+  const span = entry && combineCells(entry).spans.find((s) => s.uri === cellUri);
+  if (!entry || !span) return undefined;
+  const uri = URI.file(notebookFile(entry)).toString();
+  return { uri, position: Position.create(position.line + span.offset, position.character), offset: span.offset };
+}
+
+async function checkNotebook(entry: NotebookEntry) {
+  const version = entry.version;
+  const spans = combineCells(entry).spans;
+  const diagnostics = await getDiagnostics(notebookFile(entry));
+  if (notebooks.get(entry.uri) !== entry || entry.version !== version) return;
+  const byCell = new Map<string, Diagnostic[]>(spans.map((span) => [span.uri, []]));
+  for (const diagnostic of diagnostics) {
+    const span = spanAtLine(spans, diagnostic.range.start.line);
+    if (!span) continue;
+    // Related locations in the combined file move to their own cells
+    const relatedInformation = diagnostic.relatedInformation?.flatMap((info) => {
+      const at = spanAtLine(spans, info.location.range.start.line);
+      return at ? [{ ...info, location: { uri: at.uri, range: shiftRange(info.location.range, -at.offset) } }] : [];
+    });
+    const range = shiftRange(diagnostic.range, -span.offset);
+    byCell.get(span.uri)?.push({ ...diagnostic, range, relatedInformation });
+  }
+  for (const cell of entry.cells) {
+    if (cell.code) publishDiagnostics(cell.uri, { types: byCell.get(cell.uri) ?? [] });
+  }
+}
+
+function scheduleNotebookCheck(entry: NotebookEntry, delayMs: number) {
+  clearTimeout(typeCheckTimers.get(entry.uri));
+  typeCheckTimers.set(
+    entry.uri,
+    setTimeout(() => {
+      typeCheckTimers.delete(entry.uri);
+      inBackground(`type check of ${entry.uri}`, checkNotebook(entry));
+    }, delayMs),
+  );
+}
+
+function clearCellDiagnostics(uri: string) {
+  diagnosticSources.delete(uri);
+  connection.sendDiagnostics({ uri, diagnostics: [] });
+}
+
+const toNotebookCell = (cell: LspNotebookCell) => ({ uri: cell.document, code: cell.kind === NotebookCellKind.Code });
+
+connection.notebooks.synchronization.onDidOpenNotebookDocument(
+  guarded('notebookDocument/didOpen', (params: DidOpenNotebookDocumentParams) => {
+    const { uri, version, cells } = params.notebookDocument;
+    const entry = notebooks.open(uri, version, cells.map(toNotebookCell), params.cellTextDocuments);
+    for (const cell of params.cellTextDocuments) publishDiagnostics(cell.uri, { syntax: syntaxDiagnostics(cell.text) });
+    writeNotebook(entry);
+    scheduleNotebookCheck(entry, 0);
+  }),
+);
+
+connection.notebooks.synchronization.onDidChangeNotebookDocument(
+  guarded('notebookDocument/didChange', (params: DidChangeNotebookDocumentParams) => {
+    const entry = notebooks.get(params.notebookDocument.uri);
+    if (!entry) return;
+    entry.version = params.notebookDocument.version;
+    const { structure, textContent } = params.change.cells ?? {};
+    if (structure) {
+      const { start, deleteCount, cells = [] } = structure.array;
+      for (const cell of entry.cells.slice(start, start + deleteCount)) clearCellDiagnostics(cell.uri);
+      notebooks.splice(entry, start, deleteCount, cells.map(toNotebookCell));
+      for (const cell of structure.didOpen ?? []) {
+        notebooks.setText(entry, cell.uri, cell.text);
+        publishDiagnostics(cell.uri, { syntax: syntaxDiagnostics(cell.text) });
+      }
+    }
+    for (const { document, changes } of textContent ?? []) {
+      // FULL sync: last change contains full text
+      const last = changes[changes.length - 1];
+      if (!last) continue;
+      notebooks.setText(entry, document.uri, last.text);
+      publishDiagnostics(document.uri, { syntax: syntaxDiagnostics(last.text) });
+    }
+    writeNotebook(entry);
+    scheduleNotebookCheck(entry, TYPE_CHECK_DEBOUNCE_MS);
+  }),
+);
+
+connection.notebooks.synchronization.onDidCloseNotebookDocument(
+  guarded('notebookDocument/didClose', (params: DidCloseNotebookDocumentParams) => {
+    const entry = notebooks.close(params.notebookDocument.uri);
+    if (!entry) return;
+    clearTimeout(typeCheckTimers.get(entry.uri));
+    typeCheckTimers.delete(entry.uri);
+    for (const cell of entry.cells) clearCellDiagnostics(cell.uri);
+    notebookImportBases.delete(notebookFile(entry));
+    fs.rmSync(path.dirname(notebookFile(entry)), { recursive: true, force: true });
+  }),
+);
+
+// A cached type check is keyed on its own file only, so an edit to any module
+// it might import invalidates them all. ASTs don't depend on other files.
+connection.onDidChangeWatchedFiles(
+  guarded('workspace/didChangeWatchedFiles', () => {
+    typesCache.clear();
+  }),
+);
+
+connection.onCompletion((params: CompletionParams) => {
+  const cell = notebookTarget(params.textDocument.uri, params.position);
+  if (cell) {
+    const inCell = { ...params, textDocument: { uri: cell.uri }, position: cell.position };
+    return answer('textDocument/completion', () => completion(inCell), cell.uri);
+  }
+  return readDocument('textDocument/completion', params.textDocument.uri, () => completion(params));
+});
+
+async function completion(params: CompletionParams): Promise<CompletionItem[]> {
+  const patterns = await patternCompletion(params.textDocument.uri, params.position);
+  if (patterns.length > 0) return patterns;
+  const fields = await recordFieldCompletion(params.textDocument.uri, params.position);
+  if (fields.length > 0) return fields;
+  const items: CompletionItem[] = [];
+  const keywords = ['fn', 'if', 'then', 'else', 'match', 'with', 'variant', 'mut', 'constraint', 'implement'];
+  const ctors = ['True', 'False', 'Some', 'None', 'Ok', 'Err'];
+  const mk = (label: string, kind: CompletionItemKind) => ({
+    label,
+    kind,
+    detail: `Noolang ${kind === CompletionItemKind.Constructor ? 'constructor' : 'keyword'}`,
+    insertText: label,
+  });
+  items.push(...keywords.map((k) => mk(k, CompletionItemKind.Keyword)));
+  items.push(...ctors.map((c) => mk(c, CompletionItemKind.Constructor)));
+  for (const [name, builtin] of Object.entries(BUILTINS)) {
+    items.push({
+      label: name,
+      kind: builtin.signature.includes('->') ? CompletionItemKind.Function : CompletionItemKind.Variable,
+      detail: builtin.signature,
+      documentation: { kind: MarkupKind.Markdown, value: builtinDoc(builtin) },
+      insertText: name,
+    });
+  }
+  // The file's own top-level definitions, with their types as the detail
+  const filePath = uriToFilePath(params.textDocument.uri);
+  if (filePath && supports('--symbol-types')) {
+    const timeoutMs = settings.timeouts.completionMs;
+    const types = await optional(getSymbolTypes(filePath, [], timeoutMs), {});
+    const format = await optional(fileTypeFormatter(filePath, timeoutMs), typeFormatter(undefined));
+    const file = workspace.get(filePath);
+    const deprecated = file ? deprecatedNames(workspace, file) : new Map();
+    for (const [name, type] of Object.entries(types)) {
+      if (builtinNamed(name)) continue;
+      const kind = type?.includes('->') ? CompletionItemKind.Function : CompletionItemKind.Variable;
+      const tags = deprecated.has(name) ? { tags: [CompletionItemTag.Deprecated] } : {};
+      items.push({ label: name, kind, detail: type ? format(type) : undefined, insertText: name, ...tags });
+    }
+    await rankByExpectedType(params.textDocument.uri, filePath, params.position, items);
+  }
+  return items;
+}
+
+// --- Completion by expected type ---
+// Where the checker knows the type an expression needs (in a hole, after
+// `=`, as an argument), the bindings that have it come first, and functions
+// that give it once applied are offered as templates with holes for their
+// arguments, like `map ? ?`. Typed from the file on disk with the cursor's
+// line as the buffer has it; other unsaved lines leave the list unranked.
+async function getExpectedType(
+  filePath: string,
+  position: Position,
+  lineText: string,
+): Promise<ExpectedTypeResponse | undefined> {
+  if (!supports('--expected-type')) return undefined;
+  const [line, column] = [String(position.line + 1), String(position.character + 1)];
+  const cliArgs = withJson(['--expected-type', filePath, line, column, lineText]);
+  const res = await runFileCli(typesCache, filePath, cliArgs, settings.timeouts.completionMs);
+  return readResponse(cliArgs, res, readExpectedType);
+}
+
+// Whether the buffer matches the file on disk everywhere but one line
+function savedExceptLine(filePath: string, source: string, line: number): boolean {
+  if (!fs.existsSync(filePath)) return false;
+  const saved = fs.readFileSync(filePath, 'utf8').split(/\r?\n/);
+  const current = source.split(/\r?\n/);
+  return saved.length === current.length && saved.every((text, i) => i === line || text === current[i]);
+}
+
+async function rankByExpectedType(uri: string, filePath: string, position: Position, items: CompletionItem[]) {
+  const source = documentText(uri, filePath);
+  if (!savedExceptLine(filePath, source, position.line)) return;
+  const lineText = source.split(/\r?\n/)[position.line] ?? '';
+  const expected = await optional(getExpectedType(filePath, position, lineText), undefined);
+  if (!expected?.type) return;
+  const rank = (group: number, i: number) => `${group}${String(i).padStart(4, '0')}`;
+  const byLabel = new Map(items.map((item) => [item.label, item]));
+  expected.candidates.forEach((name, i) => {
+    const item = byLabel.get(name);
+    if (item) item.sortText = rank(0, i);
+  });
+  for (const item of items) item.sortText ??= `2${item.label}`;
+  const first = byLabel.get(expected.candidates.find((name) => byLabel.has(name)) ?? '');
+  if (first) first.preselect = true;
+  expected.templates.forEach((template, i) => {
+    const name = template.split(' ')[0];
+    const item = byLabel.get(name);
+    if (!item) return;
+    items.push({ ...item, label: template, insertText: template, filterText: name, sortText: rank(1, i) });
+  });
+}
+
+// --- Pattern completion ---
+// Where a match case starts, only the constructors of the type matched on,
+// as snippets with a placeholder per argument. Typed like completion by
+// expected type: from the file on disk with the cursor's line as the buffer
+// has it.
+async function getPatternType(
+  filePath: string,
+  position: Position,
+  lineText: string,
+): Promise<PatternTypeResponse | undefined> {
+  if (!supports('--pattern-type')) return undefined;
+  const [line, column] = [String(position.line + 1), String(position.character + 1)];
+  const cliArgs = withJson(['--pattern-type', filePath, line, column, lineText]);
+  const res = await runFileCli(typesCache, filePath, cliArgs, settings.timeouts.completionMs);
+  return readResponse(cliArgs, res, readPatternType);
+}
+
+async function patternCompletion(uri: string, position: Position): Promise<CompletionItem[]> {
+  const filePath = uriToFilePath(uri);
+  if (!filePath) return [];
+  const source = documentText(uri, filePath);
+  if (!inPatternPosition(source, offsetAt(source, position))) return [];
+  if (!savedExceptLine(filePath, source, position.line)) return [];
+  const lineText = source.split(/\r?\n/)[position.line] ?? '';
+  const matched = await optional(getPatternType(filePath, position, lineText), undefined);
+  return (matched?.constructors ?? []).map(({ name, arity }, i) => {
+    const args = Array.from({ length: arity }, (_, n) => ` \${${n + 1}:_}`).join('');
+    return {
+      label: name,
+      kind: CompletionItemKind.Constructor,
+      detail: matched?.type ?? undefined,
+      insertText: `${name}${args} => $0`,
+      insertTextFormat: InsertTextFormat.Snippet,
+      sortText: String(i).padStart(4, '0'),
+    };
+  });
+}
+
+// --- Record field completion ---
+// Where a field of a record literal goes, the fields of the record type
+// expected there that the literal doesn't have yet. The expected type comes
+// from --expected-type with the literal replaced by a hole, so the literal
+// has to start and end (if it's closed) on the cursor's line.
+async function recordFieldCompletion(uri: string, position: Position): Promise<CompletionItem[]> {
+  const filePath = uriToFilePath(uri);
+  if (!filePath) return [];
+  const source = documentText(uri, filePath);
+  const entry = recordEntryAt(source, offsetAt(source, position));
+  if (!entry || !savedExceptLine(filePath, source, position.line)) return [];
+  const open = positionAt(source, entry.open);
+  const close = entry.close === undefined ? undefined : positionAt(source, entry.close);
+  if (open.line !== position.line || (close && close.line !== position.line)) return [];
+  const lineText = source.split(/\r?\n/)[position.line] ?? '';
+  const withHole = `${lineText.slice(0, open.character)}?${close ? lineText.slice(close.character) : ''}`;
+  const expected = await optional(getExpectedType(filePath, open, withHole), undefined);
+  const fields = expected?.type ? recordFields(expected.type) : undefined;
+  if (!fields) return [];
+  const timeoutMs = settings.timeouts.completionMs;
+  const format = await optional(fileTypeFormatter(filePath, timeoutMs), typeFormatter(undefined));
+  const range = Range.create(positionAt(source, entry.replace.start), positionAt(source, entry.replace.end));
+  return fields
+    .filter((f) => !entry.present.includes(f.name))
+    .map((f, i) => ({
+      label: `@${f.name}`,
+      kind: CompletionItemKind.Field,
+      detail: format(f.type),
+      textEdit: TextEdit.replace(range, `@${f.name} `),
+      sortText: String(i).padStart(4, '0'),
+    }));
+}
+
+// --- Typed holes ---
+// Hovering a `?` shows the type the checker expects there and the bindings
+// in scope that have it, from one --holes query for the file.
+const MAX_HOLE_CANDIDATES = 10;
+
+async function getHoles(filePath: string): Promise<HolesResponse['holes']> {
+  if (!supports('--holes')) return [];
+  const cliArgs = withJson(['--holes', filePath]);
+  const res = await runFileCli(typesCache, filePath, cliArgs, settings.timeouts.hoverMs);
+  return readResponse(cliArgs, res, readHoles)?.holes ?? [];
+}
+
+async function holeHover(filePath: string, source: string, hole: SyntaxToken): Promise<Hover | null> {
+  const start = positionAt(source, hole.start);
+  const holes = await optional(getHoles(filePath), []);
+  const found = holes.find((h) => h.line === start.line + 1 && h.column === start.character + 1);
+  if (!found?.type) return null;
+  const format = await fileTypeFormatter(filePath, settings.timeouts.hoverMs);
+  const shown = found.candidates.slice(0, MAX_HOLE_CANDIDATES).map((c) => '`' + c + '`');
+  const more = found.candidates.length - shown.length;
+  const fits = shown.length ? `\n\nFits here: ${shown.join(', ')}${more > 0 ? `, and ${more} more` : ''}` : '';
+  const signature = '```noolang\n' + `${hole.text} : ${format(found.type)}` + '\n```';
+  return {
+    contents: { kind: MarkupKind.Markdown, value: signature + fits },
+    range: Range.create(start, positionAt(source, hole.end)),
+  };
+}
+
+// --- Import hover ---
+// What a module exports, from the workspace index, typed by the module's
+// top-level names; an export whose value isn't a plain name is listed
+// without a type
+const MAX_EXPORTS_SHOWN = 20;
+
+async function importHover(filePath: string, module: IndexedFile): Promise<Hover> {
+  const timeoutMs = settings.timeouts.hoverMs;
+  const types = supports('--symbol-types') ? await optional(getSymbolTypes(module.filePath, [], timeoutMs), {}) : {};
+  const format = await fileTypeFormatter(module.filePath, timeoutMs);
+  const lines = module.exports.slice(0, MAX_EXPORTS_SHOWN).map((e) => {
+    const type = e.local ? types[e.local] : undefined;
+    return type ? `@${e.field} : ${format(type)}` : `@${e.field}`;
+  });
+  const more = module.exports.length - lines.length;
+  if (more > 0) lines.push(`# and ${more} more`);
+  const heading = `Module \`${importSpecifier(filePath, module.filePath)}\``;
+  const body = lines.length ? '```noolang\n' + lines.join('\n') + '\n```' : 'No export record';
+  return { contents: { kind: MarkupKind.Markdown, value: `${heading}\n\n${body}` } };
+}
+
+// --- Type hover ---
+// On a type's name where a type goes (an annotation, a declaration), its
+// parameters, kind and constructors, from the workspace index: declared in
+// the file, a module it imports or the standard library. The checker's own
+// types have no declaration to read.
+const BUILTIN_TYPES: Record<string, string[]> = { Float: [], String: [], Unit: [], List: ['a'] };
+
+function typeDeclaration(
+  filePath: string,
+  name: string,
+): { module: IndexedFile; declaration: Declaration } | undefined {
+  for (const modulePath of [filePath, ...importedModules(filePath)]) {
+    const module = modulePath ? workspace.get(modulePath) : undefined;
+    const declaration = module?.declarations.find((d) => (d.kind === 'type' || d.kind === 'alias') && d.name === name);
+    if (module && declaration) return { module, declaration };
+  }
+  return undefined;
+}
+
+// The type named at the offset. A name that's a constructor too (`variant
+// Point = Point Float Float`) is the type only right after `variant` or
+// `type`, or after a `:` in the same statement.
+function typeNameAt(filePath: string, source: string, offset: number): string | undefined {
+  const tokens = lexSyntax(source).filter((t) => !isTrivia(t));
+  const at = tokens.findIndex((t) => t.start <= offset && offset < t.end);
+  const name = tokens[at];
+  if (name?.kind !== 'identifier' || !/^[A-Z]/.test(name.text)) return undefined;
+  if (!(name.text in BUILTIN_TYPES) && !typeDeclaration(filePath, name.text)) return undefined;
+  if (!declarationIn([filePath, ...importedModules(filePath)], name.text, 'constructor')) return name.text;
+  const before = tokens[at - 1];
+  if (before?.kind === 'keyword' && (before.text === 'variant' || before.text === 'type')) return name.text;
+  for (let i = at - 1; i >= 0 && tokens[i].text !== ';'; i--) if (tokens[i].text === ':') return name.text;
+  return undefined;
+}
+
+function typeHover(filePath: string, name: string): Hover {
+  const found = typeDeclaration(filePath, name);
+  const params = found?.declaration.params ?? BUILTIN_TYPES[name] ?? [];
+  const keyword = found ? (found.declaration.kind === 'alias' ? 'type ' : 'variant ') : '';
+  const heading = '```noolang\n' + [`${keyword}${name}`, ...params].join(' ') + '\n```';
+  const count = `${params.length} type parameter${params.length === 1 ? '' : 's'}`;
+  const kind = `${name} : ${count}, kind \`${Array(params.length + 1).fill('*').join(' -> ')}\``;
+  const constructors = (found?.module.declarations ?? [])
+    .filter((d) => d.kind === 'constructor' && d.container === name)
+    .map((d) => '`' + [d.name, d.args].filter(Boolean).join(' ') + '`');
+  const listed = constructors.length ? `\n\nConstructors: ${constructors.join(', ')}` : '';
+  return { contents: { kind: MarkupKind.Markdown, value: `${heading}\n\n${kind}${listed}` } };
+}
+
+// --- Position guards ---
+// Hover and navigation answer from the CLI's analysis of the file on disk,
+// while requests give positions in the buffer, which may have moved on. Each
+// open document keeps a line map from its buffer to the saved file (see
+// positions.ts), made for the buffer's version and the file's stamp and made
+// again when either changes. A position on a line edited since the save gets
+// nothing from the analysis, and results on such lines are left out.
+const lineMaps = new Map<string, { version: number; stamp: string; map: LineMap }>();
+
+function savedLines(uri: string, filePath: string): LineMap | undefined {
+  const entry = documents.get(uri);
+  const stamp = fileStamp(filePath);
+  if (!entry || !stamp) return undefined;
+  const cached = lineMaps.get(uri);
+  if (cached && cached.version === entry.version && cached.stamp === stamp) return cached.map;
+  const map = lineMap(entry.text, fs.readFileSync(filePath, 'utf8'));
+  lineMaps.set(uri, { version: entry.version, stamp, map });
+  return map;
+}
+
+// The buffer position as a position in the saved file
+function savedPosition(uri: string, filePath: string, position: Position): Position | undefined {
+  const map = savedLines(uri, filePath);
+  const line = map ? map.toSaved(position.line) : position.line;
+  return line === undefined ? undefined : Position.create(line, position.character);
+}
+
+function fromSavedRange(map: LineMap, range: Range): Range | undefined {
+  const [start, end] = [map.fromSaved(range.start.line), map.fromSaved(range.end.line)];
+  if (start === undefined || end === undefined) return undefined;
+  return Range.create(start, range.start.character, end, range.end.character);
+}
+
+// A range in the saved file as a range in the buffer
+function bufferRange(uri: string, filePath: string, range: Range): Range | undefined {
+  const map = savedLines(uri, filePath);
+  return map ? fromSavedRange(map, range) : range;
+}
+
+// The saved file's diagnostics on the lines of `text`, the buffer at the
+// version they're published for. Ones on lines edited since the save are
+// left out until the next check of the saved file.
+function onBufferLines(text: string, filePath: string, diagnostics: Diagnostic[]): Diagnostic[] {
+  let saved: string;
+  try {
+    saved = fs.readFileSync(filePath, 'utf8');
+  } catch {
+    return diagnostics;
+  }
+  if (saved === text) return diagnostics;
+  const map = lineMap(text, saved);
+  return diagnostics.flatMap((d) => {
+    const range = fromSavedRange(map, d.range);
+    return range ? [{ ...d, range }] : [];
+  });
+}
+
+function bufferLocation(uri: string, filePath: string, range: Range): Location | undefined {
+  const moved = bufferRange(uri, filePath, range);
+  return moved && Location.create(uri, moved);
+}
+
+connection.onHover((params: HoverParams) => {
+  const cell = notebookTarget(params.textDocument.uri, params.position);
+  if (!cell) return readDocument('textDocument/hover', params.textDocument.uri, () => hover(params));
+  return answer('textDocument/hover', async () => {
+    const result = await hover({ ...params, textDocument: { uri: cell.uri }, position: cell.position });
+    return result?.range ? { ...result, range: shiftRange(result.range, -cell.offset) } : result;
+  }, cell.uri);
+});
+
+async function hover(params: HoverParams): Promise<Hover | null> {
+  const uri = params.textDocument.uri;
+  const filePath = filePathOf(uri);
   const pos = params.position;
-  const type = getPositionType(filePath, pos.line + 1, pos.character + 1);
-  if (type) {
+  const source = documentText(uri, filePath);
+  const lineText = source.split(/\r?\n/)[pos.line] ?? '';
+  const op = extractOperatorAtPosition(lineText, pos.character);
+  if (op) {
+    const { signature, description } = op.info;
+    const code = signature ? '```noolang\n(' + op.operator + ') : ' + signature + '\n```\n\n' : '';
     return {
-      contents: { kind: MarkupKind.Markdown, value: 'Type: ' + type },
+      contents: { kind: MarkupKind.Markdown, value: code + description },
+      range: Range.create(pos.line, op.start, pos.line, op.end),
+    };
+  }
+  const offset = offsetAt(source, pos);
+  const hole = lexSyntax(source).find(
+    (t) => t.kind === 'punctuation' && t.text.startsWith('?') && t.start <= offset && offset < t.end,
+  );
+  if (hole) return holeHover(filePath, source, hole);
+  const module = importedModuleAt(filePath, pos);
+  if (module) return importHover(filePath, module);
+  const typeName = typeNameAt(filePath, source, offset);
+  if (typeName) return typeHover(filePath, typeName);
+  const saved = savedPosition(uri, filePath, pos);
+  if (!saved) return null;
+  const ast = await getAstFile(filePath, settings.timeouts.hoverMs);
+  const scheme = await getPositionType(filePath, saved.line + 1, saved.character + 1, ast);
+  // On a use of a polymorphic binding, the type it has in this call leads
+  const here = await getTypeAt(filePath, saved.line + 1, saved.character + 1);
+  const instantiated = here?.type ? typeFormatter(ast)(here.type) : undefined;
+  const type = instantiated ?? scheme;
+  const name = ast ? extractSymbolAtPosition(ast, saved.line + 1, saved.character + 1) : undefined;
+  const def = ast && name ? findDefinition(ast, name) : undefined;
+  const defined = def && bufferRange(uri, filePath, def.range);
+  const snippet = defined ? definitionSnippet(source, defined) : undefined;
+  // Unless the file defines its own
+  const builtin = name && !def ? builtinNamed(name) : undefined;
+  const value = hoverMarkdown(
+    type ?? builtin?.signature,
+    defined && snippet ? { snippet, filePath, line: defined.start.line } : undefined,
+    source,
+    filePath,
+    instantiated && scheme !== instantiated ? scheme : undefined,
+    here?.resolutions ?? [],
+    builtin,
+  );
+  const raw = here?.type ?? (name ? await getSymbolType(filePath, name, settings.timeouts.hoverMs) : undefined);
+  const expand = value && raw ? await expandTypeLink(uri, raw) : undefined;
+  if (value) {
+    return {
+      contents: { kind: MarkupKind.Markdown, value: expand ? `${value}\n\n${expand}` : value },
       range: Range.create(pos, Position.create(pos.line, pos.character + 1)),
     };
   }
-  const types = getTypeInfo(filePath);
+  const types = await getTypeInfo(filePath, settings.timeouts.hoverMs);
   if (types[0]) {
     return {
-      contents: { kind: MarkupKind.Markdown, value: 'Type: ' + simplifyTypeString(types[0]) },
+      contents: { kind: MarkupKind.Markdown, value: 'Type: ' + typeFormatter(ast)(types[0]) },
       range: Range.create(pos, Position.create(pos.line, pos.character + 1)),
     };
   }
   return null;
-});
+}
+
+// Custom request: the inferred type of an arbitrary selected expression,
+// for the extension's "type of selection" command.
+type TypeOfExpressionParams = { textDocument: { uri: string }; range: Range };
+
+connection.onRequest('noolang/typeOfExpression', (params: TypeOfExpressionParams) =>
+  readDocument('noolang/typeOfExpression', params.textDocument.uri, async () => {
+    const uri = params.textDocument.uri;
+    const filePath = filePathOf(uri);
+    const expr = textInRange(documentText(uri, filePath), params.range).trim();
+    if (!expr) return null;
+    const type = await getExpressionTypeInFile(filePath, expr);
+    return type ? { type: (await fileTypeFormatter(filePath))(type) } : null;
+  }),
+);
+
+// Custom request: evaluate the selection (or the expression under the
+// cursor) for inline-result decorations.
+type EvaluateExpressionParams = { textDocument: { uri: string }; range?: Range; position?: Position };
 
-connection.onDefinition((params: DefinitionParams) => {
+async function evaluateInDocument(params: EvaluateExpressionParams) {
   const uri = params.textDocument.uri;
-  const filePath = uriToFilePath(uri);
-  if (!filePath) return null;
-  const pos = params.position;
-  const ast = getAstFile(filePath);
-  if (!ast) return null;
-  const name = extractSymbolAtPosition(ast, pos.line + 1, pos.character + 1);
-  if (!name) return null;
-  const def = findDefinition(ast, name);
-  if (!def) return null;
-  return Location.create(uri, def.range);
-});
+  const filePath = filePathOf(uri);
+  const source = documentText(uri, filePath);
+  const expr = params.range
+    ? textInRange(source, params.range).trim()
+    : params.position
+      ? extractExpressionAtPosition(source.split(/\r?\n/)[params.position.line] ?? '', params.position.character)
+      : undefined;
+  if (!expr) return null;
+  const result = await underReadLock(uri, () => evaluateExpressionInFile(filePath, expr));
+  if ('error' in result) return new ResponseError(LSPErrorCodes.RequestFailed, result.error);
+  const entry = documents.get(uri);
+  if (entry && result.bindings) {
+    evaluatedBindings.set(uri, { version: entry.version, values: result.bindings });
+    if (inlineValueRefresh) void connection.languages.inlineValue.refresh();
+  }
+  const format = await fileTypeFormatter(filePath);
+  return { value: result.value, type: result.type && format(result.type) };
+}
+
+connection.onRequest('noolang/evaluateExpression', (params: EvaluateExpressionParams) =>
+  answer('noolang/evaluateExpression', () => evaluateInDocument(params), params.textDocument.uri),
+);
+
+// --- textDocument/inlineValue ---
+// After an evaluation, the values it gave the file's top-level bindings are
+// shown at their definitions until the document changes. Otherwise (a debug
+// session) the client is asked to look each binding up in the debugger.
+const evaluatedBindings = new Map<string, { version: number; values: Record<string, string> }>();
+let inlineValueRefresh = false;
 
-connection.onReferences((params: ReferenceParams) => {
+connection.languages.inlineValue.on((params: InlineValueParams) =>
+  readDocument('textDocument/inlineValue', params.textDocument.uri, () => inlineValues(params)),
+);
+
+async function inlineValues(params: InlineValueParams): Promise<InlineValue[]> {
   const uri = params.textDocument.uri;
-  const filePath = uriToFilePath(uri);
-  if (!filePath) return [];
-  const pos = params.position;
-  const ast = getAstFile(filePath);
+  const filePath = filePathOf(uri);
+  const ast = await getAstFile(filePath, settings.timeouts.navigationMs);
   if (!ast) return [];
-  const name = extractSymbolAtPosition(ast, pos.line + 1, pos.character + 1);
-  if (!name) return [];
-  // References in this file
-  const refs = findReferences(ast, name, uri);
-  return refs;
+  const inRange = (location: SourceLocation) =>
+    location.start.line - 1 >= params.range.start.line && location.start.line - 1 <= params.range.end.line;
+  const evaluated = evaluatedBindings.get(uri);
+  if (evaluated && evaluated.version === documents.get(uri)?.version) {
+    const lines = documentText(uri, filePath).split(/\r?\n/);
+    return topLevelStatements(ast).flatMap((node) => {
+      if (node.kind !== 'definition' && node.kind !== 'mutable-definition') return [];
+      const value = evaluated.values[node.name];
+      // Functions have nothing worth showing
+      if (value === undefined || value === '<function>' || !inRange(node.location)) return [];
+      // At the end of the line the definition ends on, as debuggers show them
+      const line = node.value.location.end.line - 1;
+      const end = Position.create(line, lines[line]?.length ?? 0);
+      return [InlineValueText.create(Range.create(end, end), `${node.name} = ${value}`)];
+    });
+  }
+  // Bindings the debugger can have reached: those defined up to where it stopped
+  const stopped = params.context.stoppedLocation.end.line;
+  const lookups: InlineValue[] = [];
+  forEachNode(ast.statements, (node) => {
+    if ((node.kind === 'definition' || node.kind === 'mutable-definition') && inRange(node.location)) {
+      if (node.location.start.line - 1 <= stopped) {
+        lookups.push(InlineValueVariableLookup.create(toRange(node.location), node.name, true));
+      }
+    }
+    return undefined;
+  });
+  return lookups;
+}
+
+const ORGANIZE_DEFINITIONS_KIND = `${CodeActionKind.Source}.organizeDefinitions`;
+
+function fullDocumentRange(text: string): Range {
+  const lines = text.split(/\r?\n/);
+  return Range.create(0, 0, lines.length - 1, lines[lines.length - 1].length);
+}
+
+function organizeDefinitionsEdit(uri: string): WorkspaceEdit | null {
+  const filePath = filePathOf(uri);
+  const source = documentText(uri, filePath);
+  const organized = organizeDefinitions(source);
+  if (organized === undefined || organized === source) return null;
+  return { changes: { [uri]: [TextEdit.replace(fullDocumentRange(source), organized)] } };
+}
+
+// The same formatter noofmt runs (format.ts); files that don't lex are left alone
+connection.onDocumentFormatting((params: DocumentFormattingParams) =>
+  answer('textDocument/formatting', (): TextEdit[] => {
+    const uri = params.textDocument.uri;
+    const filePath = filePathOf(uri);
+    const source = documentText(uri, filePath);
+    const formatted = formatSource(source);
+    if (formatted === undefined || formatted === source) return [];
+    return [TextEdit.replace(fullDocumentRange(source), formatted)];
+  }, params.textDocument.uri),
+);
+
+connection.languages.semanticTokens.on((params: SemanticTokensParams) =>
+  readDocument('textDocument/semanticTokens/full', params.textDocument.uri, () => semanticTokens(params)),
+);
+
+async function semanticTokens(params: SemanticTokensParams): Promise<SemanticTokens> {
+  const uri = params.textDocument.uri;
+  const filePath = filePathOf(uri);
+  const source = documentText(uri, filePath);
+  if (largeFile(uri, source)) return { data: [] };
+  // Without types every name is still classified, just never as effectful
+  const names = lexSyntax(source).filter((t) => t.kind === 'identifier').map((t) => t.text);
+  const types = await optional(namedTypes(filePath, names, settings.timeouts.hoverMs), {});
+  return { data: encodeSemanticTokens(classifySemanticTokens(source, types)) };
+}
+
+// Checker errors that an application chain grouped the wrong way tends to give
+const MISAPPLIED = /Function application type mismatch|Cannot apply non-function|Too many arguments/;
+
+// How many arguments `name` takes: from its type when the CLI has one, else
+// from its definition in the source or the builtin table
+function knownArity(source: string, name: string, types: Record<string, string | null> = {}): number | undefined {
+  const parameters = (type?: string | null) => (type ? builtinParameters(type)?.parameters.length : undefined);
+  const typed = Object.prototype.hasOwnProperty.call(types, name) ? types[name] : undefined;
+  return parameters(typed) ?? definedArity(source, name) ?? parameters(builtinNamed(name)?.signature);
+}
+
+// One fix per way of parenthesizing the rejected chain, titled with the result
+function precedenceFixes(uri: string, diagnostics: Diagnostic[]): CodeAction[] {
+  const source = documentText(uri, filePathOf(uri));
+  const at = (offset: number) => positionAt(source, offset);
+  const arity = (name: string) => knownArity(source, name);
+  return diagnostics
+    .filter((d) => d.source === 'noolang' && MISAPPLIED.test(d.message))
+    .flatMap((diagnostic) => {
+      const call = (diagnostic.data as { call?: Position } | undefined)?.call ?? diagnostic.range.start;
+      const groupings = applicationGroupings(source, offsetAt(source, call), arity);
+      return groupings.map(({ open, close, text }) => {
+        const brackets = [TextEdit.insert(at(open), '('), TextEdit.insert(at(close), ')')];
+        return {
+          title: `Add parentheses: ${text}`,
+          kind: CodeActionKind.QuickFix,
+          diagnostics: [diagnostic],
+          isPreferred: groupings.length === 1,
+          edit: { changes: { [uri]: brackets } },
+        };
+      });
+    });
+}
+
+// Rewrites of the lambda at the cursor; eta-reduction needs the arity of
+// the function its body applies, so that's typed
+async function lambdaActions(uri: string, range: Range): Promise<CodeAction[]> {
+  const filePath = filePathOf(uri);
+  const source = documentText(uri, filePath);
+  const lambda = lambdaAt(source, offsetAt(source, range.start));
+  if (!lambda) return [];
+  const head = lexSyntax(source.slice(lambda.body.start, lambda.body.end)).find((t) => t.kind === 'identifier');
+  const types = head ? await optional(namedTypes(filePath, [head.text], settings.timeouts.hoverMs), {}) : {};
+  return lambdaRewrites(source, lambda, (name) => knownArity(source, name, types)).map(({ title, span, text }) => {
+    const replaced = Range.create(positionAt(source, span.start), positionAt(source, span.end));
+    const edit = { changes: { [uri]: [TextEdit.replace(replaced, text)] } };
+    return { title, kind: CodeActionKind.RefactorRewrite, edit };
+  });
+}
+
+// On the line of a top-level binding of a record or tuple, destructuring
+// it; typed from the saved file, so only while the buffer matches it
+async function destructureActions(uri: string, range: Range): Promise<CodeAction[]> {
+  const filePath = filePathOf(uri);
+  const source = documentText(uri, filePath);
+  if (!savedExceptLine(filePath, source, -1)) return [];
+  const ast = await optional(getAstFile(filePath, settings.timeouts.hoverMs), undefined);
+  const definition = topLevelStatements(ast ?? { statements: [], errors: [] }).find(
+    (node) => node.kind === 'definition' && node.location.start.line - 1 === range.start.line,
+  );
+  if (!ast || definition?.kind !== 'definition') return [];
+  const types = await optional(getSymbolTypes(filePath, [], settings.timeouts.hoverMs), {});
+  const type = Object.prototype.hasOwnProperty.call(types, definition.name) ? types[definition.name] : null;
+  const parsed = type ? parseType(type) : undefined;
+  const rewrite = parsed && destructureBinding(source, ast.statements, definition, parsed);
+  if (!rewrite) return [];
+  const edits = rewrite.edits.map(({ span, text }) =>
+    TextEdit.replace(Range.create(positionAt(source, span.start), positionAt(source, span.end)), text),
+  );
+  return [{ title: rewrite.title, kind: CodeActionKind.RefactorRewrite, edit: { changes: { [uri]: edits } } }];
+}
+
+// On a top-level function of several parameters, taking them as one record
+// instead, its calls across the workspace following (record-params.ts). Not
+// offered when a use can't follow, when it calls itself, or when one call is
+// an argument of another
+function recordParameterActions(uri: string, range: Range): CodeAction[] {
+  const file = workspace.get(filePathOf(uri));
+  const found = file && recordParameterAt(file.text, offsetAt(file.text, range.start));
+  if (!file || !found) return [];
+  const open = openDocumentsByPath();
+  const target = { kind: 'name' as const, name: found.name, span: found.nameSpan };
+  const plan = planRename(workspace, file, target, [...open.keys()]);
+  if (conflictingFiles(workspace, plan).length > 0) return [];
+  const overlap = (a: Span, b: Span) => a.start < b.end && b.start < a.end;
+  const changes: Record<string, TextEdit[]> = {};
+  // The defining file takes the lambda's edit even with no calls in it
+  const files = new Map<string, { spans: Span[] }>([[file.filePath, { spans: [] }], ...plan.edits]);
+  for (const [filePath, { spans }] of files) {
+    const text = workspace.get(filePath)?.text ?? '';
+    const sites = spans.map((span) => callSiteAt(text, span, found.params));
+    const edits: SpanEdit[] = sites.flatMap((site) => (site.kind === 'call' ? [site.edit] : []));
+    if (filePath === file.filePath) edits.push(found.edit);
+    if (sites.some((site) => site.kind === 'blocked')) return [];
+    if (edits.some((a, i) => edits.some((b, j) => i !== j && overlap(a.span, b.span)))) return [];
+    const range = (span: Span) => Range.create(positionAt(text, span.start), positionAt(text, span.end));
+    const fileUri = open.get(filePath)?.uri ?? URI.file(filePath).toString();
+    changes[fileUri] = edits.map((e) => TextEdit.replace(range(e.span), e.text));
+  }
+  const title = `Take the parameters of ${found.name} as a record`;
+  return [{ title, kind: CodeActionKind.RefactorRewrite, edit: { changes } }];
+}
+
+const UNDEFINED_VARIABLE = /Undefined variable\s+Variable: (\S+)/;
+
+// "Import X from ./module" for each module in the workspace that exports a
+// name the checker couldn't find: into an import of that module that already
+// destructures fields, or else as a new import at the top of the file
+function importFixes(uri: string, diagnostics: Diagnostic[]): CodeAction[] {
+  const filePath = filePathOf(uri);
+  const file = workspace.get(filePath);
+  if (!file) return [];
+  return diagnostics.flatMap((diagnostic) => {
+    const name = diagnostic.source === 'noolang' ? UNDEFINED_VARIABLE.exec(diagnostic.message)?.[1] : undefined;
+    if (!name) return [];
+    const modules = workspace
+      .exportersOf(name, [...openDocumentsByPath().keys()])
+      .filter((m) => m.filePath !== filePath);
+    return modules.map((module) => {
+      const specifier = importSpecifier(filePath, module.filePath);
+      const site = file.imports.find((s) => s.target === module.filePath && s.fields.length > 0);
+      const last = site?.fields[site.fields.length - 1];
+      const insert = last
+        ? TextEdit.insert(positionAt(file.text, (last.localSpan ?? last.fieldSpan).end), `, @${name}`)
+        : TextEdit.insert(Position.create(0, 0), `{@${name}} = import ${JSON.stringify(specifier)};\n`);
+      return {
+        title: `Import ${name} from ${specifier}`,
+        kind: CodeActionKind.QuickFix,
+        diagnostics: [diagnostic],
+        isPreferred: modules.length === 1,
+        edit: { changes: { [uri]: [insert] } },
+      };
+    });
+  });
+}
+
+connection.onCodeAction((params: CodeActionParams) =>
+  answer('textDocument/codeAction', async (): Promise<CodeAction[]> => {
+    const uri = params.textDocument.uri;
+    const wanted = (kind: string) => !params.context.only || params.context.only.some((k) => kind.startsWith(k));
+    const { diagnostics } = params.context;
+    const actions = wanted(CodeActionKind.QuickFix)
+      ? [...importFixes(uri, diagnostics), ...precedenceFixes(uri, diagnostics)]
+      : [];
+    if (wanted(CodeActionKind.RefactorRewrite)) {
+      actions.push(...(await lambdaActions(uri, params.range)), ...(await destructureActions(uri, params.range)));
+      actions.push(...recordParameterActions(uri, params.range));
+    }
+    const edit = wanted(ORGANIZE_DEFINITIONS_KIND) ? organizeDefinitionsEdit(uri) : null;
+    if (edit) actions.push({ title: 'Organize definitions', kind: ORGANIZE_DEFINITIONS_KIND, edit });
+    return actions;
+  }, params.textDocument.uri),
+);
+
+// --- Inlay hints ---
+// Types after top-level bindings and their functions' parameters, from the
+// file's symbol table, and the type crossing each operator of a pipeline,
+// after the operator, from one --pipeline-types query; each kind can be
+// turned off (noolang.inlayHints). Both read the file on disk, so a buffer
+// with unsaved changes gets none until it's saved.
+let inlayHintRefresh = false;
+
+async function getPipelineTypes(filePath: string): Promise<PipelineTypesResponse['pipelines']> {
+  if (!supports('--pipeline-types')) return [];
+  const cliArgs = withJson(['--pipeline-types', filePath]);
+  const res = await runFileCli(typesCache, filePath, cliArgs, settings.timeouts.hoverMs);
+  return readResponse(cliArgs, res, readPipelineTypes)?.pipelines ?? [];
+}
+
+connection.languages.inlayHint.on((params: InlayHintParams) =>
+  readDocument('textDocument/inlayHint', params.textDocument.uri, () => inlayHints(params)),
+);
+
+const typeHint = (position: Position, type: string, paddingLeft = false): InlayHint => ({
+  position,
+  label: paddingLeft ? type : `: ${type}`,
+  kind: InlayHintKind.Type,
+  paddingLeft,
 });
 
-connection.onDocumentSymbol((params: DocumentSymbolParams) => {
+async function inlayHints(params: InlayHintParams): Promise<InlayHint[]> {
   const uri = params.textDocument.uri;
-  const filePath = uriToFilePath(uri);
-  if (!filePath) return [];
-  const ast = getAstFile(filePath);
-  if (!ast) return [];
-  return extractAllSymbols(ast, uri);
+  const filePath = filePathOf(uri);
+  const source = documentText(uri, filePath);
+  if (largeFile(uri, source)) return [];
+  if (!fs.existsSync(filePath) || fs.readFileSync(filePath, 'utf8') !== source) return [];
+  const { bindings, parameters, pipelines } = settings.inlayHints;
+  const format = await optional(fileTypeFormatter(filePath, settings.timeouts.hoverMs), typeFormatter(undefined));
+  const hints = [
+    ...(bindings || parameters ? await definitionHints(filePath, source, format) : []),
+    ...(pipelines ? await pipelineHints(filePath, source, format) : []),
+  ];
+  const { start, end } = params.range;
+  return hints.filter((h) => h.position.line >= start.line && h.position.line <= end.line);
+}
+
+// Unannotated top-level definitions: the type after the name, and for a
+// function each parameter's after the parameter
+async function definitionHints(filePath: string, source: string, format: (type: string) => string) {
+  const ast = await optional(getAstFile(filePath, settings.timeouts.hoverMs), undefined);
+  const types = ast ? await optional(getSymbolTypes(filePath, [], settings.timeouts.hoverMs), {}) : {};
+  const at = (p: SourcePosition) => Position.create(p.line - 1, p.column - 1);
+  return topLevelStatements(ast ?? { statements: [], errors: [] }).flatMap((node): InlayHint[] => {
+    if (node.kind !== 'definition' && node.kind !== 'mutable-definition') return [];
+    const type = Object.prototype.hasOwnProperty.call(types, node.name) ? types[node.name] : null;
+    if (!type || node.value.kind === 'typed' || node.value.kind === 'constrained') return [];
+    const hints = settings.inlayHints.bindings ? [typeHint(at(node.location.end), format(type))] : [];
+    if (node.value.kind !== 'function' || !settings.inlayHints.parameters) return hints;
+    const lambda = lambdaAt(source, offsetAt(source, at(node.value.location.start)));
+    const parameterTypes = builtinParameters(type)?.parameters ?? [];
+    return [
+      ...hints,
+      ...(lambda?.params ?? []).flatMap((p, i) =>
+        parameterTypes[i] ? [typeHint(positionAt(source, p.end), format(parameterTypes[i]))] : [],
+      ),
+    ];
+  });
+}
+
+async function pipelineHints(filePath: string, source: string, format: (type: string) => string) {
+  const pipelines = await optional(getPipelineTypes(filePath), []);
+  return pipelines.flatMap(({ line, column, types }) => {
+    const from = offsetAt(source, { line: line - 1, character: column - 1 });
+    return pipelineOperators(source, from, types.length).flatMap((operator, i) => {
+      const type = types[i];
+      return type ? [typeHint(positionAt(source, operator.end), format(type), true)] : [];
+    });
+  });
+}
+
+// --- Tests ---
+// *.test.noo suites get a "Run tests" lens, and after a run each test_case
+// call shows its result. Failures are also published as diagnostics, and the
+// whole report goes out as a noolang/testResults notification.
+const TEST_TIMEOUT_MS = 30_000;
+
+const testRuns = new Map<string, TestReport | { error: string }>();
+let codeLensRefresh = false;
+
+connection.onCodeLens((params: CodeLensParams) =>
+  answer('textDocument/codeLens', async (): Promise<CodeLens[]> => {
+    const uri = params.textDocument.uri;
+    const filePath = filePathOf(uri);
+    const lenses = [...testLenses(uri, filePath), ...implementationLenses(uri, filePath)];
+    return [...lenses, ...(await annotationLenses(uri, filePath))];
+  }, params.textDocument.uri),
+);
+
+function testLenses(uri: string, filePath: string): CodeLens[] {
+  if (!isTestFile(filePath)) return [];
+  const source = documentText(uri, filePath);
+  const run = testRuns.get(uri);
+  const command = (title: string) => ({ title, command: 'noolang.runTests', arguments: [uri] });
+  const summary = !run ? '' : 'error' in run ? ' (did not run)' : ` (${run.passed} passed, ${run.failed} failed)`;
+  const lenses: CodeLens[] = [{ range: Range.create(0, 0, 0, 0), command: command(`▶ Run tests${summary}`) }];
+  if (!run || 'error' in run) return lenses;
+  for (const { site, result } of matchResults(findTestSites(source), run.cases)) {
+    const range = Range.create(positionAt(source, site.start), positionAt(source, site.end));
+    lenses.push({ range, command: command(result.passed ? '✓ passed' : '✗ failed') });
+  }
+  return lenses;
+}
+
+async function runTests(uri: string) {
+  const filePath = filePathOf(uri);
+  if (!isTestFile(filePath)) return null;
+  const version = documents.get(uri)?.version;
+  const res = await runNodeCli(['--test-file', filePath], { timeoutMs: TEST_TIMEOUT_MS, runsUserCode: true });
+  const report = res.timedOut ? undefined : parseTestReport(res.stdout);
+  const run = report ?? {
+    error: res.timedOut ? `Tests timed out after ${TEST_TIMEOUT_MS}ms` : cleanErrorMessage(res.stderr || res.stdout),
+  };
+  testRuns.set(uri, run);
+  const source = documentText(uri, filePath);
+  const failure = (range: Range, message: string): Diagnostic => ({
+    range,
+    severity: DiagnosticSeverity.Error,
+    source: 'noolang-test',
+    message,
+  });
+  const diagnostics =
+    'error' in run
+      ? [failure(Range.create(0, 0, 0, 0), run.error)]
+      : matchResults(findTestSites(source), run.cases)
+          .filter(({ result }) => !result.passed)
+          .map(({ site, result }) =>
+            failure(
+              Range.create(positionAt(source, site.start), positionAt(source, site.end)),
+              `Test failed: ${result.name}${result.message ? `\n${result.message}` : ''}`,
+            ),
+          );
+  publishDiagnostics(uri, { tests: diagnostics }, version);
+  connection.sendNotification('noolang/testResults', { uri, ...run });
+  if (codeLensRefresh) void connection.sendRequest(CodeLensRefreshRequest.type);
+  return run;
+}
+
+// --- noolang/showAst ---
+// How the file (or the selection) parses, for the extension's syntax tree
+// view: an outline of the nodes or the AST as the CLI gives it, in JSON. A
+// saved file is parsed with error recovery; unsaved text as it stands, so a
+// syntax error there leaves no tree.
+type ShowAstParams = { textDocument: { uri: string }; range?: Range; format?: 'tree' | 'json' };
+
+async function documentAst(uri: string, filePath: string): Promise<AstProgram | undefined> {
+  const source = documentText(uri, filePath);
+  if (savedExceptLine(filePath, source, -1)) return getAstFile(filePath, settings.timeouts.navigationMs);
+  const cliArgs = ['--ast', source];
+  return readResponse(cliArgs, await runNodeCli(cliArgs), readAst);
+}
+
+// The outermost nodes inside the range; failing that, the innermost one
+// around its start
+function nodesInRange(ast: AstProgram, range: Range): AstNode[] {
+  const before = (a: SourcePosition, b: Position) =>
+    a.line - 1 < b.line || (a.line - 1 === b.line && a.column - 1 <= b.character);
+  const after = (a: SourcePosition, b: Position) =>
+    a.line - 1 > b.line || (a.line - 1 === b.line && a.column - 1 >= b.character);
+  const inside = outermost(
+    ast.statements,
+    (n) => !!n.location && after(n.location.start, range.start) && before(n.location.end, range.end),
+  );
+  if (inside.length > 0) return inside;
+  let around: AstNode | undefined;
+  walkNodes(ast.statements, (n) => {
+    if (containsPosition(n.location, range.start.line + 1, range.start.character + 1)) around = n;
+    return undefined;
+  });
+  return around ? [around] : [];
+}
+
+async function showAst(params: ShowAstParams) {
+  const uri = params.textDocument.uri;
+  const filePath = filePathOf(uri);
+  const ast = await documentAst(uri, filePath);
+  if (!ast) return new ResponseError(LSPErrorCodes.RequestFailed, 'The file does not parse');
+  const range = params.range;
+  const selected = range && (range.start.line !== range.end.line || range.start.character !== range.end.character);
+  const nodes = range && selected ? nodesInRange(ast, range) : ast.statements;
+  if (params.format === 'json') return { format: 'json', text: JSON.stringify(nodes, null, 2) };
+  const errors = ast.errors.map((e) => `# ${e.message}`);
+  return { format: 'tree', text: [...errors, ...syntaxTree(nodes)].join('\n') };
+}
+
+connection.onRequest('noolang/showAst', (params: ShowAstParams) =>
+  readDocument('noolang/showAst', params.textDocument.uri, () => showAst(params)),
+);
+
+// --- Generated documents ---
+// Read-only views the server renders, served under the `noolang:` scheme;
+// the extension shows them through noolang/virtualDocument. Benchmark
+// reports stay as they were made. Views of a source file (see views.ts) are
+// kept in a document store of their own while the editor has them open, and
+// rendered again when their source changes, the extension being told with
+// noolang/virtualDocumentChanged.
+const virtualDocuments = new Map<string, string>();
+const generatedDocuments = createDocumentStore();
+
+async function renderView({ kind, source }: View): Promise<string> {
+  const filePath = filePathOf(source);
+  const failed = (e: unknown) => `# ${e instanceof ResponseError || e instanceof Error ? e.message : String(e)}`;
+  try {
+    switch (kind) {
+      case 'ast':
+      case 'ast-json': {
+        const result = await showAst({ textDocument: { uri: source }, format: kind === 'ast' ? 'tree' : 'json' });
+        return result instanceof ResponseError ? failed(result) : result.text;
+      }
+      case 'types': {
+        const types = await getSymbolTypes(filePath, [], settings.timeouts.navigationMs);
+        const format = await fileTypeFormatter(filePath, settings.timeouts.navigationMs);
+        return Object.entries(types)
+          .map(([name, type]) => `${name} : ${type ? format(type) : '?'}`)
+          .join('\n');
+      }
+      case 'evaluation': {
+        const result = await evaluateExpressionInFile(filePath, '{}');
+        if ('error' in result) return failed(result.error);
+        return Object.entries(result.bindings ?? {})
+          .map(([name, value]) => `${name} = ${value}`)
+          .join('\n');
+      }
+    }
+  } catch (e) {
+    return failed(e);
+  }
+}
+
+connection.onRequest('noolang/virtualDocument', (params: { uri: string }) =>
+  answer('noolang/virtualDocument', async () => {
+    const report = virtualDocuments.get(params.uri);
+    if (report !== undefined) return report;
+    const view = parseViewUri(params.uri);
+    if (!view) return null;
+    const open = generatedDocuments.get(params.uri);
+    if (open) return open.text;
+    return generatedDocuments.open(params.uri, await renderView(view), 1).text;
+  }),
+);
+
+connection.onNotification('noolang/virtualDocumentClosed', (params: { uri: string }) => {
+  generatedDocuments.close(params.uri);
 });
 
+// After `source` changes (or, with 'save', is saved): its open views that
+// now read differently
+async function refreshViews(source: string, on: 'change' | 'save') {
+  for (const entry of generatedDocuments.all()) {
+    const view = parseViewUri(entry.uri);
+    if (!view || view.source !== source || (on === 'change' && REFRESHED_ON[view.kind] === 'save')) continue;
+    const text = await renderView(view);
+    if (text === entry.text || generatedDocuments.get(entry.uri) !== entry) continue;
+    await generatedDocuments.update(entry, text, entry.version + 1);
+    connection.sendNotification('noolang/virtualDocumentChanged', { uri: entry.uri });
+  }
+}
+
+const VIEW_KINDS: ViewKind[] = ['ast', 'ast-json', 'types', 'evaluation'];
+
+// For the extension's commands: where a view of the document is
+function openView(source: string, kind: unknown) {
+  const view = VIEW_KINDS.find((k) => k === kind);
+  return view ? { uri: viewUri({ kind: view, source }) } : null;
+}
+
+const BENCHMARK_TIMEOUT_MS = 60_000;
+
+// Runs the whole file, effects included, as `noo --benchmark` does
+async function benchmarkFile(uri: string) {
+  const filePath = filePathOf(uri);
+  const res = await runNodeCli(['--benchmark', filePath], { timeoutMs: BENCHMARK_TIMEOUT_MS, runsUserCode: true });
+  const failed = (message: string) => new ResponseError(LSPErrorCodes.RequestFailed, message);
+  if (res.timedOut) return failed(`Benchmark timed out after ${BENCHMARK_TIMEOUT_MS}ms`);
+  const result = parseBenchmark(res.stdout);
+  if (res.status !== 0 || !result) return failed(cleanErrorMessage(res.stderr || res.stdout || 'Benchmark failed'));
+  const name = path.basename(filePath);
+  const reportUri = `noolang:/benchmark/${encodeURIComponent(name)}.md`;
+  virtualDocuments.set(reportUri, benchmarkReport(name, result));
+  return { uri: reportUri, ...result };
+}
+
+// --- REPL ---
+// Selections sent with noolang.sendToRepl run in a REPL kept per workspace
+// folder; what it prints comes back as noolang/replOutput notifications.
+let workspaceFolders: string[] = WORKSPACE ? [WORKSPACE] : [];
+
+const repls = createReplSessions(CLI_RUNTIME, CLI_PATH, (output) =>
+  connection.sendNotification('noolang/replOutput', output),
+);
+
+// The innermost workspace folder holding the file, else the file's directory
+function workspaceOf(filePath: string): string {
+  const containing = workspaceFolders.filter((f) => filePath.startsWith(f + path.sep));
+  return containing.sort((a, b) => b.length - a.length)[0] ?? path.dirname(filePath);
+}
+
+// `code` as given, else the range's text, else the whole document
+type SendToReplParams = { textDocument: { uri: string }; range?: Range; code?: string };
+
+function sendToRepl(params: SendToReplParams) {
+  const uri = params.textDocument.uri;
+  const filePath = filePathOf(uri);
+  const source = params.code === undefined ? documentText(uri, filePath) : '';
+  const code = params.code ?? (params.range ? textInRange(source, params.range) : source);
+  const workspace = workspaceOf(filePath);
+  repls.send(workspace, code);
+  return { workspace };
+}
+
+// --- Annotation lenses ---
+// Above each unannotated top-level definition, its inferred type; clicking
+// it appends the annotation, `name = value : Type`. Typed from the saved
+// file, so only while the buffer matches it.
+async function annotationLenses(uri: string, filePath: string): Promise<CodeLens[]> {
+  if (!settings.codeLens.annotations || !supports('--symbol-types')) return [];
+  const source = documentText(uri, filePath);
+  if (!savedExceptLine(filePath, source, -1)) return [];
+  const timeoutMs = settings.timeouts.hoverMs;
+  const ast = await optional(getAstFile(filePath, timeoutMs), undefined);
+  const types = ast ? await optional(getSymbolTypes(filePath, [], timeoutMs), {}) : {};
+  const format = await optional(fileTypeFormatter(filePath, timeoutMs), typeFormatter(undefined));
+  const at = (p: SourcePosition) => Position.create(p.line - 1, p.column - 1);
+  const version = documents.get(uri)?.version ?? null;
+  return topLevelStatements(ast ?? { statements: [], errors: [] }).flatMap((node): CodeLens[] => {
+    if (node.kind !== 'definition' && node.kind !== 'mutable-definition') return [];
+    const type = Object.prototype.hasOwnProperty.call(types, node.name) ? types[node.name] : null;
+    if (!type || node.value.kind === 'typed' || node.value.kind === 'constrained') return [];
+    const start = at(node.location.start);
+    const title = `${node.name} : ${format(type)}`;
+    const args = [uri, version, at(node.location.end), type];
+    const command = { title, command: 'noolang.insertAnnotation', arguments: args };
+    return [{ range: Range.create(start, start), command }];
+  });
+}
+
+// Refused when the document has changed since the lens was made
+function insertAnnotation(uri: string, version: number | null, position: Position, type: string) {
+  const entry = documents.get(uri);
+  if (entry && entry.version !== version) return false;
+  const edit = TextEdit.insert(position, ` : ${type}`);
+  const workspaceEdit: WorkspaceEdit = documentChanges
+    ? { documentChanges: [TextDocumentEdit.create({ uri, version: entry ? entry.version : null }, [edit])] }
+    : { changes: { [uri]: [edit] } };
+  return applyWorkspaceEdit('Insert type annotation', workspaceEdit);
+}
+
+// --- workspace/applyEdit ---
+// Edits the server makes itself, from commands, rather than ones handed back
+// for the client to apply (code actions, rename). A refused edit is logged
+// with the client's reason; documents an applied edit touched are checked
+// again straight away instead of after the typing debounce, when changes
+// trigger checks.
+async function applyWorkspaceEdit(label: string, edit: WorkspaceEdit): Promise<boolean> {
+  const result = await connection.workspace.applyEdit({ label, edit });
+  if (!result.applied) {
+    log.warn(`${label}: edit not applied${result.failureReason ? ` (${result.failureReason})` : ''}`);
+    return false;
+  }
+  const uris = [
+    ...Object.keys(edit.changes ?? {}),
+    ...(edit.documentChanges ?? []).flatMap((change) => ('textDocument' in change ? [change.textDocument.uri] : [])),
+  ];
+  for (const uri of new Set(uris)) {
+    const entry = documents.get(uri);
+    if (entry && checksOn('change') && !largeFile(uri, entry.text)) scheduleTypeCheck(entry, 0);
+  }
+  return true;
+}
+
+// --- workspace/executeCommand ---
+// Server-side commands that code lenses and code actions can trigger. Every
+// entry is advertised in executeCommandProvider at initialize.
+type CommandHandler = (args: any[]) => unknown;
+
+const commands: Record<string, CommandHandler> = {
+  'noolang.evaluate': ([params]) => (params?.textDocument ? evaluateInDocument(params) : null),
+  // Spawned calls are a fresh CLI process each, so restarting the bridge
+  // amounts to lifting any backoff, unloading an in-process compiler and
+  // dropping everything derived from earlier runs.
+  'noolang.restartBridge': () => {
+    supervisor.reset();
+    backend.reset();
+    // The CLI may have been rebuilt since startup
+    cliCapabilities = undefined;
+    void probeCapabilities();
+    stdlibIndex = undefined;
+    astCache.clear();
+    typesCache.clear();
+    log.info('Noolang bridge state cleared');
+    return null;
+  },
+  'noolang.sendToRepl': ([params]) => (params?.textDocument ? sendToRepl(params) : null),
+  'noolang.benchmarkFile': ([uri]) => (typeof uri === 'string' ? benchmarkFile(uri) : null),
+  'noolang.openView': ([uri, kind]) => (typeof uri === 'string' ? openView(uri, kind) : null),
+  'noolang.checkDocument': ([uri]) => (typeof uri === 'string' ? checkDocument(uri) : false),
+  'noolang.runTests': ([uri]) => (typeof uri === 'string' ? runTests(uri) : null),
+  'noolang.expandType': ([uri, type]) =>
+    typeof uri === 'string' && typeof type === 'string' ? expandType(uri, type) : null,
+  'noolang.insertAnnotation': ([uri, version, position, type]) =>
+    typeof uri === 'string' && typeof type === 'string'
+      ? insertAnnotation(uri, version ?? null, position, type)
+      : false,
+  'noolang.organizeDefinitions': ([uri]) => {
+    const edit = typeof uri === 'string' ? organizeDefinitionsEdit(uri) : null;
+    return edit ? applyWorkspaceEdit('Organize definitions', edit) : false;
+  },
+  'noolang.dumpCacheStats': () => {
+    const stats = cacheStats();
+    log.info(`Cache stats: ${JSON.stringify(stats, null, 2)}`);
+    return stats;
+  },
+  // The NOO_LSP_PROFILE report so far, also written to the log
+  'noolang.dumpProfile': () => {
+    if (!profiler.enabled) return 'Profiling is off; start the server with NOO_LSP_PROFILE=1';
+    const report = profiler.report();
+    log.info(report);
+    return report;
+  },
+  'noolang.showAst': ([uri]) =>
+    typeof uri === 'string' ? underReadLock(uri, async () => (await getAstFile(filePathOf(uri))) ?? null) : null,
+};
+
+connection.onExecuteCommand((params: ExecuteCommandParams) => {
+  const handler = commands[params.command];
+  if (!handler) {
+    return new ResponseError(ErrorCodes.InvalidParams, `Unknown command: ${params.command}`);
+  }
+  return answer(params.command, () => handler(params.arguments ?? []));
+});
+
+// Custom request: server health for the extension's status bar item and
+// diagnostics panel.
+function cliVersion(): string | undefined {
+  try {
+    const pkg = path.join(path.dirname(CLI_PATH), '..', 'package.json');
+    return JSON.parse(fs.readFileSync(pkg, 'utf8')).version;
+  } catch {
+    return undefined;
+  }
+}
+
+connection.onRequest('noolang/status', () => ({
+  serverVersion: SERVER_VERSION,
+  // 'spawn' starts a CLI process per call; 'in-process' runs the compiler in
+  // the server (evaluation is still spawned)
+  bridgeMode: backend.kind,
+  nodeVersion: process.version,
+  cli: {
+    path: CLI_PATH,
+    runtime: CLI_RUNTIME,
+    runtimeKind: runtimeKind(CLI_RUNTIME),
+    exists: fs.existsSync(CLI_PATH),
+    version: cliVersion() ?? null,
+    // null until the startup probe answers
+    flags: cliCapabilities ? [...cliCapabilities.flags] : null,
+  },
+  caches: {
+    openDocuments: documents.size,
+    stdlibConstraints: stdlibIndex?.size ?? 0,
+    ast: astCache.stats(),
+    types: typesCache.stats(),
+  },
+  indexedFiles: workspace.stats().files,
+  // Request and bridge timings, when noolang.telemetry.enabled is on
+  telemetry: telemetry.enabled ? telemetry.snapshot() : null,
+  lastError: lastBridgeError ?? null,
+  supervisor: supervisor.state(),
+}));
+
+// --- Cache statistics ---
+// For reports of the server using too much memory: each cache's entries,
+// estimated size and hit rate, the documents whose CLI results take the most
+// room, and the process's memory. From noolang.dumpCacheStats, which also
+// writes them to the log.
+const LARGEST_CACHED_DOCUMENTS = 10;
+
+// The file a cached CLI result is for: the `.noo` path among its arguments
+const cachedFile = (key: string) => key.split('\0').find((arg) => arg.endsWith('.noo')) ?? '(no file)';
+
+function cacheStats() {
+  const byFile = new Map<string, { entries: number; bytes: number }>();
+  for (const cache of [astCache, typesCache]) {
+    for (const { key, bytes } of cache.sizes()) {
+      const file = byFile.get(cachedFile(key)) ?? { entries: 0, bytes: 0 };
+      byFile.set(cachedFile(key), { entries: file.entries + 1, bytes: file.bytes + bytes });
+    }
+  }
+  const largest = [...byFile]
+    .sort(([, a], [, b]) => b.bytes - a.bytes)
+    .slice(0, LARGEST_CACHED_DOCUMENTS)
+    .map(([filePath, sizes]) => ({ filePath, ...sizes }));
+  const open = documents.all();
+  const { rss, heapTotal, heapUsed, external } = process.memoryUsage();
+  return {
+    caches: {
+      ast: astCache.stats(),
+      types: typesCache.stats(),
+      workspaceIndex: workspace.stats(),
+      openDocuments: { entries: open.length, bytes: open.reduce((n, d) => n + Buffer.byteLength(d.text), 0) },
+      stdlibConstraints: stdlibIndex?.size ?? 0,
+    },
+    largestDocuments: largest,
+    memory: { rss, heapTotal, heapUsed, external },
+  };
+}
+
+// Record fields go by their `@name` across the workspace: in record
+// literals, accessors, patterns and type declarations alike. The field at a
+// position, if that's where it is.
+function fieldAt(uri: string, filePath: string, position: Position): string | undefined {
+  const text = documentText(uri, filePath);
+  const target = renameTargetAt(text, offsetAt(text, position));
+  return target?.kind === 'field' ? target.name : undefined;
+}
+
+const spanLocation = (file: IndexedFile, span: Span) =>
+  Location.create(
+    URI.file(file.filePath).toString(),
+    Range.create(positionAt(file.text, span.start), positionAt(file.text, span.end)),
+  );
+
+// The module imported by the path or module binding at the position
+function importedModuleAt(filePath: string, position: Position): IndexedFile | undefined {
+  const file = workspace.get(filePath);
+  const site = file && importAt(file, offsetAt(file.text, position));
+  return site?.target ? workspace.get(site.target) : undefined;
+}
+
+// A module's first declaration, or its start when it has none
+const moduleLocation = (module: IndexedFile) =>
+  spanLocation(module, module.declarations[0]?.span ?? { start: 0, end: 0 });
+
+// The record types declaring a field
+function fieldDeclarations(field: string): Location[] {
+  return workspace
+    .all([...openDocumentsByPath().keys()])
+    .flatMap((file) =>
+      file.declarations.filter((d) => d.kind === 'field' && d.name === field).map((d) => spanLocation(file, d.span)),
+    );
+}
+
+connection.onDefinition((params: DefinitionParams) =>
+  readDocument('textDocument/definition', params.textDocument.uri, async () => {
+    const uri = params.textDocument.uri;
+    const filePath = filePathOf(uri);
+    const pos = params.position;
+    const module = importedModuleAt(filePath, pos);
+    if (module) return moduleLocation(module);
+    // A field goes to the record types that declare it
+    const field = fieldAt(uri, filePath, pos);
+    if (field) return fieldDeclarations(field);
+    const saved = savedPosition(uri, filePath, pos);
+    const ast = saved && (await getAstFile(filePath));
+    if (!saved || !ast) return null;
+    const name = extractSymbolAtPosition(ast, saved.line + 1, saved.character + 1);
+    if (!name) return null;
+    const def = findDefinition(ast, name);
+    if (def) return bufferLocation(uri, filePath, def.range) ?? null;
+    if (!/^[A-Z]/.test(name)) return (await methodDefinition(uri, filePath, pos, saved)) ?? null;
+    const constructor = findConstructor(ast, name);
+    if (constructor) return bufferLocation(uri, filePath, constructor) ?? null;
+    return constructorElsewhere(filePath, name) ?? null;
+  }),
+);
+
+// --- Implementations ---
+// The `implement` blocks of a constraint, from the workspace index and the
+// standard library: listed from the constraint's name (goto implementation,
+// and a lens over its declaration) or, from one of its methods, that
+// method's definition in each block.
+function indexedModules(): IndexedFile[] {
+  const stdlib = stdlibPath();
+  return workspace.all([...openDocumentsByPath().keys(), ...(stdlib ? [stdlib] : [])]);
+}
+
+// The constraint (and method) named at a position
+function constraintAt(uri: string, filePath: string, position: Position) {
+  const text = documentText(uri, filePath);
+  const target = renameTargetAt(text, offsetAt(text, position));
+  if (target?.kind !== 'name') return undefined;
+  const declared = indexedModules().flatMap((file) => file.declarations);
+  if (declared.some((d) => d.kind === 'constraint' && d.name === target.name)) return { constraint: target.name };
+  const method = declared.find(
+    (d) =>
+      d.kind === 'method' &&
+      d.name === target.name &&
+      declared.some((c) => c.kind === 'constraint' && c.name === d.container),
+  );
+  return method?.container ? { constraint: method.container, method: method.name } : undefined;
+}
+
+connection.onImplementation((params: ImplementationParams) =>
+  readDocument('textDocument/implementation', params.textDocument.uri, () => {
+    const uri = params.textDocument.uri;
+    const found = constraintAt(uri, filePathOf(uri), params.position);
+    if (!found) return null;
+    const blocks = implementationsOf(indexedModules(), found.constraint, found.method);
+    return blocks.map(({ file, declaration }) => spanLocation(file, declaration.span));
+  }),
+);
+
+// Where goto definition takes a constraint method: to the method in the
+// block its call resolved to, when the checker resolved it to just one, and
+// otherwise to the constraint's declaration of it. `saved` is the position
+// in the saved file, which the checker reads.
+async function methodDefinition(
+  uri: string,
+  filePath: string,
+  position: Position,
+  saved: Position,
+): Promise<Location | undefined> {
+  const found = constraintAt(uri, filePath, position);
+  if (!found?.method) return undefined;
+  const { constraint, method } = found;
+  const here = await getTypeAt(filePath, saved.line + 1, saved.character + 1);
+  const chosen = (here?.resolutions ?? [])
+    .filter((r) => r.trait === constraint)
+    .map((r) => chosenImplementation(filePath, r.trait, r.type));
+  const [block] = chosen;
+  const same = (c: typeof block) =>
+    c?.file.filePath === block?.file.filePath && c?.declaration.name === block?.declaration.name;
+  if (block && chosen.every(same)) {
+    const defined = implementationsOf([block.file], constraint, method).find(
+      ({ declaration }) => declaration.container === block.declaration.name,
+    );
+    if (defined) return spanLocation(defined.file, defined.declaration.span);
+  }
+  for (const file of indexedModules()) {
+    const declared = file.declarations.find(
+      (d) => d.kind === 'method' && d.name === method && d.container === constraint,
+    );
+    if (declared) return spanLocation(file, declared.span);
+  }
+  return undefined;
+}
+
+function implementationLenses(uri: string, filePath: string): CodeLens[] {
+  const file = settings.codeLens.implementations ? workspace.get(filePath) : undefined;
+  if (!file) return [];
+  const modules = indexedModules();
+  return file.declarations.flatMap((d): CodeLens[] => {
+    if (d.kind !== 'constraint') return [];
+    const locations = implementationsOf(modules, d.name).map((i) => spanLocation(i.file, i.declaration.span));
+    const range = spanLocation(file, d.span).range;
+    const title = `${locations.length} implementation${locations.length === 1 ? '' : 's'}`;
+    const command = { title, command: 'noolang.showImplementations', arguments: [uri, range.start, locations] };
+    return [{ range, command }];
+  });
+}
+
+connection.onReferences((params: ReferenceParams, token, _workDone, progress?: ResultProgressReporter<Location[]>) =>
+  readDocument('textDocument/references', params.textDocument.uri, async () => {
+    const uri = params.textDocument.uri;
+    const filePath = filePathOf(uri);
+    const pos = params.position;
+    const includeDeclaration = params.context.includeDeclaration;
+    const field = fieldAt(uri, filePath, pos);
+    if (field) {
+      // A field's uses include the record types declaring it, left out unless asked for
+      const declares = (file: IndexedFile, span: Span) =>
+        file.declarations.some((d) => d.kind === 'field' && d.span.start === span.start && d.span.end === span.end);
+      const uses = (files: IndexedFile[]) =>
+        files.flatMap((file) =>
+          (file.fields.get(field) ?? [])
+            .filter((span) => includeDeclaration || !declares(file, span))
+            .map((span) => spanLocation(file, span)),
+        );
+      return searchWorkspace(uses, progress, token);
+    }
+    const saved = savedPosition(uri, filePath, pos);
+    const ast = saved && (await getAstFile(filePath));
+    if (!saved || !ast) return [];
+    const name = extractSymbolAtPosition(ast, saved.line + 1, saved.character + 1);
+    if (!name) return [];
+    // References in this file
+    return findReferences(ast, name, uri, includeDeclaration).flatMap(
+      (ref) => bufferLocation(uri, filePath, ref.range) ?? [],
+    );
+  }),
+);
+
+// --- Rename ---
+// Across the workspace, through the reference index (rename.ts)
+let documentChanges = false;
+// Whether the client can ask the user to confirm annotated edits
+let changeAnnotations = false;
+
+function renameTarget(uri: string, position: Position) {
+  const file = workspace.get(filePathOf(uri));
+  const target = file && renameTargetAt(file.text, offsetAt(file.text, position));
+  return file && target ? { file, target } : undefined;
+}
+
+connection.onPrepareRename((params: PrepareRenameParams) =>
+  readDocument('textDocument/prepareRename', params.textDocument.uri, () => {
+    const found = renameTarget(params.textDocument.uri, params.position);
+    if (!found) return null;
+    const { file, target } = found;
+    const range = Range.create(positionAt(file.text, target.span.start), positionAt(file.text, target.span.end));
+    return { range, placeholder: target.name };
+  }),
+);
+
+// The compiler's lexing of a new name as a field, taken before the rename
+// holds the document's lock
+async function newNameTokens(newName: string): Promise<CompilerToken[]> {
+  if (newName === '') return [];
+  const res = await runNodeCli(['--tokens', `@${newName}`]);
+  const lexed = !res.error && !res.signal && !res.timedOut && res.status === 0;
+  return lexed ? parseCliTokens(res.stdout) : syntaxTokens(`@${newName}`);
+}
+
+connection.onRenameRequest(async (params: RenameParams) => {
+  const tokens = await newNameTokens(params.newName);
+  return readDocument('textDocument/rename', params.textDocument.uri, () => {
+    const found = renameTarget(params.textDocument.uri, params.position);
+    if (!found) return null;
+    const invalid = invalidRename(found.target, params.newName, tokens);
+    if (invalid) return new ResponseError(ErrorCodes.InvalidParams, `Rename not applied: ${invalid}`);
+    const open = openDocumentsByPath();
+    const plan = planRename(workspace, found.file, found.target, [...open.keys()]);
+    const conflicts = conflictingFiles(workspace, plan);
+    if (conflicts.length > 0) {
+      const names = conflicts.map((f) => path.basename(f)).join(', ');
+      return new ResponseError(LSPErrorCodes.ContentModified, `Rename not applied: ${names} changed meanwhile`);
+    }
+    const clashes = found.target.kind === 'field' ? fieldClashes(workspace, plan, params.newName) : [];
+    if (clashes.length > 0) {
+      const names = clashes.map((f) => path.basename(f)).join(', ');
+      const message = `Rename not applied: a record in ${names} already has a field @${params.newName}`;
+      return new ResponseError(LSPErrorCodes.RequestFailed, message);
+    }
+    const taken = found.target.kind === 'name' ? nameClashes(workspace, plan, params.newName) : [];
+    const listed = (files: { filePath: string }[]) => files.map((f) => path.basename(f.filePath)).join(', ');
+    const defined = taken.filter((t) => t.declared);
+    if (defined.length > 0) {
+      const message = `Rename not applied: '${params.newName}' is already defined in ${listed(defined)}`;
+      return new ResponseError(LSPErrorCodes.RequestFailed, message);
+    }
+    // A clash with a local name is left to the user, edit by edit, where the
+    // client can ask
+    if (taken.length > 0 && !(documentChanges && changeAnnotations)) {
+      const used = `'${params.newName}' is already used in ${listed(taken)}`;
+      return new ResponseError(LSPErrorCodes.RequestFailed, `Rename not applied: ${used} and could be shadowed`);
+    }
+    return renameEdit(plan, params.newName, open, new Set(taken.map((t) => t.filePath)));
+  });
+});
+
+// Versioned edits when the client takes them, so an editor whose buffer has
+// moved on since refuses the edit instead of misapplying it. Edits in
+// `risky` files, where the new name is already in use, need confirming.
+const SHADOWING = 'shadowing';
+
+function renameEdit(
+  plan: RenamePlan,
+  newName: string,
+  open: Map<string, DocumentEntry>,
+  risky = new Set<string>(),
+): WorkspaceEdit {
+  const fileEdits = [...plan.edits].map(([filePath, { spans }]) => {
+    const entry = open.get(filePath);
+    const text = entry?.text ?? workspace.get(filePath)?.text ?? '';
+    const range = (s: Span) => Range.create(positionAt(text, s.start), positionAt(text, s.end));
+    const edit = (s: Span) =>
+      risky.has(filePath)
+        ? AnnotatedTextEdit.replace(range(s), newName, SHADOWING)
+        : TextEdit.replace(range(s), newName);
+    const edits = spans.map(edit);
+    return { uri: entry?.uri ?? URI.file(filePath).toString(), version: entry ? entry.version : null, edits };
+  });
+  if (documentChanges) {
+    const versioned = fileEdits.map((f) => TextDocumentEdit.create({ uri: f.uri, version: f.version }, f.edits));
+    if (risky.size === 0) return { documentChanges: versioned };
+    const annotation = ChangeAnnotation.create(
+      `'${newName}' is already used here`,
+      true,
+      `Renaming to '${newName}' may shadow a name already in scope, or be shadowed by one`,
+    );
+    return { documentChanges: versioned, changeAnnotations: { [SHADOWING]: annotation } };
+  }
+  return { changes: Object.fromEntries(fileEdits.map((f) => [f.uri, f.edits])) };
+}
+
+// --- Document symbols ---
+// The outline (symbols.ts) as a tree when the client takes one, otherwise
+// flattened with each symbol's container named
+let hierarchicalSymbols = false;
+
+const OUTLINE_KINDS: Record<OutlineKind, SymbolKind> = {
+  function: SymbolKind.Function,
+  value: SymbolKind.Variable,
+  type: SymbolKind.Enum,
+  constructor: SymbolKind.EnumMember,
+  alias: SymbolKind.Class,
+  constraint: SymbolKind.Interface,
+  implementation: SymbolKind.Object,
+  method: SymbolKind.Method,
+  field: SymbolKind.Field,
+};
+
+const symbolTags = (deprecated: boolean) => (deprecated ? { tags: [SymbolTag.Deprecated] } : {});
+
+function toDocumentSymbol(text: string, symbol: OutlineSymbol): DocumentSymbol {
+  const range = (s: Span) => Range.create(positionAt(text, s.start), positionAt(text, s.end));
+  const created = DocumentSymbol.create(
+    symbol.name,
+    symbol.detail,
+    OUTLINE_KINDS[symbol.kind],
+    range(symbol.span),
+    range(symbol.selection),
+    symbol.children.map((child) => toDocumentSymbol(text, child)),
+  );
+  return { ...created, ...symbolTags(symbol.deprecated) };
+}
+
+connection.onDocumentSymbol((params: DocumentSymbolParams) =>
+  readDocument('textDocument/documentSymbol', params.textDocument.uri, async () => {
+    const uri = params.textDocument.uri;
+    const filePath = filePathOf(uri);
+    const ast = await getAstFile(filePath);
+    if (!ast) return [];
+    const text = documentText(uri, filePath);
+    const outline = documentOutline(ast, text);
+    if (hierarchicalSymbols) return outline.map((symbol) => toDocumentSymbol(text, symbol));
+    return flattenOutline(outline).map(({ symbol, container }) => {
+      const range = Range.create(positionAt(text, symbol.span.start), positionAt(text, symbol.span.end));
+      const created = SymbolInformation.create(symbol.name, OUTLINE_KINDS[symbol.kind], range, uri, container);
+      return { ...created, ...symbolTags(symbol.deprecated) };
+    });
+  }),
+);
+
+// --- Signature help ---
+// The parameters still to be passed to the function at the cursor
+// (signatures.ts), the next one active. With arguments already applied,
+// their types come from typing that partial application in the file's
+// scope, so `compose double ` shows `(a -> Float) -> a -> Float`; failing
+// that, a builtin's signature less the applied parameters.
+connection.onSignatureHelp((params: SignatureHelpParams) =>
+  readDocument('textDocument/signatureHelp', params.textDocument.uri, () => signatureHelp(params)),
+);
+
+async function signatureHelp(params: SignatureHelpParams): Promise<SignatureHelp | null> {
+  const uri = params.textDocument.uri;
+  const filePath = filePathOf(uri);
+  const text = documentText(uri, filePath);
+  const call = callAt(text, offsetAt(text, params.position));
+  if (!call) return null;
+  const declared = workspace.get(filePath)?.declarations.some((d) => d.name === call.callee);
+  const builtin = declared ? undefined : builtinNamed(call.callee);
+  const applied = [call.callee, ...call.applied].join(' ');
+  // A builtin on its own keeps its documented signature, constraints and all
+  const needsType = call.applied.length > 0 || !builtin;
+  const typed = needsType ? await optional(getExpressionTypeInFile(filePath, applied), undefined) : undefined;
+  const signature = typed ?? builtin?.signature;
+  const shape = signature && builtinParameters(signature);
+  if (!shape) return null;
+  const remaining = typed ? shape.parameters : shape.parameters.slice(call.applied.length);
+  if (remaining.length === 0) return null;
+  let label = `${applied} : `;
+  const parameters = remaining.map((p) => {
+    const start = label.length;
+    label += `${p} -> `;
+    return ParameterInformation.create([start, start + p.length]);
+  });
+  label += shape.result + (shape.constraints ? ` ${shape.constraints}` : '');
+  const documentation = builtin && { kind: MarkupKind.Markdown, value: builtinDoc(builtin) };
+  return {
+    signatures: [{ ...SignatureInformation.create(label, undefined, ...parameters), documentation }],
+    activeSignature: 0,
+    activeParameter: 0,
+  };
+}
+
+// --- Workspace symbols ---
+// Declarations from the workspace index (workspace.ts), fuzzy-matched and
+// ranked (fuzzy.ts), up to the configured number of results. Clients that
+// can resolve a symbol's range later get only its file, and the range when
+// they go to it (workspaceSymbol/resolve), so a long result list costs no
+// offset-to-position conversions.
+let lazySymbolRanges = false;
+
+// What resolving a symbol needs: its file, and its declaration's kind and
+// name span as of the search
+type SymbolData = { filePath: string; kind: OutlineKind; start: number; end: number };
+
+type FoundSymbol = SymbolInformation | WorkspaceSymbol;
+
+// Streamed, the best matches of each chunk of files come as it's searched,
+// rather than the best of the whole workspace first
+connection.onWorkspaceSymbol(
+  (params: WorkspaceSymbolParams, token, _workDone, progress?: ResultProgressReporter<FoundSymbol[]>) =>
+    answer('workspace/symbol', () => {
+      const open = openDocumentsByPath();
+      const found = (files: IndexedFile[], wanted: number) => {
+        const declared = files.flatMap((file) =>
+          file.declarations.map((d) => ({ name: d.name, kind: d.kind, declaration: d, file })),
+        );
+        return rankSymbols(params.query, declared, wanted).map(({ declaration, file }) =>
+          workspaceSymbol(declaration, file, open),
+        );
+      };
+      const max = settings.workspaceSymbols.maxResults;
+      if (progress) return searchWorkspace(found, progress, token, max);
+      return found(workspace.all([...open.keys()]), max);
+    }),
+);
+
+function workspaceSymbol(d: Declaration, file: IndexedFile, open: Map<string, DocumentEntry>): FoundSymbol {
+  const uri = open.get(file.filePath)?.uri ?? URI.file(file.filePath).toString();
+  const tags = symbolTags(d.deprecated !== undefined);
+  if (lazySymbolRanges) {
+    const data: SymbolData = { filePath: file.filePath, kind: d.kind, ...d.span };
+    const kind = OUTLINE_KINDS[d.kind];
+    return { name: d.name, kind, containerName: d.container, location: { uri }, data, ...tags };
+  }
+  const range = Range.create(positionAt(file.text, d.span.start), positionAt(file.text, d.span.end));
+  return { ...SymbolInformation.create(d.name, OUTLINE_KINDS[d.kind], range, uri, d.container), ...tags };
+}
+
+// The declaration found by the search, or the nearest one of the same name
+// and kind if its file changed since
+connection.onWorkspaceSymbolResolve((symbol: WorkspaceSymbol) =>
+  answer('workspaceSymbol/resolve', () => {
+    const data = symbol.data as SymbolData | undefined;
+    const file = data && workspace.get(data.filePath);
+    if (!data || !file || 'range' in symbol.location) return symbol;
+    const candidates = file.declarations.filter((d) => d.name === symbol.name && d.kind === data.kind);
+    const distance = (span: Span) => Math.abs(span.start - data.start);
+    const nearest = candidates.sort((a, b) => distance(a.span) - distance(b.span))[0];
+    if (!nearest) return symbol;
+    const range = Range.create(positionAt(file.text, nearest.span.start), positionAt(file.text, nearest.span.end));
+    return { ...symbol, location: { uri: symbol.location.uri, range } };
+  }),
+);
+
 connection.listen();
\ No newline at end of file
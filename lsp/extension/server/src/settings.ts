@@ -0,0 +1,146 @@
+// User-facing server settings. The client sends them as initializationOptions
+// and again under `noolang` in workspace/didChangeConfiguration; anything
+// missing or malformed keeps its current value.
+
+export type Timeouts = {
+  diagnosticsMs: number;
+  hoverMs: number;
+  completionMs: number;
+  // Definition, references, symbols and other AST-driven requests
+  navigationMs: number;
+  // Evaluating an expression against a file, after which it is stopped
+  evaluateMs: number;
+};
+
+// Limits for each analysis cache (ASTs, type checks), see cache.ts
+export type CacheSettings = { maxEntries: number; maxBytes: number };
+
+// 'in-process' loads the built compiler into the server (see backend.ts)
+export type BackendSetting = 'spawn' | 'in-process';
+
+// How types are shown in hovers and completion details (see types.ts)
+export type DisplaySettings = {
+  // Wider types have record fields elided
+  maxWidth: number;
+  // Records and tuples nested deeper than this are elided
+  maxDepth: number;
+  arrows: 'unicode' | 'ascii';
+  // false shows `type` aliases by name instead of their structure
+  expandAliases: boolean;
+};
+
+// Request and bridge timings (see telemetry.ts); off unless asked for
+export type TelemetrySettings = {
+  enabled: boolean;
+  // Also write the histograms to the log now and then
+  logHistograms: boolean;
+};
+
+// Which inlay hints to show: types after top-level bindings, after the
+// parameters of top-level functions, and across pipeline operators
+export type InlayHintSettings = { bindings: boolean; parameters: boolean; pipelines: boolean };
+
+// Code lenses other than the test runner's: the inferred type above each
+// unannotated top-level definition, which inserts it when clicked, and the
+// number of implementations above each constraint
+export type CodeLensSettings = { annotations: boolean; implementations: boolean };
+
+// When files are type checked: as they change (debounced), when they're
+// saved, or only through the noolang.checkFile command. Syntax errors are
+// reported as you type regardless.
+export type DiagnosticsTrigger = 'onChange' | 'onSave' | 'manual';
+
+export type DiagnosticsSettings = { trigger: DiagnosticsTrigger };
+
+// Documents this big or bigger lose the features that cost the most on
+// every change (see server.ts)
+export type LargeFileSettings = { thresholdBytes: number };
+
+// Workspace symbol search stops at the best `maxResults` matches
+export type WorkspaceSymbolSettings = { maxResults: number };
+
+export type Settings = {
+  timeouts: Timeouts;
+  cache: CacheSettings;
+  backend: BackendSetting;
+  display: DisplaySettings;
+  telemetry: TelemetrySettings;
+  inlayHints: InlayHintSettings;
+  workspaceSymbols: WorkspaceSymbolSettings;
+  codeLens: CodeLensSettings;
+  diagnostics: DiagnosticsSettings;
+  largeFiles: LargeFileSettings;
+};
+
+export const DEFAULT_SETTINGS: Settings = {
+  timeouts: { diagnosticsMs: 10_000, hoverMs: 3_000, completionMs: 2_000, navigationMs: 5_000, evaluateMs: 5_000 },
+  cache: { maxEntries: 200, maxBytes: 64 * 1024 * 1024 },
+  backend: 'spawn',
+  display: { maxWidth: 60, maxDepth: 3, arrows: 'unicode', expandAliases: true },
+  telemetry: { enabled: false, logHistograms: false },
+  inlayHints: { bindings: true, parameters: true, pipelines: true },
+  workspaceSymbols: { maxResults: 200 },
+  codeLens: { annotations: true, implementations: true },
+  diagnostics: { trigger: 'onChange' },
+  largeFiles: { thresholdBytes: 1024 * 1024 },
+};
+
+const isObject = (v: unknown): v is Record<string, unknown> => typeof v === 'object' && v !== null;
+
+const positive = (v: unknown, fallback: number) => (typeof v === 'number' && v > 0 ? v : fallback);
+
+const flag = (v: unknown, fallback: boolean) => (typeof v === 'boolean' ? v : fallback);
+
+const TRIGGERS: DiagnosticsTrigger[] = ['onChange', 'onSave', 'manual'];
+
+export function mergeSettings(base: Settings, raw: unknown): Settings {
+  if (!isObject(raw)) return base;
+  const t = isObject(raw.timeouts) ? raw.timeouts : {};
+  const c = isObject(raw.cache) ? raw.cache : {};
+  const d = isObject(raw.display) ? raw.display : {};
+  const m = isObject(raw.telemetry) ? raw.telemetry : {};
+  const h = isObject(raw.inlayHints) ? raw.inlayHints : {};
+  const w = isObject(raw.workspaceSymbols) ? raw.workspaceSymbols : {};
+  const l = isObject(raw.codeLens) ? raw.codeLens : {};
+  const g = isObject(raw.diagnostics) ? raw.diagnostics : {};
+  const f = isObject(raw.largeFiles) ? raw.largeFiles : {};
+  return {
+    ...base,
+    timeouts: {
+      diagnosticsMs: positive(t.diagnosticsMs, base.timeouts.diagnosticsMs),
+      hoverMs: positive(t.hoverMs, base.timeouts.hoverMs),
+      completionMs: positive(t.completionMs, base.timeouts.completionMs),
+      navigationMs: positive(t.navigationMs, base.timeouts.navigationMs),
+      evaluateMs: positive(t.evaluateMs, base.timeouts.evaluateMs),
+    },
+    cache: {
+      maxEntries: positive(c.maxEntries, base.cache.maxEntries),
+      maxBytes: positive(c.maxBytes, base.cache.maxBytes),
+    },
+    backend: raw.backend === 'spawn' || raw.backend === 'in-process' ? raw.backend : base.backend,
+    display: {
+      maxWidth: positive(d.maxWidth, base.display.maxWidth),
+      maxDepth: positive(d.maxDepth, base.display.maxDepth),
+      arrows: d.arrows === 'unicode' || d.arrows === 'ascii' ? d.arrows : base.display.arrows,
+      expandAliases: flag(d.expandAliases, base.display.expandAliases),
+    },
+    telemetry: {
+      enabled: flag(m.enabled, base.telemetry.enabled),
+      logHistograms: flag(m.logHistograms, base.telemetry.logHistograms),
+    },
+    inlayHints: {
+      bindings: flag(h.bindings, base.inlayHints.bindings),
+      parameters: flag(h.parameters, base.inlayHints.parameters),
+      pipelines: flag(h.pipelines, base.inlayHints.pipelines),
+    },
+    workspaceSymbols: { maxResults: positive(w.maxResults, base.workspaceSymbols.maxResults) },
+    codeLens: {
+      annotations: flag(l.annotations, base.codeLens.annotations),
+      implementations: flag(l.implementations, base.codeLens.implementations),
+    },
+    diagnostics: {
+      trigger: TRIGGERS.find((t) => t === g.trigger) ?? base.diagnostics.trigger,
+    },
+    largeFiles: { thresholdBytes: positive(f.thresholdBytes, base.largeFiles.thresholdBytes) },
+  };
+}
@@ -0,0 +1,55 @@
+// Signature help: which function the cursor is passing an argument to, and
+// the arguments it already has. Found with the lossless lexer, so it works
+// on a buffer that doesn't parse yet: from the cursor back to the start of
+// the application (an opening bracket, `;`, `,`, an operator or a keyword),
+// the operands are its spine, the function and then its arguments. Functions
+// are curried, so a parenthesized application at the head is more of the
+// same spine: `(compose double) inc` applies `compose` to both.
+import { applicationOperands } from './precedence';
+import { CLOSERS, isPunctuation, isTrivia, lexSyntax, OPENERS } from './syntax';
+import { Span } from './workspace';
+
+// `applied` holds the text of each argument before the one at the cursor
+export type CallSite = { callee: string; applied: string[] };
+
+// A name applied to the rest of the operands, or a parenthesized
+// application applied to them
+function spineOf(source: string, operands: Span[]): CallSite | undefined {
+  const [head, ...args] = operands;
+  if (!head) return undefined;
+  const text = (s: Span) => source.slice(s.start, s.end);
+  const applied = args.map(text);
+  if (/^[A-Za-z_]\w*$/.test(text(head))) return { callee: text(head), applied };
+  if (source[head.start] !== '(') return undefined;
+  const inner = applicationOperands(source, head.start + 1);
+  const last = inner[inner.length - 1];
+  // Only when the parentheses hold nothing but the application
+  if (!last || source.slice(last.end, head.end - 1).trim() !== '') return undefined;
+  const spine = spineOf(source, inner);
+  return spine && { callee: spine.callee, applied: [...spine.applied, ...applied] };
+}
+
+export function callAt(source: string, offset: number): CallSite | undefined {
+  const tokens = lexSyntax(source).filter((t) => t.start < offset);
+  // Still typing an operand: that operand is the argument the cursor is on
+  const last = tokens[tokens.length - 1];
+  const typing = !!last && !isTrivia(last) && last.end >= offset && !isPunctuation(last, OPENERS);
+  const significant = tokens.filter((t) => !isTrivia(t));
+  const operands: Span[] = [];
+  let depth = 0;
+  let end = 0;
+  for (let i = significant.length - 1; i >= 0; i--) {
+    const t = significant[i];
+    if (isPunctuation(t, CLOSERS)) {
+      if (depth++ === 0) end = t.end;
+    } else if (isPunctuation(t, OPENERS)) {
+      if (depth === 0) break;
+      if (--depth === 0) operands.unshift({ start: t.start, end });
+    } else if (depth === 0) {
+      if (t.kind === 'operator' || t.kind === 'keyword' || t.kind === 'punctuation') break;
+      operands.unshift({ start: t.start, end: t.end });
+    }
+  }
+  if (typing) operands.pop();
+  return spineOf(source, operands);
+}
@@ -0,0 +1,50 @@
+// Bridge supervision. Each bridge call is a fresh CLI process, so "restarting"
+// means deciding when to try again: after a crash (spawn error, signal)
+// further calls are held off with exponential backoff instead of every
+// request re-failing, and the client is told once failures repeat. A timeout
+// isn't a crash: it only fails its own request. Time is passed in so the
+// policy stays testable.
+
+export const INITIAL_BACKOFF_MS = 500;
+export const MAX_BACKOFF_MS = 30_000;
+export const NOTIFY_AFTER_FAILURES = 3;
+
+export type SupervisorState = {
+  consecutiveFailures: number;
+  retryAt: number;
+  notified: boolean;
+};
+
+export type Supervisor = {
+  canCall(now: number): boolean;
+  recordSuccess(): void;
+  // Returns true exactly once per failure streak, when the client should be told
+  recordFailure(now: number): boolean;
+  reset(): void;
+  state(): Readonly<SupervisorState>;
+};
+
+export function backoffMs(consecutiveFailures: number): number {
+  if (consecutiveFailures <= 0) return 0;
+  return Math.min(INITIAL_BACKOFF_MS * 2 ** (consecutiveFailures - 1), MAX_BACKOFF_MS);
+}
+
+export function createSupervisor(): Supervisor {
+  let s: SupervisorState = { consecutiveFailures: 0, retryAt: 0, notified: false };
+  return {
+    canCall: (now) => now >= s.retryAt,
+    recordSuccess() {
+      s = { consecutiveFailures: 0, retryAt: 0, notified: false };
+    },
+    recordFailure(now) {
+      const consecutiveFailures = s.consecutiveFailures + 1;
+      const notify = consecutiveFailures >= NOTIFY_AFTER_FAILURES && !s.notified;
+      s = { consecutiveFailures, retryAt: now + backoffMs(consecutiveFailures), notified: s.notified || notify };
+      return notify;
+    },
+    reset() {
+      s = { consecutiveFailures: 0, retryAt: 0, notified: false };
+    },
+    state: () => s,
+  };
+}
@@ -0,0 +1,156 @@
+// The document outline: a file's definitions as a tree, each with the
+// definitions local to it (a function's own names, a `where`'s bindings)
+// as children; variant types with their constructors, type aliases, and
+// constraints and implement blocks with their functions. The AST locates
+// only a definition's start, so the full extent comes from the lexer: up to
+// the `;` or closing bracket that ends the statement.
+import { AstNode, AstProgram, outermost, SourceLocation } from './ast';
+import { docCommentAbove, isTrivia, lexSyntax, offsetAt, SyntaxToken } from './syntax';
+import { DEFAULT_RENDER, fromCompilerType, renderType } from './types';
+
+// `type` is a variant type, `alias` a `type` declaration; constraints and
+// implement blocks have `method` members
+export type OutlineKind =
+  | 'function'
+  | 'value'
+  | 'type'
+  | 'constructor'
+  | 'alias'
+  | 'constraint'
+  | 'implementation'
+  | 'method'
+  // Only in workspace declarations: the AST has no locations for them
+  | 'field';
+
+// Offsets into the source; `selection` is the name, `span` all of it
+export type OutlineSymbol = {
+  name: string;
+  kind: OutlineKind;
+  // A type, for aliases and constraint functions
+  detail?: string;
+  // Its doc comment says `@deprecated`
+  deprecated: boolean;
+  span: { start: number; end: number };
+  selection: { start: number; end: number };
+  children: OutlineSymbol[];
+};
+
+const OUTLINED = new Set([
+  'definition',
+  'mutable-definition',
+  'type-definition',
+  'user-defined-type',
+  'constraint-definition',
+  'implement-definition',
+]);
+
+const isOutlined = (node: AstNode) => OUTLINED.has(node.kind);
+
+// A type from the AST as written in source, when it can be rendered
+function typeText(raw: unknown): string | undefined {
+  const type = fromCompilerType(raw);
+  return type && renderType(type, { ...DEFAULT_RENDER, arrow: '->' });
+}
+
+export function documentOutline(ast: AstProgram, source: string): OutlineSymbol[] {
+  const tokens = lexSyntax(source).filter((t) => !isTrivia(t));
+  const offset = (loc: SourceLocation['start']) => offsetAt(source, { line: loc.line - 1, character: loc.column - 1 });
+
+  // The first token at or after `from` that's `name`: definitions' locations
+  // may start at `mut` or `variant` rather than the name
+  function nameToken(from: number, name: string): SyntaxToken | undefined {
+    return tokens.find((t) => t.start >= from && t.kind === 'identifier' && t.text === name);
+  }
+
+  // The end of the last token before the `;` or unmatched closer that ends
+  // the statement starting at `from`, or before a `|` for a constructor
+  function statementEnd(from: number, alternatives = false): number {
+    let depth = 0;
+    let end = from;
+    for (let i = tokens.findIndex((t) => t.end > from); i >= 0 && i < tokens.length; i++) {
+      const t = tokens[i];
+      if (t.kind === 'punctuation' && '([{'.includes(t.text)) depth++;
+      if (t.kind === 'punctuation' && ')]}'.includes(t.text) && --depth < 0) break;
+      if (depth === 0 && t.kind === 'punctuation' && t.text === ';') break;
+      if (depth === 0 && alternatives && t.kind === 'operator' && t.text === '|') break;
+      end = t.end;
+    }
+    return end;
+  }
+
+  // A symbol whose location starts at `at` and whose name is the first
+  // `name` token from there, shown as `label` when that's different
+  function declared(
+    at: SourceLocation['start'],
+    name: string,
+    kind: OutlineKind,
+    more: { label?: string; detail?: string; children?: OutlineSymbol[]; alternatives?: boolean } = {},
+  ): OutlineSymbol {
+    const start = offset(at);
+    const token = nameToken(start, name);
+    const selection = token ? { start: token.start, end: token.end } : { start, end: start + name.length };
+    const span = { start, end: Math.max(statementEnd(start, more.alternatives), selection.end) };
+    const deprecated = deprecationNote(docCommentAbove(source, start)) !== undefined;
+    const children = more.children ?? [];
+    return { name: more.label ?? name, kind, detail: more.detail, deprecated, span, selection, children };
+  }
+
+  const locals = (value: AstNode) => outermost([value], isOutlined).flatMap(symbol);
+
+  function symbol(node: AstNode): OutlineSymbol[] {
+    if (!node.location) return [];
+    const at = node.location.start;
+    switch (node.kind) {
+      case 'definition':
+      case 'mutable-definition':
+        return [
+          declared(at, node.name, node.value.kind === 'function' ? 'function' : 'value', {
+            children: locals(node.value),
+          }),
+        ];
+      case 'type-definition': {
+        const constructors = node.constructors.flatMap((c) =>
+          c.location ? [declared(c.location.start, c.name, 'constructor', { alternatives: true })] : [],
+        );
+        return [declared(at, node.name, 'type', { children: constructors })];
+      }
+      case 'user-defined-type':
+        return [declared(at, node.name, 'alias', { detail: typeText(node.definition) })];
+      case 'constraint-definition': {
+        const functions = node.functions.flatMap((f) =>
+          f.location ? [declared(f.location.start, f.name, 'method', { detail: typeText(f.type) })] : [],
+        );
+        const detail = node.typeParams.join(' ') || undefined;
+        return [declared(at, node.name, 'constraint', { detail, children: functions })];
+      }
+      case 'implement-definition': {
+        const methods = node.implementations.flatMap((i) =>
+          i.location ? [declared(i.location.start, i.name, 'method', { children: locals(i.value) })] : [],
+        );
+        const type = typeText(node.typeExpr);
+        const label = type && `${node.constraintName} ${/\s/.test(type) ? `(${type})` : type}`;
+        return [declared(at, node.constraintName, 'implementation', { label, children: methods })];
+      }
+      default:
+        return [];
+    }
+  }
+
+  return outermost(ast.statements, isOutlined).flatMap(symbol);
+}
+
+// `@deprecated` in a doc comment, with the rest of its line: what to use
+// instead, say. Undefined when the doc comment doesn't have it.
+export function deprecationNote(doc: string | undefined): string | undefined {
+  const match = doc?.match(/(?:^|\s)@deprecated\b(.*)/);
+  return match ? match[1].trim() : undefined;
+}
+
+// Depth-first, each symbol with the name of the one it's nested in, for
+// clients that only take a flat list
+export function flattenOutline(
+  symbols: OutlineSymbol[],
+  container?: string,
+): { symbol: OutlineSymbol; container?: string }[] {
+  return symbols.flatMap((symbol) => [{ symbol, container }, ...flattenOutline(symbol.children, symbol.name)]);
+}
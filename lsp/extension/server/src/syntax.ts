@@ -0,0 +1,298 @@
+// Lossless syntax tree for Noolang source, built in-process. Every character
+// of the input lands in exactly one token — whitespace and comments included
+// — so printing the tokens back in order reproduces the file. The tree is
+// shallow on purpose: statements, bracketed groups and definitions, which is
+// what formatting, refactorings and offline navigation need without the CLI.
+// Token rules follow src/lexer/lexer.ts, and its keywords and operators
+// are the compiler's own (see Lexicon).
+
+export type TokenKind =
+  | 'whitespace'
+  | 'comment'
+  | 'identifier'
+  | 'keyword'
+  | 'number'
+  | 'string'
+  | 'template'
+  | 'accessor'
+  | 'operator'
+  | 'punctuation'
+  | 'unknown';
+
+// Offsets are UTF-16 indices into the source, `end` exclusive
+export type SyntaxToken = { kind: TokenKind; text: string; start: number; end: number };
+
+export type NodeKind = 'file' | 'statement' | 'definition' | 'group';
+
+export type SyntaxNode = { kind: NodeKind; start: number; end: number; children: SyntaxElement[] };
+
+export type SyntaxElement = SyntaxNode | SyntaxToken;
+
+export type SyntaxError = { message: string; start: number; end: number };
+
+// The compiler's keyword and operator tables (KEYWORDS and OPERATORS in
+// src/lexer/lexer.ts), which the CLI reports with --capabilities. Until the
+// startup probe answers, or for a CLI too old to report them, the tables of
+// the compiler this server was released with are used;
+// __tests__/syntax.test.ts keeps them the same.
+export type Lexicon = { keywords: readonly string[]; operators: readonly string[] };
+
+export const DEFAULT_LEXICON: Lexicon = {
+  keywords: [
+    'if', 'then', 'else', 'let', 'in', 'fn', 'import', 'mut', 'mut!', 'where', 'variant', 'type', 'match',
+    'with', 'given', 'is', 'has', 'implements', 'constraint', 'implement',
+    'Float', 'Number', 'String', 'Unit', 'List', 'Unknown',
+  ],
+  operators: [
+    '|?', '|>', '<|', '&&', '||', '==', '!=', '<=', '>=', '=>', '->',
+    '+', '-', '*', '%', '/', '<', '>', '=', '|', '$',
+  ],
+};
+
+let KEYWORDS = new Set(DEFAULT_LEXICON.keywords);
+let OPERATORS = DEFAULT_LEXICON.operators;
+
+export function useLexicon(lexicon: Lexicon) {
+  KEYWORDS = new Set(lexicon.keywords);
+  OPERATORS = lexicon.operators;
+}
+
+export const isKeyword = (word: string) => KEYWORDS.has(word);
+
+const OPERATOR_CHAR = /[+\-*/%<>=!|$&]/;
+const PUNCTUATION_CHAR = /[(),;:[\]{}_]/;
+
+export const isTrivia = (t: SyntaxToken) => t.kind === 'whitespace' || t.kind === 'comment';
+
+export const isToken = (e: SyntaxElement): e is SyntaxToken => !('children' in e);
+
+export const OPENERS = '([{';
+export const CLOSERS = ')]}';
+
+// Punctuation that's one of `chars`, e.g. isPunctuation(t, OPENERS)
+export const isPunctuation = (t: SyntaxToken | undefined, chars: string) =>
+  t?.kind === 'punctuation' && chars.includes(t.text);
+
+export function lexSyntax(source: string, errors: SyntaxError[] = []): SyntaxToken[] {
+  const tokens: SyntaxToken[] = [];
+  let i = 0;
+  const push = (kind: TokenKind, end: number) => {
+    tokens.push({ kind, text: source.slice(i, end), start: i, end });
+    i = end;
+  };
+  const scan = (from: number, re: RegExp) => {
+    let j = from;
+    while (j < source.length && re.test(source[j])) j++;
+    return j;
+  };
+
+  while (i < source.length) {
+    const ch = source[i];
+    if (/\s/.test(ch)) push('whitespace', scan(i, /\s/));
+    else if (ch === '#') {
+      const nl = source.indexOf('\n', i);
+      push('comment', nl < 0 ? source.length : nl);
+    } else if (ch === '"' || ch === "'") push('string', scanString(source, i, errors));
+    else if (ch === '`') push('template', scanTemplate(source, i, errors));
+    else if (/[a-zA-Z]/.test(ch) || (ch === '_' && /[a-zA-Z0-9]/.test(source[i + 1] ?? ''))) {
+      let end = scan(i + 1, /[a-zA-Z0-9_]/);
+      if (source.slice(i, end) === 'mut' && source[end] === '!') end++;
+      push(KEYWORDS.has(source.slice(i, end)) ? 'keyword' : 'identifier', end);
+    } else if (/\d/.test(ch)) {
+      let end = scan(i, /\d/);
+      if (source[end] === '.' && /\d/.test(source[end + 1] ?? '')) end = scan(end + 1, /\d/);
+      push('number', end);
+    } else if (ch === '@') {
+      let end = scan(i + 1, /[a-zA-Z0-9_]/);
+      if (source[end] === '?') end++;
+      push('accessor', end);
+    } else if (OPERATOR_CHAR.test(ch)) {
+      const op = OPERATORS.find((o) => source.startsWith(o, i));
+      push('operator', i + (op ? op.length : 1));
+    } else if (PUNCTUATION_CHAR.test(ch)) push('punctuation', i + 1);
+    else if (ch === '?') push('punctuation', scan(i, /\?/));
+    else {
+      errors.push({ message: `Unexpected character '${ch}'`, start: i, end: i + 1 });
+      push('unknown', i + 1);
+    }
+  }
+  return tokens;
+}
+
+function scanString(source: string, start: number, errors: SyntaxError[]): number {
+  const quote = source[start];
+  let j = start + 1;
+  while (j < source.length && source[j] !== quote) j += source[j] === '\\' ? 2 : 1;
+  if (j >= source.length) {
+    errors.push({ message: 'Unterminated string', start, end: source.length });
+    return source.length;
+  }
+  return j + 1;
+}
+
+// A template is one token, holes included; ${...} braces are tracked so a
+// record inside a hole doesn't end it, and nested templates recurse.
+function scanTemplate(source: string, start: number, errors: SyntaxError[]): number {
+  let j = start + 1;
+  while (j < source.length && source[j] !== '`') {
+    if (source[j] === '\\') j += 2;
+    else if (source[j] === '$' && source[j + 1] === '{') {
+      let depth = 0;
+      j += 2;
+      while (j < source.length && !(source[j] === '}' && depth === 0)) {
+        if (source[j] === '{') depth++;
+        else if (source[j] === '}') depth--;
+        if (source[j] === '`') j = scanTemplate(source, j, errors);
+        else if (source[j] === '"' || source[j] === "'") j = scanString(source, j, errors);
+        else j++;
+      }
+      j++;
+    } else j++;
+  }
+  if (j >= source.length) {
+    errors.push({ message: 'Unterminated template string', start, end: source.length });
+    return source.length;
+  }
+  return j + 1;
+}
+
+const CLOSER_OF: Record<string, string> = { '(': ')', '[': ']', '{': '}' };
+
+const isCloser = (t: SyntaxToken) => isPunctuation(t, CLOSERS);
+const isSemicolon = (t: SyntaxToken) => t.kind === 'punctuation' && t.text === ';';
+
+const node = (kind: NodeKind, children: SyntaxElement[]): SyntaxNode => ({
+  kind,
+  start: children[0]?.start ?? 0,
+  end: children[children.length - 1]?.end ?? 0,
+  children,
+});
+
+export function parseSyntax(source: string): { root: SyntaxNode; errors: SyntaxError[] } {
+  const errors: SyntaxError[] = [];
+  const tokens = lexSyntax(source, errors);
+  let i = 0;
+
+  // Reads elements until `stop` matches at this nesting level (not consumed)
+  function elements(stop: (t: SyntaxToken) => boolean): SyntaxElement[] {
+    const out: SyntaxElement[] = [];
+    while (i < tokens.length && !stop(tokens[i])) {
+      const t = tokens[i];
+      if (t.kind === 'punctuation' && CLOSER_OF[t.text]) out.push(group());
+      else {
+        out.push(t);
+        i++;
+      }
+    }
+    return out;
+  }
+
+  function group(): SyntaxNode {
+    const open = tokens[i++];
+    const close = CLOSER_OF[open.text];
+    const children: SyntaxElement[] = [open, ...elements(isCloser)];
+    const end = tokens[i];
+    if (end?.text === close) {
+      children.push(end);
+      i++;
+    } else {
+      // A mismatched closer is left for an enclosing group that wants it
+      errors.push({ message: `Missing '${close}'`, start: open.start, end: open.end });
+    }
+    return node('group', children);
+  }
+
+  const fileChildren: SyntaxElement[] = [];
+  while (i < tokens.length) {
+    const t = tokens[i];
+    if (isTrivia(t) || isSemicolon(t)) {
+      fileChildren.push(t);
+      i++;
+      continue;
+    }
+    if (isCloser(t)) {
+      errors.push({ message: `Unmatched '${t.text}'`, start: t.start, end: t.end });
+      fileChildren.push(t);
+      i++;
+      continue;
+    }
+    const children = elements((t) => isSemicolon(t) || isCloser(t));
+    // Trailing trivia belongs between statements, not inside one
+    let keep = children.length;
+    while (keep > 0 && isToken(children[keep - 1]) && isTrivia(children[keep - 1] as SyntaxToken)) keep--;
+    fileChildren.push(node(isDefinition(children) ? 'definition' : 'statement', children.slice(0, keep)));
+    fileChildren.push(...children.slice(keep));
+  }
+  return { root: { kind: 'file', start: 0, end: source.length, children: fileChildren }, errors };
+}
+
+function significant(children: SyntaxElement[]): SyntaxElement[] {
+  return children.filter((c) => !isToken(c) || !isTrivia(c));
+}
+
+// `name = …` or `mut name = …`
+function isDefinition(children: SyntaxElement[]): boolean {
+  const sig = significant(children);
+  const at = sig[0] && isToken(sig[0]) && sig[0].text === 'mut' ? 1 : 0;
+  const name = sig[at];
+  const eq = sig[at + 1];
+  return !!name && isToken(name) && name.kind === 'identifier' && !!eq && isToken(eq) && eq.text === '=';
+}
+
+export type SyntaxDefinition = { name: string; nameToken: SyntaxToken; node: SyntaxNode };
+
+// Top-level definitions, in source order
+export function syntaxDefinitions(root: SyntaxNode): SyntaxDefinition[] {
+  return root.children.flatMap((c) => {
+    if (isToken(c) || c.kind !== 'definition') return [];
+    const sig = significant(c.children) as SyntaxToken[];
+    const nameToken = sig[0].text === 'mut' ? sig[1] : sig[0];
+    return [{ name: nameToken.text, nameToken, node: c }];
+  });
+}
+
+// Zero-based line and UTF-16 character of an offset, as LSP positions count
+export function positionAt(source: string, offset: number): { line: number; character: number } {
+  let line = 0;
+  let lineStart = 0;
+  for (let i = source.indexOf('\n'); i >= 0 && i < offset; i = source.indexOf('\n', i + 1)) {
+    line++;
+    lineStart = i + 1;
+  }
+  return { line, character: offset - lineStart };
+}
+
+// The inverse of positionAt; characters past the end of a line clamp to it
+export function offsetAt(source: string, position: { line: number; character: number }): number {
+  let lineStart = 0;
+  for (let line = 0; line < position.line; line++) {
+    const next = source.indexOf('\n', lineStart);
+    if (next < 0) return source.length;
+    lineStart = next + 1;
+  }
+  const lineEnd = source.indexOf('\n', lineStart);
+  return Math.min(lineStart + position.character, lineEnd < 0 ? source.length : lineEnd);
+}
+
+// The `#` comment lines directly above the line `offset` is on, each without
+// its `#`: a declaration's doc comment. Undefined when there are none, or
+// when anything but indentation (or a constructor's `|`) comes before
+// `offset` on its line.
+export function docCommentAbove(source: string, offset: number): string | undefined {
+  let lineStart = source.lastIndexOf('\n', offset - 1) + 1;
+  if (!/^\s*\|?\s*$/.test(source.slice(lineStart, offset))) return undefined;
+  const lines: string[] = [];
+  while (lineStart > 0) {
+    const above = source.lastIndexOf('\n', lineStart - 2) + 1;
+    const line = source.slice(above, lineStart - 1).trim();
+    if (!line.startsWith('#')) break;
+    lines.unshift(line.slice(1).trim());
+    lineStart = above;
+  }
+  return lines.length > 0 ? lines.join('\n') : undefined;
+}
+
+// All tokens under an element, in order; joined, their text is the element's
+export function tokensOf(element: SyntaxElement): SyntaxToken[] {
+  return isToken(element) ? [element] : element.children.flatMap(tokensOf);
+}
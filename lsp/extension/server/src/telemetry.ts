@@ -0,0 +1,105 @@
+// Opt-in performance telemetry (noolang.telemetry.enabled): how long each
+// LSP request and each bridge call takes, kept as a fixed-bucket histogram
+// per request method or CLI flag. Nothing leaves the machine; noolang/status
+// reports it and, with logHistograms, it's written to the log periodically.
+
+// Upper bounds of the histogram buckets, in ms; the last bucket is open
+export const BUCKETS_MS = [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10_000];
+
+export type TimingKind = 'request' | 'bridge';
+
+export type Timing = {
+  count: number;
+  totalMs: number;
+  maxMs: number;
+  // One count per bucket in BUCKETS_MS, then one for anything slower
+  buckets: number[];
+};
+
+export type TimingSummary = { count: number; meanMs: number; p50Ms: number; p95Ms: number; maxMs: number };
+
+export type TelemetrySnapshot = {
+  since: string;
+  requests: Record<string, TimingSummary>;
+  bridge: Record<string, TimingSummary>;
+};
+
+// The bucket bound the given fraction of calls fall under; the maximum for
+// calls past the last bound
+export function percentile(t: Timing, fraction: number): number {
+  const wanted = Math.ceil(t.count * fraction);
+  let seen = 0;
+  for (let i = 0; i < t.buckets.length; i++) {
+    seen += t.buckets[i];
+    if (seen >= wanted) return Math.min(BUCKETS_MS[i] ?? t.maxMs, t.maxMs);
+  }
+  return t.maxMs;
+}
+
+export function summarize(t: Timing): TimingSummary {
+  return {
+    count: t.count,
+    meanMs: Math.round(t.totalMs / Math.max(t.count, 1)),
+    p50Ms: percentile(t, 0.5),
+    p95Ms: percentile(t, 0.95),
+    maxMs: Math.round(t.maxMs),
+  };
+}
+
+// One line per name: its summary, then the non-empty buckets
+export function histogramLines(timings: Map<string, Timing>): string[] {
+  return [...timings].map(([name, t]) => {
+    const s = summarize(t);
+    const bound = (i: number) => (i < BUCKETS_MS.length ? `≤${BUCKETS_MS[i]}ms` : `>${BUCKETS_MS[i - 1]}ms`);
+    const buckets = t.buckets.flatMap((n, i) => (n === 0 ? [] : [`${bound(i)}:${n}`])).join(' ');
+    const stats = `mean ${s.meanMs}ms, p50 ${s.p50Ms}ms, p95 ${s.p95Ms}ms, max ${s.maxMs}ms`;
+    return `${name}: ${s.count} calls, ${stats} | ${buckets}`;
+  });
+}
+
+export function createTelemetry() {
+  let enabled = false;
+  let since = new Date();
+  const timings: Record<TimingKind, Map<string, Timing>> = { request: new Map(), bridge: new Map() };
+
+  function reset() {
+    timings.request.clear();
+    timings.bridge.clear();
+    since = new Date();
+  }
+
+  return {
+    get enabled() {
+      return enabled;
+    },
+    // Turning it on starts from nothing
+    setEnabled(on: boolean) {
+      if (on && !enabled) reset();
+      enabled = on;
+    },
+    record(kind: TimingKind, name: string, ms: number) {
+      if (!enabled) return;
+      const empty = () => ({ count: 0, totalMs: 0, maxMs: 0, buckets: [...BUCKETS_MS, Infinity].map(() => 0) });
+      const t = timings[kind].get(name) ?? empty();
+      t.count++;
+      t.totalMs += ms;
+      t.maxMs = Math.max(t.maxMs, ms);
+      const bucket = BUCKETS_MS.findIndex((bound) => ms <= bound);
+      t.buckets[bucket < 0 ? BUCKETS_MS.length : bucket]++;
+      timings[kind].set(name, t);
+    },
+    snapshot(): TelemetrySnapshot {
+      const summaries = (m: Map<string, Timing>) => Object.fromEntries([...m].map(([k, t]) => [k, summarize(t)]));
+      return { since: since.toISOString(), requests: summaries(timings.request), bridge: summaries(timings.bridge) };
+    },
+    histograms(): string[] {
+      return [
+        ...histogramLines(timings.request).map((l) => `request ${l}`),
+        ...histogramLines(timings.bridge).map((l) => `bridge ${l}`),
+      ];
+    },
+    reset,
+  };
+}
+
+export type Telemetry = ReturnType<typeof createTelemetry>;
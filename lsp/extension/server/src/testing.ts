@@ -0,0 +1,120 @@
+// An in-process harness for testing the language server: the server runs in
+// the test's own process over in-memory streams, and a client connection
+// (vscode-jsonrpc, which does the message framing) drives it, with helpers
+// for a test's usual steps: open a document, edit it, ask for a hover, wait
+// for its diagnostics. The server keeps its state in its module, so there's
+// one per process: startTestServer returns the same one every time, and
+// tests keep to their own documents; the options (host.ts) the first call
+// passes are the ones it runs with.
+//
+//   const server = startTestServer({ cliPath: '/w/src/cli.ts', runtime: 'bun' });
+//   await server.initialize();
+//   await server.open(uri, 'x = 1 + "a"');
+//   const { diagnostics } = await server.diagnostics(uri, (d) => d.diagnostics.length > 0);
+import { PassThrough } from 'stream';
+import {
+  createMessageConnection,
+  Hover,
+  InitializeParams,
+  InitializeResult,
+  MessageConnection,
+  Position,
+  PublishDiagnosticsParams,
+  StreamMessageReader,
+  StreamMessageWriter,
+} from 'vscode-languageserver/node';
+import { HostOptions, serveWithIo } from './host';
+
+const WAIT_TIMEOUT_MS = 10_000;
+
+export type TestServer = {
+  // For requests and notifications the helpers don't cover
+  connection: MessageConnection;
+  initialize(params?: Partial<InitializeParams>): Promise<InitializeResult>;
+  open(uri: string, text: string, version?: number): Promise<void>;
+  // Replaces the whole text, as the server syncs documents in full
+  edit(uri: string, text: string, version: number): Promise<void>;
+  save(uri: string): Promise<void>;
+  close(uri: string): Promise<void>;
+  hover(uri: string, position: Position): Promise<Hover | null>;
+  // The latest diagnostics published for `uri` that satisfy `until`, waiting
+  // for a publish if none have yet
+  diagnostics(uri: string, until?: (published: PublishDiagnosticsParams) => boolean): Promise<PublishDiagnosticsParams>;
+  // `shutdown`, but not `exit`, which would end the test's process too
+  shutdown(): Promise<void>;
+};
+
+let started: TestServer | undefined;
+
+export function startTestServer(options: HostOptions = {}): TestServer {
+  if (started) return started;
+  // The server doesn't see the input end: that would make it exit
+  const toServer = new PassThrough();
+  const fromServer = new PassThrough();
+  serveWithIo(toServer, fromServer, options);
+  const connection = createMessageConnection(new StreamMessageReader(fromServer), new StreamMessageWriter(toServer));
+
+  const published = new Map<string, PublishDiagnosticsParams>();
+  const waiting: { uri: string; until: (p: PublishDiagnosticsParams) => boolean; resolve: () => void }[] = [];
+  connection.onNotification('textDocument/publishDiagnostics', (params: PublishDiagnosticsParams) => {
+    published.set(params.uri, params);
+    for (const w of waiting.filter((w) => w.uri === params.uri && w.until(params))) {
+      waiting.splice(waiting.indexOf(w), 1);
+      w.resolve();
+    }
+  });
+  // Requests from the server (edits to apply, refreshes, messages to show)
+  // are answered as a client that does nothing with them
+  connection.onRequest(() => null);
+  connection.listen();
+
+  const document = (uri: string) => ({ textDocument: { uri } });
+  started = {
+    connection,
+    async initialize(params = {}) {
+      const result = await connection.sendRequest<InitializeResult>('initialize', {
+        processId: null,
+        rootUri: null,
+        capabilities: {},
+        workspaceFolders: null,
+        ...params,
+      });
+      await connection.sendNotification('initialized', {});
+      return result;
+    },
+    open: (uri, text, version = 1) =>
+      connection.sendNotification('textDocument/didOpen', {
+        textDocument: { uri, languageId: 'noolang', version, text },
+      }),
+    edit: (uri, text, version) =>
+      connection.sendNotification('textDocument/didChange', {
+        textDocument: { uri, version },
+        contentChanges: [{ text }],
+      }),
+    save: (uri) => connection.sendNotification('textDocument/didSave', document(uri)),
+    close: (uri) => connection.sendNotification('textDocument/didClose', document(uri)),
+    hover: (uri, position) =>
+      connection.sendRequest<Hover | null>('textDocument/hover', { ...document(uri), position }),
+    diagnostics(uri, until = () => true) {
+      const latest = published.get(uri);
+      if (latest && until(latest)) return Promise.resolve(latest);
+      return new Promise((resolve, reject) => {
+        const timer = setTimeout(() => {
+          waiting.splice(waiting.indexOf(entry), 1);
+          reject(new Error(`no diagnostics for ${uri} within ${WAIT_TIMEOUT_MS}ms`));
+        }, WAIT_TIMEOUT_MS);
+        const entry = {
+          uri,
+          until,
+          resolve: () => {
+            clearTimeout(timer);
+            resolve(published.get(uri) as PublishDiagnosticsParams);
+          },
+        };
+        waiting.push(entry);
+      });
+    },
+    shutdown: () => connection.sendRequest<void>('shutdown'),
+  };
+  return started;
+}
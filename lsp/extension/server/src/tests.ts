@@ -0,0 +1,85 @@
+// Test lenses for std/test suites (*.test.noo). Cases are found in the
+// buffer by their `test_case "name"` calls, and a run's results come from the
+// report `noo --test-file` prints (format_result in std/test.noo), matched
+// back to those calls by name in order.
+import { isTrivia, lexSyntax } from './syntax';
+
+export const isTestFile = (filePath: string) => filePath.endsWith('.test.noo');
+
+// Offsets span the name's string literal
+export type TestCaseSite = { kind: 'case' | 'group'; name: string; start: number; end: number };
+
+export type TestCaseResult = { groups: string[]; name: string; passed: boolean; message?: string };
+
+export type TestReport = { cases: TestCaseResult[]; passed: number; failed: number };
+
+function unquote(literal: string): string {
+  try {
+    return JSON.parse(literal);
+  } catch {
+    return literal.slice(1, -1);
+  }
+}
+
+export function findTestSites(source: string): TestCaseSite[] {
+  const tokens = lexSyntax(source).filter((t) => !isTrivia(t));
+  const sites: TestCaseSite[] = [];
+  tokens.forEach((token, i) => {
+    const next = tokens[i + 1];
+    if (token.kind !== 'identifier' || next?.kind !== 'string') return;
+    if (token.text !== 'test_case' && token.text !== 'group') return;
+    const kind = token.text === 'test_case' ? 'case' : 'group';
+    sites.push({ kind, name: unquote(next.text), start: next.start, end: next.end });
+  });
+  return sites;
+}
+
+const CASE_LINE = /^( *)([✓✗]) (.*)$/;
+const SUMMARY_LINE = /^(\d+) passed, (\d+) failed$/;
+
+// Undefined when the output has no summary line: the suite didn't run
+export function parseTestReport(stdout: string): TestReport | undefined {
+  const cases: TestCaseResult[] = [];
+  const groups: string[] = [];
+  let failing: { result: TestCaseResult; detailIndent: number } | undefined;
+  // The first line is the suite's path
+  for (const line of stdout.split(/\r?\n/).slice(1)) {
+    const indent = line.length - line.trimStart().length;
+    if (failing && line.trim() && indent >= failing.detailIndent) {
+      const detail = line.trim();
+      failing.result.message = failing.result.message ? `${failing.result.message}\n${detail}` : detail;
+      continue;
+    }
+    failing = undefined;
+    const summary = SUMMARY_LINE.exec(line.trim());
+    if (summary) return { cases, passed: Number(summary[1]), failed: Number(summary[2]) };
+    if (!line.trim()) continue;
+    const depth = indent / 2;
+    groups.length = Math.min(groups.length, depth);
+    const match = CASE_LINE.exec(line);
+    if (!match) {
+      groups.push(line.trim());
+      continue;
+    }
+    const result: TestCaseResult = { groups: [...groups], name: match[3], passed: match[2] === '✓' };
+    cases.push(result);
+    if (!result.passed) failing = { result, detailIndent: indent + 4 };
+  }
+  return undefined;
+}
+
+// Each result paired with the `test_case` call it came from, in order
+export function matchResults(
+  sites: TestCaseSite[],
+  results: TestCaseResult[],
+): { site: TestCaseSite; result: TestCaseResult }[] {
+  const unmatched = sites.filter((s) => s.kind === 'case');
+  const pairs: { site: TestCaseSite; result: TestCaseResult }[] = [];
+  for (const result of results) {
+    const index = unmatched.findIndex((s) => s.name === result.name);
+    if (index < 0) continue;
+    pairs.push({ site: unmatched[index], result });
+    unmatched.splice(index, 1);
+  }
+  return pairs;
+}
@@ -0,0 +1,55 @@
+// How the server talks to its client. stdio is the default; --node-ipc and
+// --socket=<port> are left to vscode-languageserver, which reads them from
+// the command line itself. This module adds a TCP listener mode for editors
+// and remote setups that connect to an already-running server, a named pipe
+// (a Unix domain socket elsewhere) the client opened for the server, and
+// streams a host running the server in its own process hands over
+// (host.ts). Flags are parsed in args.ts.
+import * as net from 'net';
+import { PassThrough } from 'stream';
+
+export const DEFAULT_TCP_PORT = 2087;
+
+export type Streams = { input: NodeJS.ReadableStream; output: NodeJS.WritableStream };
+
+export type Transport =
+  | { kind: 'stdio' }
+  | { kind: 'library' }
+  | { kind: 'tcp'; host: string; port: number }
+  | { kind: 'pipe'; name: string };
+
+// The connection has to exist before the first client connects, since every
+// handler registers on it at startup. Hand it a pair of pass-through streams
+// now and splice the socket in once a client arrives. One server serves one
+// client, so the listener stops accepting after the first connection.
+export function listenTcp(
+  host: string,
+  port: number,
+  onListening: (address: string) => void,
+): { input: NodeJS.ReadableStream; output: NodeJS.WritableStream } {
+  const input = new PassThrough();
+  const output = new PassThrough();
+  const server = net.createServer((socket) => {
+    server.close();
+    socket.pipe(input);
+    output.pipe(socket);
+  });
+  server.listen(port, host, () => onListening(`${host}:${port}`));
+  return { input, output };
+}
+
+// A bare pipe name, as Windows clients pass it, is under \\.\pipe\; a full
+// pipe path, or a socket path anywhere else, is used as it is.
+export function pipePath(name: string, platform: NodeJS.Platform = process.platform): string {
+  if (platform !== 'win32' || name.startsWith('\\\\')) return name;
+  return `\\\\.\\pipe\\${name}`;
+}
+
+// The client creates the pipe and waits for the server to connect to it, as
+// vscode-languageclient does for TransportKind.pipe. The connection ends
+// the process when the pipe closes, as it does when stdin does.
+export function connectPipe(name: string, onError: (error: Error) => void): Streams {
+  const socket = net.connect(pipePath(name));
+  socket.on('error', onError);
+  return { input: socket, output: socket };
+}
@@ -0,0 +1,368 @@
+// Structured Noolang types, parsed from the strings the CLI prints (see
+// typeToString in src/typer/helpers.ts), and one renderer for every place the
+// server shows a type. Rendering from the structure lets long types be
+// shortened where it loses least: record fields are elided before anything
+// else, instead of the whole type collapsing to a placeholder.
+
+export type NoolangType =
+  // Curried: `a -> b -> c` is a function returning a function. Effects belong
+  // to the innermost function, which is where the CLI prints them.
+  | { kind: 'function'; param: NoolangType; result: NoolangType; effects: string[] }
+  | { kind: 'variable'; name: string }
+  // Primitives, ADTs and List; `name` is lowercase for higher-kinded variables
+  | { kind: 'named'; name: string; args: NoolangType[] }
+  | { kind: 'record'; fields: { name: string; type: NoolangType }[] }
+  | { kind: 'tuple'; elements: NoolangType[] }
+  | { kind: 'unit' }
+  | { kind: 'union'; types: NoolangType[] }
+  | { kind: 'constrained'; type: NoolangType; constraints: TypeConstraint[] };
+
+export type TypeConstraint =
+  | { kind: 'implements'; variable: string; trait: string }
+  | { kind: 'has'; variable: string; fields: NoolangType }
+  | { kind: 'hasField'; variable: string; field: string; type: NoolangType }
+  | { kind: 'is'; variable: string; name: string };
+
+const TOKEN = /\s*(->|[{}(),|:]|@\w+|!\w+|[A-Za-z_]\w*)/y;
+const KEYWORDS = new Set(['given', 'and', 'implements', 'has', 'is']);
+
+function tokenize(text: string): string[] | undefined {
+  const tokens: string[] = [];
+  TOKEN.lastIndex = 0;
+  while (TOKEN.lastIndex < text.length) {
+    if (!text.slice(TOKEN.lastIndex).trim()) break;
+    const m = TOKEN.exec(text);
+    if (!m) return undefined;
+    tokens.push(m[1]);
+  }
+  return tokens;
+}
+
+// Undefined for anything that isn't a type string the CLI would print
+export function parseType(text: string): NoolangType | undefined {
+  const tokens = tokenize(text);
+  if (!tokens) return undefined;
+  let i = 0;
+  const peek = () => tokens[i];
+  const take = (expected?: string) => {
+    const t = tokens[i];
+    if (t === undefined || (expected !== undefined && t !== expected)) throw new Error(`expected ${expected}`);
+    i++;
+    return t;
+  };
+  const isName = (t: string | undefined) => !!t && /^[A-Za-z_]/.test(t) && !KEYWORDS.has(t);
+
+  function functionType(): NoolangType {
+    const param = application();
+    if (peek() !== '->') return param;
+    take();
+    const result = functionType();
+    const effects: string[] = [];
+    while (peek()?.startsWith('!')) effects.push(take().slice(1));
+    return { kind: 'function', param, result, effects };
+  }
+
+  function application(): NoolangType {
+    if (!isName(peek())) return atom();
+    const name = take();
+    const args: NoolangType[] = [];
+    while (isName(peek()) || peek() === '{' || peek() === '(') args.push(atom());
+    if (args.length === 0 && /^[a-z]/.test(name)) return { kind: 'variable', name };
+    return { kind: 'named', name, args };
+  }
+
+  function atom(): NoolangType {
+    const t = take();
+    if (isName(t)) {
+      return /^[a-z]/.test(t) ? { kind: 'variable', name: t } : { kind: 'named', name: t, args: [] };
+    }
+    if (t === '(') {
+      const types = [functionType()];
+      while (peek() === '|') {
+        take();
+        types.push(functionType());
+      }
+      take(')');
+      return types.length === 1 ? types[0] : { kind: 'union', types };
+    }
+    if (t === '{') {
+      if (peek() === '}') {
+        take();
+        return { kind: 'unit' };
+      }
+      if (peek()?.startsWith('@')) {
+        const fields: { name: string; type: NoolangType }[] = [];
+        do {
+          if (fields.length > 0) take(',');
+          fields.push({ name: take().slice(1), type: functionType() });
+        } while (peek() === ',');
+        take('}');
+        return { kind: 'record', fields };
+      }
+      const elements = [functionType()];
+      while (peek() === ',') {
+        take();
+        elements.push(functionType());
+      }
+      take('}');
+      return { kind: 'tuple', elements };
+    }
+    throw new Error(`unexpected ${t}`);
+  }
+
+  function constraint(): TypeConstraint {
+    const variable = take();
+    const relation = take();
+    if (relation === 'implements') return { kind: 'implements', variable, trait: take() };
+    if (relation === 'is') return { kind: 'is', variable, name: take() };
+    if (relation === 'has' && peek() === '{') return { kind: 'has', variable, fields: atom() };
+    if (relation === 'has') {
+      const field = take();
+      take(':');
+      return { kind: 'hasField', variable, field, type: functionType() };
+    }
+    throw new Error(`unknown constraint ${relation}`);
+  }
+
+  try {
+    const type = functionType();
+    if (peek() === 'given') {
+      take();
+      const constraints = [constraint()];
+      while (peek() === ',' || peek() === 'and') {
+        take();
+        constraints.push(constraint());
+      }
+      if (i < tokens.length) return undefined;
+      return { kind: 'constrained', type, constraints };
+    }
+    return i < tokens.length ? undefined : type;
+  } catch {
+    return undefined;
+  }
+}
+
+// What each effect the type checker tracks (Effect in src/ast.ts) stands for
+export const EFFECT_DESCRIPTIONS: Record<string, string> = {
+  log: 'writes to the log',
+  read: 'reads files or input',
+  write: 'prints or writes files',
+  state: 'reads or changes mutable state',
+  time: 'reads the clock',
+  rand: 'uses randomness',
+  ffi: 'calls foreign (host) functions',
+  async: 'runs asynchronously',
+};
+
+// The effects a call performs, from a type string as printed or rendered
+// (either arrow). Effects of function-typed parameters or fields sit inside
+// brackets and belong to those functions, so bracketed parts are skipped.
+export function callEffects(text: string): string[] {
+  let outer = text;
+  for (let prev = ''; prev !== outer; ) {
+    prev = outer;
+    outer = outer.replace(/\([^()]*\)|\{[^{}]*\}/g, '');
+  }
+  return [...new Set([...outer.matchAll(/!(\w+)/g)].map((m) => m[1]))];
+}
+
+// `type Name = …` declarations. The CLI prints these expanded, as the
+// structural type they stand for.
+export type TypeAlias = { name: string; type: NoolangType };
+
+export type RenderOptions = {
+  // '→' or '->'
+  arrow: string;
+  // Record fields are elided, last first, until the type fits
+  maxWidth: number;
+  // Records and tuples nested deeper than this show as {…}
+  maxDepth: number;
+  // Types equal to one of these are shown by the alias's name
+  aliases: TypeAlias[];
+  // Aliases nested in fewer than this many others are shown expanded
+  // instead; none by default
+  expandLevels?: number;
+};
+
+export const DEFAULT_RENDER: RenderOptions = { arrow: '→', maxWidth: 60, maxDepth: 3, aliases: [] };
+
+// Structural equality; record fields match regardless of order
+export function sameType(a: NoolangType, b: NoolangType): boolean {
+  const all = (xs: NoolangType[], ys: NoolangType[]) => xs.length === ys.length && xs.every((x, i) => sameType(x, ys[i]));
+  switch (a.kind) {
+    case 'function':
+      return b.kind === 'function' && sameType(a.param, b.param) && sameType(a.result, b.result);
+    case 'variable':
+      return b.kind === 'variable' && a.name === b.name;
+    case 'named':
+      return b.kind === 'named' && a.name === b.name && all(a.args, b.args);
+    case 'record':
+      return (
+        b.kind === 'record' &&
+        a.fields.length === b.fields.length &&
+        a.fields.every((f) => b.fields.some((g) => g.name === f.name && sameType(f.type, g.type)))
+      );
+    case 'tuple':
+      return b.kind === 'tuple' && all(a.elements, b.elements);
+    case 'unit':
+      return b.kind === 'unit';
+    case 'union':
+      return b.kind === 'union' && all(a.types, b.types);
+    case 'constrained':
+      return false;
+  }
+}
+
+function render(type: NoolangType, options: RenderOptions, maxFields: number, depth = 0, expanded = 0): string {
+  const alias = options.aliases.find((a) => sameType(a.type, type));
+  if (alias && expanded >= (options.expandLevels ?? 0)) return alias.name;
+  const nested = type.kind === 'record' || type.kind === 'tuple';
+  if (nested && depth >= options.maxDepth) return '{…}';
+  const go = (t: NoolangType) =>
+    render(t, options, maxFields, nested ? depth + 1 : depth, alias ? expanded + 1 : expanded);
+  // Function parameters and type arguments are parenthesized when they'd
+  // otherwise read as part of the enclosing type
+  const wrapped = (t: NoolangType) =>
+    t.kind === 'function' || t.kind === 'constrained' || (t.kind === 'named' && t.args.length > 0)
+      ? `(${go(t)})`
+      : go(t);
+  switch (type.kind) {
+    case 'function': {
+      const param = type.param.kind === 'function' ? `(${go(type.param)})` : go(type.param);
+      const effects = type.effects.map((e) => ` !${e}`).join('');
+      return `${param} ${options.arrow} ${go(type.result)}${effects}`;
+    }
+    case 'variable':
+      return type.name;
+    case 'named':
+      return [type.name, ...type.args.map(wrapped)].join(' ');
+    case 'record': {
+      const shown = type.fields.slice(0, maxFields).map((f) => `@${f.name} ${go(f.type)}`);
+      if (shown.length < type.fields.length) shown.push('…');
+      return `{ ${shown.join(', ')} }`;
+    }
+    case 'tuple':
+      return `{${type.elements.map(go).join(', ')}}`;
+    case 'unit':
+      return '{}';
+    case 'union':
+      return `(${type.types.map(go).join(' | ')})`;
+    case 'constrained':
+      return `${go(type.type)} given ${type.constraints.map((c) => renderConstraint(c, go)).join(', ')}`;
+  }
+}
+
+function renderConstraint(c: TypeConstraint, go: (t: NoolangType) => string): string {
+  switch (c.kind) {
+    case 'implements':
+      return `${c.variable} implements ${c.trait}`;
+    case 'has':
+      return `${c.variable} has ${go(c.fields)}`;
+    case 'hasField':
+      return `${c.variable} has ${c.field}: ${go(c.type)}`;
+    case 'is':
+      return `${c.variable} is ${c.name}`;
+  }
+}
+
+function widestRecord(type: NoolangType): number {
+  switch (type.kind) {
+    case 'function':
+      return Math.max(widestRecord(type.param), widestRecord(type.result));
+    case 'named':
+      return Math.max(0, ...type.args.map(widestRecord));
+    case 'record':
+      return Math.max(type.fields.length, ...type.fields.map((f) => widestRecord(f.type)));
+    case 'tuple':
+      return Math.max(0, ...type.elements.map(widestRecord));
+    case 'union':
+      return Math.max(0, ...type.types.map(widestRecord));
+    case 'constrained':
+      return widestRecord(type.type);
+    default:
+      return 0;
+  }
+}
+
+export function renderType(type: NoolangType, options: RenderOptions = DEFAULT_RENDER): string {
+  let text = render(type, options, Infinity);
+  for (let fields = widestRecord(type) - 1; fields >= 1 && text.length > options.maxWidth; fields--) {
+    text = render(type, options, fields);
+  }
+  return text;
+}
+
+// A type expanded one step at a time: first as shown, by its aliases' names
+// and with fields elided to fit; then each alias replaced by what it stands
+// for, one level of nesting per step, in full; last with none left.
+export function typeExpansions(type: NoolangType, options: RenderOptions): string[] {
+  const steps = [renderType(type, { ...options, expandLevels: 0 })];
+  const full = { ...options, maxWidth: Infinity, maxDepth: Infinity };
+  let text = renderType(type, { ...full, expandLevels: 0 });
+  for (let level = 1; ; level++) {
+    if (text !== steps[steps.length - 1]) steps.push(text);
+    // Once a step names no alias, the next is the same
+    const next = renderType(type, { ...full, expandLevels: level });
+    if (next === text) return steps;
+    text = next;
+  }
+}
+
+// A type as the compiler serializes it in the AST (src/ast.ts Type, or a
+// user-defined type's definition); undefined for anything not expressible.
+export function fromCompilerType(raw: any): NoolangType | undefined {
+  const all = (xs: unknown): NoolangType[] | undefined => {
+    if (!Array.isArray(xs)) return undefined;
+    const types = xs.map(fromCompilerType);
+    return types.every((t): t is NoolangType => !!t) ? types : undefined;
+  };
+  if (!raw || typeof raw !== 'object') return undefined;
+  switch (raw.kind) {
+    case 'primitive':
+      return { kind: 'named', name: raw.name, args: [] };
+    case 'variable':
+      return { kind: 'variable', name: raw.name };
+    case 'variant': {
+      const args = all(raw.args ?? []);
+      return args && { kind: 'named', name: raw.name, args };
+    }
+    case 'list': {
+      const element = fromCompilerType(raw.element);
+      return element && { kind: 'named', name: 'List', args: [element] };
+    }
+    case 'tuple':
+    case 'tuple-type': {
+      const elements = all(raw.elements);
+      return elements && { kind: 'tuple', elements };
+    }
+    case 'record':
+    case 'record-type': {
+      if (!raw.fields || typeof raw.fields !== 'object') return undefined;
+      const fields = Object.entries(raw.fields).map(([name, t]) => ({ name, type: fromCompilerType(t) }));
+      if (!fields.every((f) => f.type)) return undefined;
+      return fields.length === 0 ? { kind: 'unit' } : { kind: 'record', fields: fields as { name: string; type: NoolangType }[] };
+    }
+    case 'union':
+    case 'union-type': {
+      const types = all(raw.types);
+      return types && { kind: 'union', types };
+    }
+    case 'unit':
+      return { kind: 'unit' };
+    case 'function': {
+      const params = all(raw.params);
+      const result = fromCompilerType(raw.return);
+      if (!params || !result || params.length === 0) return undefined;
+      return params.reduceRight<NoolangType>((acc, param) => ({ kind: 'function', param, result: acc, effects: [] }), result);
+    }
+    default:
+      return undefined;
+  }
+}
+
+// For display: a type string from the CLI, re-rendered. Strings that don't
+// parse are shown as printed, with only the arrows swapped.
+export function formatTypeString(text: string, options: RenderOptions = DEFAULT_RENDER): string {
+  const type = parseType(text);
+  return type ? renderType(type, options) : text.replace(/ -> /g, ` ${options.arrow} `);
+}
@@ -0,0 +1,38 @@
+// Generated views of a source file, shown as read-only documents under the
+// `noolang:` scheme: its syntax tree (as an outline or JSON), the types of
+// its top-level bindings and the values they evaluate to. A view's URI names
+// the view and, in its query, the source:
+// `noolang:/ast/main.noo.txt?file%3A%2F%2F%2Fw%2Fmain.noo`.
+
+export type ViewKind = 'ast' | 'ast-json' | 'types' | 'evaluation';
+
+const EXTENSIONS: Record<ViewKind, string> = { ast: 'txt', 'ast-json': 'json', types: 'txt', evaluation: 'txt' };
+
+// When a view is rendered again: the syntax tree follows the buffer, while
+// types are checked and values computed from the saved file
+export const REFRESHED_ON: Record<ViewKind, 'change' | 'save'> = {
+  ast: 'change',
+  'ast-json': 'change',
+  types: 'save',
+  evaluation: 'save',
+};
+
+export type View = { kind: ViewKind; source: string };
+
+const isViewKind = (kind: string): kind is ViewKind => Object.prototype.hasOwnProperty.call(EXTENSIONS, kind);
+
+export function viewUri({ kind, source }: View): string {
+  const name = decodeURIComponent(source.split('/').pop() ?? 'document');
+  return `noolang:/${kind}/${encodeURIComponent(name)}.${EXTENSIONS[kind]}?${encodeURIComponent(source)}`;
+}
+
+// Undefined for other noolang: documents (benchmark reports) and other schemes
+export function parseViewUri(uri: string): View | undefined {
+  const m = /^noolang:\/([\w-]+)\/[^?]*\?(.+)$/.exec(uri);
+  if (!m || !isViewKind(m[1])) return undefined;
+  try {
+    return { kind: m[1], source: decodeURIComponent(m[2]) };
+  } catch {
+    return undefined;
+  }
+}
@@ -0,0 +1,492 @@
+// The workspace reference index: every .noo file under the workspace
+// folders, read with the lossless lexer (no CLI calls), recording where each
+// name and `@field` occurs, what the file imports and what it exports. Files
+// are re-indexed when their text source says they changed: open documents
+// by version, others by mtime, and a file whose stamp changed but whose
+// content hash didn't keeps its entry. References are by name, like the
+// single-file ones: shadowing isn't resolved.
+import * as fs from 'fs';
+import * as path from 'path';
+import { createHash } from 'crypto';
+import { docCommentAbove, isPunctuation, isTrivia, lexSyntax, SyntaxToken } from './syntax';
+import { deprecationNote, OutlineKind } from './symbols';
+
+// Offsets into the file's text
+export type Span = { start: number; end: number };
+
+// `{@add, @mul times} = import "./math"` or `math = import "./math"`
+export type ImportSite = {
+  specifier: string;
+  // The string literal, quotes included
+  specifierSpan: Span;
+  // The resolved module, when it exists
+  target?: string;
+  // Destructured fields; `local` is the binding's name and span
+  fields: { field: string; fieldSpan: Span; local: string; localSpan?: Span }[];
+  // The name the whole module is bound to
+  binding?: string;
+};
+
+export type IndexedFile = {
+  filePath: string;
+  text: string;
+  stamp: string;
+  // Of the text, see contentHash
+  hash: string;
+  identifiers: Map<string, Span[]>;
+  // Accessors and record fields, by name without the `@`; spans likewise
+  fields: Map<string, Span[]>;
+  // Fields destructured as `{@field}`, which binds a name `field` too
+  shorthands: Set<string>;
+  imports: ImportSite[];
+  // Fields of the record the file ends with (its exported value), with the
+  // name each one's value is, when that's a plain name
+  exports: { field: string; fieldSpan: Span; local?: string; localSpan?: Span }[];
+  declarations: Declaration[];
+};
+
+// A top-level name the file declares, for workspace symbols; `span` is the
+// name's. Members (constructors, constraint and implement functions, a
+// record type's fields) name their declaration as `container`. `deprecated` is the note a `@deprecated`
+// doc comment gives, possibly empty. Types and aliases list their type
+// `params`, constructors the text of their argument types as `args`.
+export type Declaration = {
+  name: string;
+  kind: OutlineKind;
+  span: Span;
+  container?: string;
+  deprecated?: string;
+  params?: string[];
+  args?: string;
+};
+
+// A stamp that changes with a file's text, and a way to read it; undefined
+// when the file is gone
+export type TextSource = (filePath: string) => { stamp: string; read: () => string } | undefined;
+
+export const contentHash = (text: string) => createHash('sha1').update(text).digest('hex');
+
+export function noolangFilesIn(dir: string): string[] {
+  let entries: fs.Dirent[];
+  try {
+    entries = fs.readdirSync(dir, { withFileTypes: true });
+  } catch {
+    return [];
+  }
+  return entries.flatMap((entry) => {
+    if (entry.name === 'node_modules' || entry.name.startsWith('.')) return [];
+    const full = path.join(dir, entry.name);
+    if (entry.isDirectory()) return noolangFilesIn(full);
+    return entry.name.endsWith('.noo') ? [full] : [];
+  });
+}
+
+// `filePath` is one of `paths` or inside one of them
+export const isUnder = (filePath: string, paths: string[]) =>
+  paths.some((p) => filePath === p || filePath.startsWith(p + path.sep));
+
+// The file an import specifier names: relative to the importer, or through
+// the import map of the nearest noolang.json above it
+export function resolveImport(fromFile: string, specifier: string): string | undefined {
+  const withExtension = (p: string) => (p.endsWith('.noo') ? p : `${p}.noo`);
+  if (specifier.startsWith('./') || specifier.startsWith('../')) {
+    return withExtension(path.resolve(path.dirname(fromFile), specifier));
+  }
+  for (let dir = path.dirname(fromFile); ; dir = path.dirname(dir)) {
+    const mapFile = path.join(dir, 'noolang.json');
+    if (fs.existsSync(mapFile)) {
+      let imports: Record<string, string> = {};
+      try {
+        imports = JSON.parse(fs.readFileSync(mapFile, 'utf8')).imports ?? {};
+      } catch {
+        return undefined;
+      }
+      if (typeof imports[specifier] === 'string') return withExtension(path.resolve(dir, imports[specifier]));
+      const prefix = Object.keys(imports).find((k) => k.endsWith('/') && specifier.startsWith(k));
+      return prefix ? withExtension(path.resolve(dir, imports[prefix] + specifier.slice(prefix.length))) : undefined;
+    }
+    if (path.dirname(dir) === dir) return undefined;
+  }
+}
+
+// How `fromFile` would import `target`: relative to it, without the
+// extension
+export function importSpecifier(fromFile: string, target: string): string {
+  const relative = path.relative(path.dirname(fromFile), target).replace(/\.noo$/, '').split(path.sep).join('/');
+  return relative.startsWith('../') ? relative : `./${relative}`;
+}
+
+const isOp = (t: SyntaxToken | undefined, text: string) => t?.kind === 'operator' && t.text === text;
+const isKeyword = (t: SyntaxToken | undefined, text: string) => t?.kind === 'keyword' && t.text === text;
+
+function unquote(literal: string): string {
+  try {
+    return JSON.parse(literal);
+  } catch {
+    return literal.slice(1, -1);
+  }
+}
+
+const fieldSpan = (t: SyntaxToken): Span => ({ start: t.start + 1, end: t.end });
+
+function push(map: Map<string, Span[]>, name: string, span: Span) {
+  const spans = map.get(name) ?? [];
+  spans.push(span);
+  map.set(name, spans);
+}
+
+export function indexText(filePath: string, text: string, stamp: string): IndexedFile {
+  const tokens = lexSyntax(text).filter((t) => !isTrivia(t));
+  const identifiers = new Map<string, Span[]>();
+  const fields = new Map<string, Span[]>();
+  const shorthands = new Set<string>();
+  tokens.forEach((t, i) => {
+    if (t.kind === 'identifier') push(identifiers, t.text, { start: t.start, end: t.end });
+    if (t.kind !== 'accessor') return;
+    push(fields, t.text.slice(1), fieldSpan(t));
+    const [before, after] = [tokens[i - 1], tokens[i + 1]];
+    const inPattern = isPunctuation(before, '{') || isPunctuation(before, ',');
+    if (inPattern && (isPunctuation(after, ',') || isPunctuation(after, '}'))) shorthands.add(t.text.slice(1));
+  });
+
+  const imports: ImportSite[] = [];
+  tokens.forEach((t, i) => {
+    const spec = tokens[i + 1];
+    if (t.kind !== 'keyword' || t.text !== 'import' || spec?.kind !== 'string') return;
+    const specifier = unquote(spec.text);
+    const site: ImportSite = {
+      specifier,
+      specifierSpan: { start: spec.start, end: spec.end },
+      target: resolveImport(filePath, specifier),
+      fields: [],
+    };
+    const bound = isOp(tokens[i - 1], '=') ? tokens[i - 2] : undefined;
+    if (bound?.kind === 'identifier') site.binding = bound.text;
+    if (bound && isPunctuation(bound, '}')) {
+      // Back to the pattern's `{`, then read `@field [local]` entries
+      let open = i - 2;
+      while (open > 0 && !isPunctuation(tokens[open], '{')) open--;
+      for (let j = open + 1; j < i - 2; j++) {
+        const field = tokens[j];
+        if (field.kind !== 'accessor') continue;
+        const local = tokens[j + 1]?.kind === 'identifier' ? tokens[j + 1] : undefined;
+        site.fields.push({
+          field: field.text.slice(1),
+          fieldSpan: fieldSpan(field),
+          local: local ? local.text : field.text.slice(1),
+          localSpan: local && { start: local.start, end: local.end },
+        });
+      }
+    }
+    imports.push(site);
+  });
+
+  const exports = exportedFields(tokens);
+  return {
+    filePath,
+    text,
+    stamp,
+    hash: contentHash(text),
+    identifiers,
+    fields,
+    shorthands,
+    imports,
+    exports,
+    declarations: declarations(text, tokens),
+  };
+}
+
+// The top-level statements, each with its tokens' bracket depths
+function statements(tokens: SyntaxToken[]): { token: SyntaxToken; depth: number }[][] {
+  const out: { token: SyntaxToken; depth: number }[][] = [[]];
+  let depth = 0;
+  for (const token of tokens) {
+    if (token.kind === 'punctuation' && ')]}'.includes(token.text)) depth--;
+    if (depth === 0 && isPunctuation(token, ';')) out.push([]);
+    else out[out.length - 1].push({ token, depth });
+    if (token.kind === 'punctuation' && '([{'.includes(token.text)) depth++;
+  }
+  return out.filter((statement) => statement.length > 0);
+}
+
+// Found lexically, like the rest of the index: `name = …`, `variant`,
+// `type`, `constraint` and `implement` declarations and their members
+function declarations(text: string, tokens: SyntaxToken[]): Declaration[] {
+  const out: Declaration[] = [];
+  // The doc comment is above `at`: the statement's start, or a member's name
+  const declare = (d: Declaration, at = d.span.start) => {
+    const deprecated = deprecationNote(docCommentAbove(text, at));
+    out.push(deprecated === undefined ? d : { ...d, deprecated });
+  };
+  const span = (t: SyntaxToken): Span => ({ start: t.start, end: t.end });
+  for (const statement of statements(tokens)) {
+    const at = isKeyword(statement[0].token, 'mut') ? 1 : 0;
+    const name = statement[at]?.token;
+    if (name?.kind === 'identifier' && isOp(statement[at + 1]?.token, '=')) {
+      const kind = isKeyword(statement[at + 2]?.token, 'fn') ? 'function' : 'value';
+      declare({ name: name.text, kind, span: span(name) }, statement[0].token.start);
+      continue;
+    }
+    const [keyword, declared] = [statement[0].token, statement[1]?.token];
+    if (keyword.kind !== 'keyword' || declared?.kind !== 'identifier') continue;
+    // A type's parameters: the names between it and the `=`
+    const params = () => {
+      const equals = statement.findIndex(({ token }) => isOp(token, '='));
+      const names = statement.slice(2, equals < 0 ? 2 : equals).map(({ token }) => token);
+      return names.flatMap((t) => (t.kind === 'identifier' ? [t.text] : []));
+    };
+    // Members: the names at depth 1 starting each entry of the body, followed
+    // by `:` in a constraint and `=` in an implement block
+    const members = (separator: string, kind: OutlineKind, container: string) =>
+      statement.forEach(({ token, depth }, i) => {
+        const before = statement[i - 1]?.token;
+        const starts = isPunctuation(before, '(') || isPunctuation(before, ';');
+        const after = statement[i + 1]?.token;
+        const separated = separator === '=' ? isOp(after, '=') : isPunctuation(after, separator);
+        if (depth === 1 && token.kind === 'identifier' && starts && separated) {
+          declare({ name: token.text, kind, span: span(token), container });
+        }
+      });
+    switch (keyword.text) {
+      case 'variant': {
+        declare({ name: declared.text, kind: 'type', span: span(declared), params: params() }, keyword.start);
+        // Constructors follow the `=` and each `|`, their arguments up to the
+        // next `|`
+        statement.forEach(({ token, depth }, i) => {
+          const before = statement[i - 1]?.token;
+          if (depth === 0 && token.kind === 'identifier' && i > 2 && (isOp(before, '=') || isOp(before, '|'))) {
+            const next = statement.findIndex((s, j) => j > i && s.depth === 0 && isOp(s.token, '|'));
+            const last = statement[(next < 0 ? statement.length : next) - 1].token;
+            const args = text.slice(token.end, last.end).trim();
+            declare({ name: token.text, kind: 'constructor', span: span(token), container: declared.text, args });
+          }
+        });
+        break;
+      }
+      case 'type':
+        declare({ name: declared.text, kind: 'alias', span: span(declared), params: params() }, keyword.start);
+        // A record type's fields, nested records' included
+        statement.forEach(({ token }) => {
+          if (token.kind !== 'accessor') return;
+          const field = { name: token.text.slice(1), kind: 'field' as const, span: fieldSpan(token) };
+          declare({ ...field, container: declared.text }, token.start);
+        });
+        break;
+      case 'constraint':
+        declare({ name: declared.text, kind: 'constraint', span: span(declared) }, keyword.start);
+        members(':', 'method', declared.text);
+        break;
+      case 'implement': {
+        // `Show (List a)`: up to the `given` or the body's `(`
+        let end = statement.length - 1;
+        while (end > 1 && !(statement[end].depth === 0 && isPunctuation(statement[end].token, '('))) end--;
+        const given = statement.findIndex(({ token }) => isKeyword(token, 'given'));
+        if (given > 1 && given < end) end = given;
+        const label = text.slice(declared.start, statement[end].token.start).trim().replace(/\s+/g, ' ');
+        declare({ name: label, kind: 'implementation', span: span(declared) }, keyword.start);
+        members('=', 'method', label);
+        break;
+      }
+    }
+  }
+  return out;
+}
+
+// `{@add addFn, @twice fn x => x * 2}` as the last top-level statement
+function exportedFields(tokens: SyntaxToken[]): IndexedFile['exports'] {
+  let depth = 0;
+  let last = 0;
+  tokens.forEach((t, i) => {
+    if (t.kind === 'punctuation' && '([{'.includes(t.text)) depth++;
+    if (t.kind === 'punctuation' && ')]}'.includes(t.text)) depth--;
+    if (depth === 0 && isPunctuation(t, ';') && i + 1 < tokens.length) last = i + 1;
+  });
+  const statement = tokens.slice(last);
+  if (!isPunctuation(statement[0], '{')) return [];
+  const exports: IndexedFile['exports'] = [];
+  depth = 0;
+  statement.forEach((t, i) => {
+    if (t.kind === 'punctuation' && '([{'.includes(t.text)) depth++;
+    if (t.kind === 'punctuation' && ')]}'.includes(t.text)) depth--;
+    if (depth !== 1 || t.kind !== 'accessor') return;
+    const value = statement[i + 1];
+    const after = statement[i + 2];
+    const plain = value?.kind === 'identifier' && (isPunctuation(after, ',') || isPunctuation(after, '}'));
+    const local = plain ? { local: value.text, localSpan: { start: value.start, end: value.end } } : {};
+    exports.push({ field: t.text.slice(1), fieldSpan: fieldSpan(t), ...local });
+  });
+  return exports;
+}
+
+// What's kept of an entry between sessions (index-cache.ts): not the text,
+// which is read back from disk
+export type SavedIndexEntry = Omit<IndexedFile, 'text'>;
+
+export function createWorkspaceIndex(source: TextSource) {
+  const files = new Map<string, IndexedFile>();
+  // Entries from an earlier session, until their file is next read
+  const restored = new Map<string, SavedIndexEntry>();
+  let roots: string[] = [];
+  // Files found under the roots; rescanned when the roots change
+  let known: Set<string> | undefined;
+
+  function get(filePath: string): IndexedFile | undefined {
+    const current = source(filePath);
+    if (!current) {
+      files.delete(filePath);
+      return undefined;
+    }
+    const cached = files.get(filePath);
+    if (cached && cached.stamp === current.stamp) return cached;
+    const text = current.read();
+    const previous = cached ?? restored.get(filePath);
+    const unchanged = previous && (previous.stamp === current.stamp || previous.hash === contentHash(text));
+    const indexed = unchanged ? { ...previous, text, stamp: current.stamp } : indexText(filePath, text, current.stamp);
+    files.set(filePath, indexed);
+    restored.delete(filePath);
+    return indexed;
+  }
+
+  // Every file under the roots, plus `extra` (open documents outside them)
+  function paths(extra: string[] = []): string[] {
+    known ??= new Set(roots.flatMap(noolangFilesIn));
+    return [...new Set([...known, ...extra])];
+  }
+
+  function all(extra: string[] = []): IndexedFile[] {
+    return paths(extra).flatMap((p) => get(p) ?? []);
+  }
+
+  return {
+    setRoots(folders: string[]) {
+      roots = folders;
+      known = undefined;
+    },
+    // Files and folders the editor created or deleted (workspace/didCreateFiles,
+    // didDeleteFiles), taken in without waiting for a rescan
+    created(paths: string[]) {
+      if (!known) return;
+      for (const p of paths) {
+        const found = fs.statSync(p, { throwIfNoEntry: false })?.isDirectory() ? noolangFilesIn(p) : [p];
+        found.filter((f) => f.endsWith('.noo') && isUnder(f, roots)).forEach((f) => known?.add(f));
+      }
+    },
+    deleted(paths: string[]) {
+      for (const f of [...files.keys()]) if (isUnder(f, paths)) files.delete(f);
+      for (const f of [...restored.keys()]) if (isUnder(f, paths)) restored.delete(f);
+      for (const f of [...(known ?? [])]) if (isUnder(f, paths)) known?.delete(f);
+    },
+    get,
+    all,
+    // How many files are indexed, and the size of their text
+    stats: () => ({
+      files: files.size,
+      textBytes: [...files.values()].reduce((n, f) => n + Buffer.byteLength(f.text), 0),
+    }),
+    // What `all` covers, for going through the workspace a few files at a time
+    paths,
+    // Entries for files under the roots, to save for the next session, and
+    // ones saved by an earlier session (index-cache.ts): `get` keeps those
+    // while their file's stamp or content hash still matches
+    saved: () => [...files.values()].filter((f) => isUnder(f.filePath, roots)),
+    restore(saved: SavedIndexEntry[]) {
+      for (const f of saved) if (!files.has(f.filePath)) restored.set(f.filePath, f);
+    },
+    // Files whose imports resolve to `target`
+    importersOf: (target: string, extra: string[] = []) =>
+      all(extra).filter((f) => f.imports.some((i) => i.target === target)),
+    // Files whose export record has `field`
+    exportersOf: (field: string, extra: string[] = []) =>
+      all(extra).filter((f) => f.exports.some((e) => e.field === field)),
+  };
+}
+
+export type WorkspaceIndex = ReturnType<typeof createWorkspaceIndex>;
+
+const within = (span: Span, offset: number) => span.start <= offset && offset <= span.end;
+
+// The import whose path is at `offset`, or whose module binding (`math` in
+// `math = import "./math"`) is, where it's bound or used
+export function importAt(file: IndexedFile, offset: number): ImportSite | undefined {
+  return file.imports.find(
+    (site) =>
+      within(site.specifierSpan, offset) ||
+      (site.binding !== undefined && (file.identifiers.get(site.binding) ?? []).some((s) => within(s, offset))),
+  );
+}
+
+// The shortest chain of imports from one file to another, both included
+function importPath(index: WorkspaceIndex, from: string, to: string): string[] | undefined {
+  const previous = new Map<string, string>();
+  const queue = [from];
+  const seen = new Set(queue);
+  for (let current = queue.shift(); current !== undefined; current = queue.shift()) {
+    if (current === to) {
+      const chain = [current];
+      for (let p = previous.get(current); p !== undefined; p = previous.get(p)) chain.unshift(p);
+      return chain;
+    }
+    for (const site of index.get(current)?.imports ?? []) {
+      if (!site.target || seen.has(site.target)) continue;
+      seen.add(site.target);
+      previous.set(site.target, current);
+      queue.push(site.target);
+    }
+  }
+  return undefined;
+}
+
+// The imports of `file` that lead back to it, each with its cycle: the files
+// from `file` round to itself again, the shortest way
+export function importCycles(index: WorkspaceIndex, file: IndexedFile): { site: ImportSite; cycle: string[] }[] {
+  return file.imports.flatMap((site) => {
+    const back = site.target && importPath(index, site.target, file.filePath);
+    return back ? [{ site, cycle: [file.filePath, ...back] }] : [];
+  });
+}
+
+export type Deprecation = { note: string; declaration?: Span };
+
+// Names in `file` whose declarations are `@deprecated`: its own, and those it
+// imports from modules that export deprecated ones (by name, or under the
+// local name a `{@field local}` pattern gives). `declaration` is where the
+// file declares the name itself, which isn't a use of it.
+export function deprecatedNames(index: WorkspaceIndex, file: IndexedFile): Map<string, Deprecation> {
+  const names = new Map<string, Deprecation>();
+  for (const d of file.declarations) {
+    // Fields aren't names: their uses are `@field`s
+    if (d.deprecated !== undefined && d.kind !== 'implementation' && d.kind !== 'field') {
+      names.set(d.name, { note: d.deprecated, declaration: d.span });
+    }
+  }
+  for (const site of file.imports) {
+    const module = site.target ? index.get(site.target) : undefined;
+    if (!module) continue;
+    for (const f of site.fields) {
+      const local = module.exports.find((e) => e.field === f.field)?.local;
+      const declared = module.declarations.find((d) => d.name === local && d.deprecated !== undefined);
+      if (declared) names.set(f.local, { note: declared.deprecated ?? '', declaration: f.localSpan });
+    }
+  }
+  return names;
+}
+
+// The `implement` blocks for a constraint in `files`, as their declarations
+// (named `Show (List a)`, on the constraint's name after `implement`); with a
+// `method`, that method's definition in each block instead
+export function implementationsOf(
+  files: IndexedFile[],
+  constraint: string,
+  method?: string,
+): { file: IndexedFile; declaration: Declaration }[] {
+  const implementsIt = (label: string | undefined) => label?.split(' ')[0] === constraint;
+  return files.flatMap((file) =>
+    file.declarations
+      .filter((d) =>
+        method === undefined
+          ? d.kind === 'implementation' && implementsIt(d.name)
+          : d.kind === 'method' && d.name === method && implementsIt(d.container) && d.container !== constraint,
+      )
+      .map((declaration) => ({ file, declaration })),
+  );
+}
@@ -1,9 +1,11 @@
 import * as path from 'path';
-import { workspace, ExtensionContext } from 'vscode';
+import { commands, env, window, workspace, EventEmitter, ExtensionContext, Uri, ViewColumn } from 'vscode';
 import {
 	LanguageClient,
 	TransportKind,
 	Executable,
+	Location as ProtocolLocation,
+	Position as ProtocolPosition,
 } from 'vscode-languageclient/node';
 
 let client: LanguageClient;
@@ -38,20 +40,19 @@ export function activate(context: ExtensionContext) {
 	const fs = require('fs') as typeof import('fs');
 	const srcCli = workspaceFolder ? path.join(workspaceFolder, 'src', 'cli.ts') : '';
 	const distCli = workspaceFolder ? path.join(workspaceFolder, 'dist', 'cli.js') : '';
-	const cliPath = srcCli && fs.existsSync(srcCli) ? srcCli : distCli;
-	// GUI-launched VS Code often lacks the shell PATH; resolve bun directly
-	const bunCandidates = [
-		path.join(process.env.HOME ?? '', '.bun', 'bin', 'bun'),
-		'/opt/homebrew/bin/bun',
-		'/usr/local/bin/bun',
-	];
-	const bunPath = bunCandidates.find(c => fs.existsSync(c)) ?? 'bun';
+	const configuredCli = config.get<string>('cliPath', '');
+	const cliPath = configuredCli || (srcCli && fs.existsSync(srcCli) ? srcCli : distCli);
+	// node, bun or deno; 'auto' leaves the choice to the server, which picks
+	// the first one installed that can run cliPath
+	const runtime = config.get<string>('runtime', 'auto');
 
 	const env = {
 		...process.env,
 		NOOLANG_WORKSPACE: workspaceFolder ?? '',
 		NOOLANG_CLI_PATH: cliPath,
-		NOOLANG_CLI_RUNTIME: cliPath.endsWith('.ts') ? bunPath : 'node',
+		...(runtime !== 'auto' ? { NOOLANG_CLI_RUNTIME: runtime } : {}),
+		// Where the server keeps its workspace index between sessions
+		...(context.storageUri ? { NOOLANG_INDEX_CACHE: context.storageUri.fsPath } : {}),
 	};
 
 	// The debug options for the server
@@ -71,8 +72,28 @@ export function activate(context: ExtensionContext) {
 	// Options to control the language client
 	const clientOptions = {
 		// Register the server for noolang documents
-		documentSelector: [{ scheme: 'file', language: 'noolang' }],
+		// Notebook cells are synced through their notebook
+		documentSelector: [
+			{ scheme: 'file', language: 'noolang' },
+			{ notebook: '*', language: 'noolang' },
+		],
+		initializationOptions: {
+			timeouts: config.get('timeouts'),
+			cache: config.get('cache'),
+			backend: config.get('backend'),
+			display: config.get('display'),
+			telemetry: config.get('telemetry'),
+			inlayHints: config.get('inlayHints'),
+			workspaceSymbols: config.get('workspaceSymbols'),
+			codeLens: config.get('codeLens'),
+			diagnostics: config.get('diagnostics'),
+			largeFiles: config.get('largeFiles'),
+		},
+		// Hovers link to commands (expanding a type's aliases)
+		markdown: { isTrusted: true },
 		synchronize: {
+			// Push `noolang.*` setting changes to the server
+			configurationSection: 'noolang',
 			// Notify the server about file changes to .noo files in the workspace
 			fileEvents: workspace.createFileSystemWatcher('**/*.noo'),
 		},
@@ -89,6 +110,99 @@ export function activate(context: ExtensionContext) {
 	console.log('🚀 Starting LSP client...');
 	client.start();
 	console.log('✅ LSP client started!');
+
+	// Read-only views the server generates (reports, and views of a file
+	// that it renders again as the file changes), under noolang:
+	const virtualDocumentChanged = new EventEmitter<Uri>();
+	client.onNotification('noolang/virtualDocumentChanged', (params: { uri: string }) =>
+		virtualDocumentChanged.fire(Uri.parse(params.uri))
+	);
+	// Opens the server's view of the active file
+	const showView = async (kind: string) => {
+		const document = window.activeTextEditor?.document;
+		if (!document || document.languageId !== 'noolang') return;
+		const result = await client.sendRequest<{ uri: string } | null>('workspace/executeCommand', {
+			command: 'noolang.openView',
+			arguments: [document.uri.toString(), kind],
+		});
+		if (!result) return;
+		await window.showTextDocument(Uri.parse(result.uri), { viewColumn: ViewColumn.Beside, preview: true });
+	};
+	context.subscriptions.push(
+		virtualDocumentChanged,
+		workspace.registerTextDocumentContentProvider('noolang', {
+			onDidChange: virtualDocumentChanged.event,
+			provideTextDocumentContent: async (uri) => {
+				const params = { uri: uri.toString() };
+				return (await client.sendRequest<string | null>('noolang/virtualDocument', params)) ?? '';
+			},
+		}),
+		workspace.onDidCloseTextDocument((document) => {
+			if (document.uri.scheme !== 'noolang') return;
+			void client.sendNotification('noolang/virtualDocumentClosed', { uri: document.uri.toString() });
+		}),
+		commands.registerCommand('noolang.showTopLevelTypes', () => showView('types')),
+		commands.registerCommand('noolang.showTopLevelValues', () => showView('evaluation')),
+		commands.registerCommand('noolang.benchmarkCurrentFile', async () => {
+			const document = window.activeTextEditor?.document;
+			if (!document || document.languageId !== 'noolang') return;
+			await document.save();
+			const result = await client.sendRequest<{ uri: string } | null>('workspace/executeCommand', {
+				command: 'noolang.benchmarkFile',
+				arguments: [document.uri.toString()],
+			});
+			if (result) await window.showTextDocument(Uri.parse(result.uri), { preview: true });
+		}),
+		// A type check of the active file, saved first since the check reads it
+		// from disk; the only one with noolang.diagnostics.trigger at manual
+		commands.registerCommand('noolang.checkFile', async () => {
+			const document = window.activeTextEditor?.document;
+			if (!document || document.languageId !== 'noolang') return;
+			await document.save();
+			await client.sendRequest('workspace/executeCommand', {
+				command: 'noolang.checkDocument',
+				arguments: [document.uri.toString()],
+			});
+		}),
+		// How the selection parses, beside the editor; with none, the whole
+		// file's tree, kept up to date
+		commands.registerCommand('noolang.showSyntaxTree', async () => {
+			const editor = window.activeTextEditor;
+			if (!editor || editor.document.languageId !== 'noolang') return;
+			if (editor.selection.isEmpty) return showView('ast');
+			const result = await client.sendRequest<{ format: string; text: string } | null>('noolang/showAst', {
+				textDocument: { uri: editor.document.uri.toString() },
+				range: client.code2ProtocolConverter.asRange(editor.selection),
+			});
+			if (!result) return;
+			const language = result.format === 'json' ? 'json' : 'plaintext';
+			const document = await workspace.openTextDocument({ content: result.text, language });
+			await window.showTextDocument(document, { viewColumn: ViewColumn.Beside, preview: true });
+		}),
+		// From a constraint's implementations lens: the blocks, listed in place
+		commands.registerCommand(
+			'noolang.showImplementations',
+			(uri: string, position: ProtocolPosition, locations: ProtocolLocation[]) =>
+				commands.executeCommand(
+					'editor.action.showReferences',
+					Uri.parse(uri),
+					client.protocol2CodeConverter.asPosition(position),
+					locations.map((l) => client.protocol2CodeConverter.asLocation(l)),
+				),
+		),
+		// From a hover's "Expand type" link: each step of the expansion, the
+		// chosen one copied
+		commands.registerCommand('noolang.showExpandedType', async (uri: string, type: string) => {
+			const steps = await client.sendRequest<string[] | null>('workspace/executeCommand', {
+				command: 'noolang.expandType',
+				arguments: [uri, type],
+			});
+			if (!steps || steps.length === 0) return;
+			const items = steps.map((label, i) => ({ label, description: i === 0 ? 'abbreviated' : `expanded ${i}` }));
+			const picked = await window.showQuickPick(items, { title: 'Expanded type', matchOnDescription: true });
+			if (picked) await env.clipboard.writeText(picked.label);
+		}),
+	);
 }
 
 export function deactivate(): Thenable<void> | undefined {
@@ -186,6 +186,7 @@ export type Expression =
 	| RecordExpression
 	| TupleExpression
 	| UnitExpression
+	| HoleExpression
 	| AccessorExpression
 	| TypedExpression
 	| ConstrainedExpression
@@ -233,6 +234,8 @@ export interface PipelineExpression {
 	kind: 'pipeline';
 	steps: Expression[];
 	operators: ('|>' | '<|')[]; // Track the operators between steps
+	// The type passed across each operator, in source order (--pipeline-types)
+	stageTypes?: Type[];
 	type?: Type;
 	location: Location;
 }
@@ -266,6 +269,8 @@ export interface BinaryExpression {
 	// non-final bare expressions to be unit-typed; the program's top-level
 	// sequence stays permissive for script/literate-doc display style.
 	parenthesized?: boolean;
+	// For `|` and `|?`: the type of the value piped in (--pipeline-types)
+	inputType?: Type;
 	type?: Type;
 	location: Location;
 }
@@ -367,6 +372,14 @@ export interface UnitExpression {
 	location: Location;
 }
 
+// `?` (or `???`): a placeholder for code not yet written. It checks as
+// whatever its context needs, which --holes reports, and fails if evaluated.
+export interface HoleExpression {
+	kind: 'hole';
+	type?: Type;
+	location: Location;
+}
+
 export interface TypedExpression {
 	kind: 'typed';
 	expression: Expression;
@@ -3,12 +3,16 @@
 import { Lexer } from './lexer/lexer';
 import { parse, preprocessLiterateNoolang } from './parser/parser';
 import { Evaluator } from './evaluator/evaluator';
+import { isString } from './evaluator/evaluator-utils';
+import type { Value } from './evaluator/evaluator-utils';
 import { typeAndDecorate } from './typer/index';
 import { typeToString } from './typer/helpers';
+import { flattenStatements } from './typer/type-operations';
 import * as fs from 'node:fs';
 import * as path from 'node:path';
 import { formatValue } from './format';
 import { colorize } from './colors';
+import { runQuery } from './queries';
 
 function printUsage() {
 	console.log(colorize.section('Usage: noo <file.noo> or noo <file.md>'));
@@ -18,6 +22,7 @@ function printUsage() {
 	console.log(`       ${colorize.command('noo --ast <expr>')}`);
 	console.log(`       ${colorize.command('noo --tokens-file <file>')}`);
 	console.log(`       ${colorize.command('noo --ast-file <file>')}`);
+	console.log(`       ${colorize.command('noo --ast-partial <file>')}`);
 	console.log(`       ${colorize.command('noo --types <expr>')}`);
 	console.log(`       ${colorize.command('noo --types-file <file>')}`);
 	console.log(`       ${colorize.command('noo --types-detailed <expr>')}`);
@@ -27,7 +32,31 @@ function printUsage() {
 	console.log(
 		`       ${colorize.command('noo --symbol-type <file> <symbol>')}`
 	);
+	console.log(
+		`       ${colorize.command('noo --symbol-types <file> [symbol...]')}`
+	);
+	console.log(`       ${colorize.command('noo --expr-type <file> <expr>')}`);
+	console.log(
+		`       ${colorize.command('noo --type-at <file> <line> <column>')}`
+	);
+	console.log(`       ${colorize.command('noo --pipeline-types <file>')}`);
+	console.log(`       ${colorize.command('noo --holes <file>')}`);
+	console.log(
+		`       ${colorize.command('noo --expected-type <file> <line> <column> [<line text>]')}`
+	);
+	console.log(
+		`       ${colorize.command('noo --pattern-type <file> <line> <column> [<line text>]')}`
+	);
+	console.log(`       ${colorize.command('noo --eval-expr <file> <expr>')}`);
 	console.log(`       ${colorize.command('noo --benchmark <file>')}`);
+	console.log(`       ${colorize.command('noo --test-file <file>')}`);
+	console.log(`       ${colorize.command('noo --capabilities')}`);
+	console.log(
+		`       ${colorize.command('noo --json <query>')} (one line of JSON from --types, --types-file, --ast-partial, --symbol-type, --symbol-types, --expr-type, --type-at, --pipeline-types, --holes, --expected-type or --pattern-type)`
+	);
+	console.log(
+		`       ${colorize.command('noo --import-base <dir> <query>')} (the queried file resolves relative imports from <dir>)`
+	);
 	console.log(
 		`       ${colorize.command('noo --verbose <file>')} (or -v; prints the final value and its type, like --eval does)`
 	);
@@ -48,6 +77,7 @@ function printUsage() {
 	);
 	console.log(`  ${colorize.identifier('noo --tokens-file std/math.noo')}`);
 	console.log(`  ${colorize.identifier('noo --ast-file std/math.noo')}`);
+	console.log(`  ${colorize.identifier('noo --ast-partial std/math.noo')}`);
 	console.log(`  ${colorize.identifier('noo --types "fn x => x + 1"')}`);
 	console.log(`  ${colorize.identifier('noo --types-file std/math.noo')}`);
 	console.log(
@@ -61,14 +91,37 @@ function printUsage() {
 	console.log(
 		`  ${colorize.identifier('noo --symbol-type examples/demo.noo factorial')}`
 	);
+	console.log(
+		`  ${colorize.identifier('noo --symbol-types examples/demo.noo factorial map')}`
+	);
+	console.log(
+		`  ${colorize.identifier('noo --expr-type examples/demo.noo "factorial 5"')}`
+	);
 	console.log(
 		`  ${colorize.identifier('noo --benchmark benchmarks/simple.noo')}`
 	);
+	console.log(`  ${colorize.identifier('noo --capabilities')}`);
+	console.log(
+		`  ${colorize.identifier('noo --json --symbol-types examples/demo.noo')}`
+	);
 	console.log('');
 	console.log(colorize.section('Or use the REPL:'));
 	console.log(`  ${colorize.identifier('noo')}`);
 }
 
+// Types and evaluates `source` as if it were the file at `filePath`, so its
+// imports resolve from that file's directory; returns its final value
+function runProgram(source: string, filePath: string): Value {
+	const program = parse(new Lexer(source).tokenize());
+	const { program: decoratedProgram, state } = typeAndDecorate(
+		program,
+		undefined,
+		path.dirname(filePath)
+	);
+	const evaluator = new Evaluator({ traitRegistry: state.traitRegistry });
+	return evaluator.evaluateProgram(decoratedProgram, filePath).finalResult;
+}
+
 async function main() {
 	const args = process.argv.slice(2);
 	if (args.length === 0) {
@@ -107,6 +160,39 @@ async function main() {
 		return;
 	}
 
+	// Check for --test-file flag: run one *.test.noo suite in this process and
+	// print std/test's report for it. The language server's test lenses use
+	// this rather than `noo test`, which runs every suite in child processes.
+	// The suite runs from the entry `noo test` generates for it
+	// (std/test-entry.noo), which exits with its failure status.
+	if (args[0] === '--test-file' && args[1]) {
+		try {
+			const fullPath = path.resolve(args[1]);
+			const suite = `./${path.basename(fullPath)}`;
+			const entry = runProgram(
+				`entry_source = import "std/test-entry";\nentry_source ${JSON.stringify(suite)}`,
+				fullPath
+			);
+			if (!isString(entry)) {
+				throw new Error('std/test-entry did not build an entry program');
+			}
+			runProgram(entry.value, fullPath);
+		} catch (err) {
+			console.error('Error:', (err as Error).message);
+			process.exit(1);
+		}
+		return;
+	}
+
+	// Queries the language server relies on (see queries.ts)
+	const query = runQuery(args);
+	if (query) {
+		process.stdout.write(query.stdout);
+		process.stderr.write(query.stderr);
+		if (query.status !== 0) process.exit(query.status);
+		return;
+	}
+
 	// Check for --tokens flag
 	if (args[0] === '--tokens' && args[1]) {
 		const expr = args[1];
@@ -185,62 +271,6 @@ async function main() {
 		return;
 	}
 
-	// Check for --types flag
-	if (args[0] === '--types' && args[1]) {
-		const expr = args[1];
-		try {
-			const lexer = new Lexer(expr);
-			const tokens = lexer.tokenize();
-			const program = parse(tokens);
-			const { program: decoratedProgram, state } = typeAndDecorate(program);
-			console.log('Types:');
-			decoratedProgram.statements.forEach((stmt, i) => {
-				console.log(
-					`  ${i}: ${
-						stmt.type
-							? typeToString(stmt.type, state.substitution)
-							: '<no type>'
-					}`
-				);
-			});
-		} catch (err) {
-			console.error('Error:', (err as Error).message);
-			process.exit(1);
-		}
-		return;
-	}
-
-	// Check for --types-file flag
-	if (args[0] === '--types-file' && args[1]) {
-		const file = args[1];
-		try {
-			const fullPath = path.resolve(file);
-			const code = fs.readFileSync(fullPath, 'utf8');
-			const lexer = new Lexer(code);
-			const tokens = lexer.tokenize();
-			const program = parse(tokens);
-			const { program: decoratedProgram, state } = typeAndDecorate(
-				program,
-				undefined,
-				path.dirname(fullPath)
-			);
-			console.log('Types:');
-			decoratedProgram.statements.forEach((stmt, i) => {
-				console.log(
-					`  ${i}: ${
-						stmt.type
-							? typeToString(stmt.type, state.substitution)
-							: '<no type>'
-					}`
-				);
-			});
-		} catch (err) {
-			console.error('Error:', (err as Error).message);
-			process.exit(1);
-		}
-		return;
-	}
-
 	// Check for --types-detailed flag
 	if (args[0] === '--types-detailed' && args[1]) {
 		const expr = args[1];
@@ -331,40 +361,64 @@ async function main() {
 		return;
 	}
 
-	// Check for --symbol-type flag
-	if (args[0] === '--symbol-type' && args[1] && args[2]) {
+	// Check for --eval-expr flag: evaluate an expression against the file's
+	// top-level definitions, refusing if the combined program would perform
+	// effects (the LSP runs this for inline results, so it must not write,
+	// log, or otherwise touch the outside world)
+	if (args[0] === '--eval-expr' && args[1] && args[2]) {
 		const file = args[1];
-		const symbol = args[2];
+		const expr = args[2];
 		try {
 			const fullPath = path.resolve(file);
 			const code = fs.readFileSync(fullPath, 'utf8');
-
-			// Create a program that includes the original file content plus the symbol reference
-			const testExpr = `${symbol}`;
-			const combinedCode = `${code}; ${testExpr}`;
+			const combinedCode = `${code}\n;\n(${expr})`;
 
 			const lexer = new Lexer(combinedCode);
 			const tokens = lexer.tokenize();
 			const program = parse(tokens);
-			const { program: decoratedProgram, state } = typeAndDecorate(
-				program,
-				undefined,
-				path.dirname(fullPath)
-			);
+			const {
+				program: decoratedProgram,
+				state,
+				effects,
+			} = typeAndDecorate(program, undefined, path.dirname(fullPath));
+
+			if (effects.size > 0) {
+				console.error(
+					`Error: refusing to evaluate, program performs effects: ${[
+						...effects,
+					]
+						.map(e => `!${e}`)
+						.join(' ')}`
+				);
+				process.exit(2);
+			}
 
-			// Get the type of the last statement (which is our symbol reference)
-			if (decoratedProgram.statements.length > 0) {
-				const lastStmt =
-					decoratedProgram.statements[decoratedProgram.statements.length - 1];
-				if (lastStmt.type) {
-					const typeStr = typeToString(lastStmt.type, state.substitution);
-					console.log(`Symbol '${symbol}' has type: ${typeStr}`);
-				} else {
-					console.log(`Symbol '${symbol}': <no type information>`);
-				}
-			} else {
-				console.log(`Symbol '${symbol}': <not found>`);
+			const evaluator = new Evaluator({ traitRegistry: state.traitRegistry });
+			const result = evaluator.evaluateProgram(decoratedProgram, fullPath);
+			const lastType =
+				decoratedProgram.statements[decoratedProgram.statements.length - 1]
+					?.type;
+			// The file's top-level bindings too, for inline values in the editor
+			const bindings: Record<string, string> = {};
+			for (const statement of decoratedProgram.statements.flatMap(
+				flattenStatements
+			)) {
+				if (
+					statement.kind !== 'definition' &&
+					statement.kind !== 'mutable-definition'
+				)
+					continue;
+				const value = result.environment.get(statement.name);
+				if (value !== undefined) bindings[statement.name] = formatValue(value);
 			}
+			console.log('Result:');
+			console.log(
+				JSON.stringify({
+					value: formatValue(result.finalResult),
+					type: lastType ? typeToString(lastType, state.substitution) : null,
+					bindings,
+				})
+			);
 		} catch (err) {
 			console.error('Error:', (err as Error).message);
 			process.exit(1);
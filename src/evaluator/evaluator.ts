@@ -1758,6 +1758,10 @@ export class Evaluator {
 				// Return unit value
 				return createUnit();
 			}
+			case 'hole': {
+				const { line, column } = expr.location.start;
+				throw new Error(`Reached a hole (?) at ${line}:${column}`);
+			}
 			case 'list': {
 				// Evaluate all elements and return a tagged list value
 				const elements = expr.elements.map(e => {
@@ -2702,6 +2706,7 @@ export class Evaluator {
 			case 'accessor':
 			case 'literal':
 			case 'unit':
+			case 'hole':
 			case 'typed':
 				return false;
 			default:
@@ -2790,6 +2795,8 @@ export class Evaluator {
 					.join(', ')} }`;
 			case 'accessor':
 				return `@${expr.field}${expr.optional ? '?' : ''}`;
+			case 'hole':
+				return '?';
 			case 'where':
 				return `${this.expressionToString(expr.main)} where (${expr.definitions
 					.map(d => this.expressionToString(d))
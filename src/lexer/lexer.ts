@@ -23,6 +23,64 @@ export interface Token {
 	location: Location;
 }
 
+// Words lexed as KEYWORD rather than IDENTIFIER. The language server's
+// syntax.ts highlights and validates names with the same lists.
+export const KEYWORDS: readonly string[] = [
+	'if',
+	'then',
+	'else',
+	'let',
+	'in',
+	'fn',
+	'import',
+	'mut',
+	'mut!',
+	'where',
+	'variant',
+	'type',
+	'match',
+	'with',
+	'given',
+	'is',
+	'has',
+	'implements',
+	'constraint',
+	'implement',
+	// Primitive type names
+	'Float',
+	'Number',
+	'String',
+	'Unit',
+	'List',
+	'Unknown',
+];
+
+// Multi-character operators (must have spaces around them) come first, so the
+// longest operator at a position wins
+export const OPERATORS: readonly string[] = [
+	'|?',
+	'|>',
+	'<|',
+	'&&',
+	'||',
+	'==',
+	'!=',
+	'<=',
+	'>=',
+	'=>',
+	'->',
+	'+',
+	'-',
+	'*',
+	'%',
+	'/',
+	'<',
+	'>',
+	'=',
+	'|',
+	'$',
+];
+
 // Template literals switch the lexer between two modes: inside `...` raw
 // text is read until a hole or the closing backtick; inside a ${...} hole
 // tokens lex normally, with brace depth tracked so record/tuple braces in
@@ -172,36 +230,7 @@ export class Lexer {
 		}
 
 		// Check if it's a keyword
-		const keywords = [
-			'if',
-			'then',
-			'else',
-			'let',
-			'in',
-			'fn',
-			'import',
-			'mut',
-			'mut!',
-			'where',
-			'variant',
-			'type',
-			'match',
-			'with',
-			'given',
-			'is',
-			'has',
-			'implements',
-			'constraint',
-			'implement',
-			// Primitive type names
-			'Float',
-			'Number',
-			'String',
-			'Unit',
-			'List',
-			'Unknown',
-		];
-		const type = keywords.includes(value) ? 'KEYWORD' : 'IDENTIFIER';
+		const type = KEYWORDS.includes(value) ? 'KEYWORD' : 'IDENTIFIER';
 
 		return {
 			type,
@@ -214,33 +243,8 @@ export class Lexer {
 		const start = this.createPosition();
 		let value = '';
 
-		// Multi-character operators (must have spaces around them)
-		const operators = [
-			'|?',
-			'|>',
-			'<|',
-			'&&',
-			'||',
-			'==',
-			'!=',
-			'<=',
-			'>=',
-			'=>',
-			'->',
-			'+',
-			'-',
-			'*',
-			'%',
-			'/',
-			'<',
-			'>',
-			'=',
-			'|',
-			'$',
-		];
-
 		// Try to match multi-character operators first
-		for (const op of operators) {
+		for (const op of OPERATORS) {
 			if (
 				this.input.substring(this.position, this.position + op.length) === op
 			) {
@@ -471,6 +475,20 @@ export class Lexer {
 			return this.readPunctuation();
 		}
 
+		// A typed hole: `?`, or a run like `???`, as one token
+		if (char === '?') {
+			const start = this.createPosition();
+			let value = '';
+			while (!this.isEOF() && this.peek() === '?') {
+				value += this.advance();
+			}
+			return {
+				type: 'PUNCTUATION',
+				value,
+				location: this.createLocation(start),
+			};
+		}
+
 		// Handle accessors
 		if (char === '@') {
 			return this.readAccessor();
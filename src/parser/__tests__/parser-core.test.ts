@@ -524,4 +524,14 @@ describe('Parser', () => {
 		const seq = program.statements[0];
 		assertBinaryExpression(seq);
 	});
+
+	test('should parse a typed hole, `?` or `???`, as an operand', () => {
+		const program = parse(new Lexer('f ? ???').tokenize());
+		const app = program.statements[0];
+		assertApplicationExpression(app);
+		assertApplicationExpression(app.func);
+		expect(app.func.args[0].kind).toBe('hole');
+		expect(app.args[0].kind).toBe('hole');
+		expect(app.args[0].location.start.column).toBe(5);
+	});
 });
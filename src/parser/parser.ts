@@ -25,6 +25,7 @@ import {
 	type Pattern,
 	type MatchCase,
 	type UnitExpression,
+	type HoleExpression,
 	type RecordExpression,
 	type TupleExpression,
 	type ConstraintDefinitionExpression,
@@ -167,6 +168,20 @@ const parseAccessor = C.map(
 	})
 );
 
+// --- Typed Hole (`?`, `???`) ---
+const parseHole: C.Parser<HoleExpression> = tokens =>
+	/^\?+$/.test(tokens[0].value)
+		? {
+				success: true,
+				value: { kind: 'hole', location: tokens[0].location },
+				remaining: tokens.slice(1),
+			}
+		: {
+				success: false,
+				error: `Unexpected punctuation: ${tokens[0].value}`,
+				position: tokens[0].location.start.line,
+			};
+
 // --- Record Parsing ---
 const parseRecordFieldName = C.map(
 	C.accessor(),
@@ -794,11 +809,7 @@ const parsePrimary: C.Parser<Expression> = tokens => {
 			} else if (firstToken.value === '(') {
 				return parseParenExpr(tokens);
 			} else {
-				return {
-					success: false,
-					error: `Unexpected punctuation: ${firstToken.value}`,
-					position: firstToken.location.start.line,
-				};
+				return parseHole(tokens);
 			}
 		case 'KEYWORD':
 			if (firstToken.value === 'fn') {
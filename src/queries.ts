@@ -0,0 +1,754 @@
+// Queries the language server makes of the compiler, answered as the text
+// the CLI prints for the same flags. The CLI forwards these flags here, and
+// the LSP can call runQuery in-process instead of spawning a CLI per request.
+// Nothing here evaluates code, so a query never prints or performs effects.
+//
+// With a leading --json, each query instead prints one JSON document on a
+// single line, so clients deserialize rather than scrape the text. Errors
+// are reported the same way in both modes, on stderr; with --json, stdout
+// has them too, as { error: { message, detail? } } with the typer's
+// structured detail for errors that have one (a non-exhaustive match).
+//
+// A leading --import-base <dir> has the queried file resolve its relative
+// imports from <dir> instead of its own directory, for a file a client wrote
+// elsewhere on behalf of another (a notebook's combined cells).
+import { KEYWORDS, Lexer, OPERATORS } from './lexer/lexer';
+import type { Token } from './lexer/lexer';
+import { parse } from './parser/parser';
+import { typeAndDecorate, typeVariableExpr } from './typer/index';
+import { typeErrorDetail } from './typer/type-errors';
+import { typeToString } from './typer/helpers';
+import {
+	createTypeState,
+	flattenStatements,
+	instantiate,
+} from './typer/type-operations';
+import { substitute } from './typer/substitute';
+import { unify } from './typer/unify';
+import type {
+	TypeEnvironment,
+	TypeScheme,
+	TypeState,
+	VariableUse,
+} from './typer/types';
+import { createLocation, typeVariable } from './ast';
+import type {
+	BinaryExpression,
+	Expression,
+	Program,
+	Type,
+	VariableExpression,
+} from './ast';
+import * as fs from 'node:fs';
+import * as path from 'node:path';
+
+export type QueryResult = { stdout: string; stderr: string; status: number };
+
+// A query's answer in both output modes
+type Answer = { text: string[]; json: unknown };
+
+// The --import-base of the query being answered, if it gave one
+let importBase: string | undefined;
+
+const importDir = (fullPath: string) => importBase ?? path.dirname(fullPath);
+
+const typeFile = (fullPath: string, code: string, initial?: TypeState) =>
+	typeAndDecorate(
+		parse(new Lexer(code).tokenize()),
+		initial,
+		importDir(fullPath)
+	);
+
+// JSON: { types: (string | null)[] }, one per top-level statement
+const statementTypes = ({
+	program,
+	state,
+}: ReturnType<typeof typeAndDecorate>): Answer => {
+	const types = program.statements.map(stmt =>
+		stmt.type ? typeToString(stmt.type, state.substitution) : null
+	);
+	return {
+		text: ['Types:', ...types.map((t, i) => `  ${i}: ${t ?? '<no type>'}`)],
+		json: { types },
+	};
+};
+
+// --types <expr>
+const types = (expr: string) =>
+	statementTypes(typeAndDecorate(parse(new Lexer(expr).tokenize())));
+
+// --types-file <file>
+const typesFile = (file: string) => {
+	const fullPath = path.resolve(file);
+	return statementTypes(typeFile(fullPath, fs.readFileSync(fullPath, 'utf8')));
+};
+
+// --ast-partial <file>: like --ast-file, but a file that doesn't parse still
+// yields the top-level statements that do, plus the errors (so navigation
+// survives a syntax error elsewhere in the file).
+// JSON: the program, plus errors: { message: string }[]
+const astPartial = (file: string): Answer => {
+	const code = fs.readFileSync(path.resolve(file), 'utf8');
+	const tokens = new Lexer(code).tokenize();
+	const errors: { message: string }[] = [];
+	let program: Program;
+	try {
+		program = parse(tokens);
+	} catch (err) {
+		errors.push({ message: (err as Error).message });
+		program = {
+			statements: splitTopLevel(tokens).flatMap(segment => {
+				try {
+					return parse(segment).statements;
+				} catch {
+					return [];
+				}
+			}),
+			location: createLocation({ line: 1, column: 1 }, { line: 1, column: 1 }),
+		};
+	}
+	const ast = { ...program, errors };
+	return { text: ['AST:', JSON.stringify(ast, null, 2)], json: ast };
+};
+
+// --symbol-type <file> <symbol>
+// JSON: { symbol: string, type: string | null }
+const symbolType = (file: string, symbol: string): Answer => {
+	const fullPath = path.resolve(file);
+	const code = fs.readFileSync(fullPath, 'utf8');
+
+	// Type the file with the symbol reference appended as the last statement
+	const { program, state } = typeFile(fullPath, `${code}; ${symbol}`);
+	const lastStmt = program.statements[program.statements.length - 1];
+	if (!lastStmt?.type) {
+		return {
+			text: [
+				lastStmt
+					? `Symbol '${symbol}': <no type information>`
+					: `Symbol '${symbol}': <not found>`,
+			],
+			json: { symbol, type: null },
+		};
+	}
+	const typeStr = typeToString(lastStmt.type, state.substitution);
+	return {
+		text: [`Symbol '${symbol}' has type: ${typeStr}`],
+		json: { symbol, type: typeStr },
+	};
+};
+
+// --symbol-types <file> [symbol...]: types for many symbols from a single
+// type check of the file, as JSON. With no symbols listed, reports every
+// top-level definition. Names that don't resolve map to null.
+// JSON: { types: Record<string, string | null> }
+const symbolTypes = (file: string, requested: string[]): Answer => {
+	const fullPath = path.resolve(file);
+	const code = fs.readFileSync(fullPath, 'utf8');
+	const program = parse(new Lexer(code).tokenize());
+	const { state } = typeAndDecorate(program, undefined, importDir(fullPath));
+
+	const symbols =
+		requested.length > 0
+			? requested
+			: program.statements
+					.flatMap(flattenStatements)
+					.flatMap(stmt =>
+						stmt.kind === 'definition' || stmt.kind === 'mutable-definition'
+							? [stmt.name]
+							: []
+					);
+
+	const result: Record<string, string | null> = {};
+	for (const symbol of symbols) {
+		try {
+			const typed = typeVariableExpr(
+				{ kind: 'variable', name: symbol, location: program.location },
+				state
+			);
+			result[symbol] = typeToString(typed.type, typed.state.substitution);
+		} catch {
+			result[symbol] = null;
+		}
+	}
+	return {
+		text: ['Symbol types:', JSON.stringify(result)],
+		json: { types: result },
+	};
+};
+
+// --expr-type <file> <expr>: type an arbitrary expression against the file's
+// top-level definitions (used by the LSP for selection types).
+// JSON: { type: string | null }
+const exprType = (file: string, expr: string): Answer => {
+	const fullPath = path.resolve(file);
+	const code = fs.readFileSync(fullPath, 'utf8');
+
+	// The newline keeps a trailing `#` comment in the file from swallowing
+	// the appended expression; extra `;`s are skipped by the parser
+	const { program, state } = typeFile(fullPath, `${code}\n;\n(${expr})`);
+	const lastStmt = program.statements[program.statements.length - 1];
+	if (!lastStmt?.type) {
+		return {
+			text: ['Expression: <no type information>'],
+			json: { type: null },
+		};
+	}
+	const typeStr = typeToString(lastStmt.type, state.substitution);
+	return { text: [`Expression has type: ${typeStr}`], json: { type: typeStr } };
+};
+
+// The innermost variable reference spanning a 1-based line and column
+const variableAt = (
+	node: unknown,
+	line: number,
+	column: number
+): VariableExpression | undefined => {
+	if (!node || typeof node !== 'object') return undefined;
+	if (Array.isArray(node)) {
+		for (const child of node) {
+			const found = variableAt(child, line, column);
+			if (found) return found;
+		}
+		return undefined;
+	}
+	const expr = node as Partial<VariableExpression>;
+	const { start, end } = expr.location ?? {};
+	if (expr.kind === 'variable' && start && end) {
+		const afterStart =
+			line > start.line || (line === start.line && column >= start.column);
+		const beforeEnd =
+			line < end.line || (line === end.line && column <= end.column);
+		return afterStart && beforeEnd ? (expr as VariableExpression) : undefined;
+	}
+	// Annotations hold types, whose type variables look like references
+	for (const [key, child] of Object.entries(node)) {
+		if (key === 'type' || key === 'location') continue;
+		const found = variableAt(child, line, column);
+		if (found) return found;
+	}
+	return undefined;
+};
+
+// A use that pins a constrained variable to a concrete type has had the
+// constraint checked; only constraints still on variables describe it
+const openConstraints = (type: Type, substitution: Map<string, Type>): Type =>
+	type.kind === 'function'
+		? {
+				...type,
+				constraints: type.constraints?.filter(
+					c =>
+						substitute(typeVariable(c.typeVar), substitution).kind ===
+						'variable'
+				),
+				return: openConstraints(type.return, substitution),
+			}
+		: type;
+
+type Resolution = { trait: string; type: string };
+
+// The implementations a use relies on: the one a trait function dispatched
+// to, and one per constraint the use pinned to a concrete type
+const resolutions = (
+	use: VariableUse,
+	substitution: Map<string, Type>
+): Resolution[] => {
+	const { implementation } = use;
+	const result: Resolution[] = implementation
+		? [{ trait: implementation.trait, type: implementation.typeName }]
+		: [];
+	for (let type = use.type; type.kind === 'function'; type = type.return) {
+		for (const c of type.constraints ?? []) {
+			if (c.kind !== 'implements') continue;
+			const pinned = substitute(typeVariable(c.typeVar), substitution);
+			if (pinned.kind === 'variable') continue;
+			const resolution = {
+				trait: c.interfaceName,
+				type: typeToString(pinned, substitution),
+			};
+			if (
+				!result.some(
+					r => r.trait === resolution.trait && r.type === resolution.type
+				)
+			) {
+				result.push(resolution);
+			}
+		}
+	}
+	return result;
+};
+
+// --type-at <file> <line> <column>: the type a reference to a binding has
+// where it's used, with the binding's type variables instantiated (so `map`
+// in `map toString [1]` is `(Float -> String) -> List Float -> List String`),
+// and the implementations that satisfied its constraints there
+// JSON: { name: string | null, type: string | null,
+//         resolutions: { trait: string, type: string }[] }
+const typeAt = (file: string, line: number, column: number): Answer => {
+	const fullPath = path.resolve(file);
+	const uses = new Map<VariableExpression, VariableUse>();
+	const { program, state } = typeFile(
+		fullPath,
+		fs.readFileSync(fullPath, 'utf8'),
+		{ ...createTypeState(), uses }
+	);
+	const found = variableAt(program.statements, line, column);
+	const use = found && uses.get(found);
+	const type = use
+		? typeToString(
+				openConstraints(use.type, state.substitution),
+				state.substitution
+			)
+		: null;
+	const resolved = use ? resolutions(use, state.substitution) : [];
+	return {
+		text: [
+			found
+				? `Type of '${found.name}' here: ${type ?? '<no type information>'}`
+				: 'Type here: <no reference at this position>',
+			...resolved.map(r => `  ${r.trait} ${r.type}`),
+		],
+		json: { name: found?.name ?? null, type, resolutions: resolved },
+	};
+};
+
+type PipelineTypes = {
+	line: number;
+	column: number;
+	types: (string | null)[];
+};
+
+const isThrush = (node: unknown): node is BinaryExpression =>
+	(node as Expression | undefined)?.kind === 'binary' &&
+	['|', '|?'].includes((node as BinaryExpression).operator);
+
+// --pipeline-types <file>: for each pipeline in the file (`x | f | g`, or
+// `f |> g` composing functions), the type passed across each of its
+// operators, left to right; `line` and `column` are where it starts. One
+// type check answers for the whole file.
+// JSON: { pipelines: { line: number, column: number,
+//                     types: (string | null)[] }[] }
+const pipelineTypes = (file: string): Answer => {
+	const fullPath = path.resolve(file);
+	const { program, state } = typeFile(
+		fullPath,
+		fs.readFileSync(fullPath, 'utf8')
+	);
+	const show = (type?: Type) =>
+		type ? typeToString(type, state.substitution) : null;
+	const pipelines: PipelineTypes[] = [];
+	const visit = (node: unknown): void => {
+		if (!node || typeof node !== 'object') return;
+		if (Array.isArray(node)) return node.forEach(visit);
+		const expr = node as Expression;
+		if (isThrush(expr)) {
+			// `x | f | g` is `(x | f) | g`: down the left to `x`
+			const stages: BinaryExpression[] = [];
+			let first: Expression = expr;
+			while (isThrush(first)) {
+				stages.unshift(first);
+				first = first.left;
+			}
+			const { line, column } = first.location.start;
+			const types = stages.map(s => show(s.inputType));
+			pipelines.push({ line, column, types });
+			return [first, ...stages.map(s => s.right)].forEach(visit);
+		}
+		if (expr.kind === 'pipeline') {
+			const { line, column } = expr.steps[0].location.start;
+			const types = expr.operators.map((_, i) =>
+				show(expr.stageTypes?.[i])
+			);
+			pipelines.push({ line, column, types });
+		}
+		for (const [key, child] of Object.entries(node)) {
+			if (key !== 'type' && key !== 'location') visit(child);
+		}
+	};
+	visit(program.statements);
+	return {
+		text: [
+			'Pipeline types:',
+			...pipelines.map(
+				p =>
+					`  ${p.line}:${p.column} ${p.types.map(t => t ?? '?').join(' | ')}`
+			),
+		],
+		json: { pipelines },
+	};
+};
+
+type Hole = {
+	line: number;
+	column: number;
+	type: string | null;
+	candidates: string[];
+};
+
+// What's left of a function type once `count` arguments are applied
+const appliedTo = (type: Type, count: number): Type | undefined => {
+	if (count === 0) return type;
+	if (type.kind !== 'function') return undefined;
+	if (count < type.params.length) {
+		return { ...type, params: type.params.slice(count) };
+	}
+	return appliedTo(type.return, count - type.params.length);
+};
+
+// Whether a binding, applied to `count` arguments, gives a value that can
+// stand where `expected` is needed. A value whose type is still open would
+// fit anything, so it doesn't count. Each try unifies against its own copy
+// of the solution.
+const fits = (
+	scheme: TypeScheme,
+	expected: Type,
+	state: TypeState,
+	count = 0
+): boolean => {
+	const trial = {
+		...state,
+		substitution: new Map(state.substitution),
+		constraints: new Map(state.constraints),
+	};
+	try {
+		const [type, next] = instantiate(scheme, trial);
+		const result = appliedTo(substitute(type, next.substitution), count);
+		if (!result || substitute(result, next.substitution).kind === 'variable') {
+			return false;
+		}
+		unify(result, expected, next);
+		return true;
+	} catch {
+		return false;
+	}
+};
+
+// The bindings in `environment`, innermost first, that can be used in a
+// hole of the `expected` type: the name alone, or (`templates`) applied to
+// holes for up to three arguments, like `map ? ?`. A hole whose type is
+// still open takes anything, so it gets no suggestions.
+const fittingBindings = (
+	expected: Type,
+	environment: TypeEnvironment,
+	state: TypeState
+): { candidates: string[]; templates: string[] } => {
+	const candidates: string[] = [];
+	const templates: string[] = [];
+	if (expected.kind === 'variable') return { candidates, templates };
+	for (const [name, scheme] of [...environment].reverse()) {
+		if (!/^[A-Za-z]\w*$/.test(name)) continue;
+		if (fits(scheme, expected, state)) {
+			candidates.push(name);
+			continue;
+		}
+		const count = [1, 2, 3].find(n => fits(scheme, expected, state, n));
+		if (count) templates.push([name, ...Array(count).fill('?')].join(' '));
+	}
+	return { candidates, templates };
+};
+
+// Types the code with every hole in it recorded, with the environment in
+// scope at each. A function is typed with only the outer bindings its body
+// names, so the file's top-level bindings are layered under a hole's own.
+const typeHoles = (fullPath: string, code: string) => {
+	const initial = { ...createTypeState(), holes: [] };
+	const { state } = typeFile(fullPath, code, initial);
+	return initial.holes.map(({ expr, environment }) => {
+		const expected = expr.type && substitute(expr.type, state.substitution);
+		const outer = [...state.environment].filter(([n]) => !environment.has(n));
+		const inScope = new Map([...outer, ...environment]);
+		return { expr, expected, environment: inScope, state };
+	});
+};
+
+const showType = (type: Type | undefined, state: TypeState) =>
+	type ? typeToString(type, state.substitution) : null;
+
+// --holes <file>: each typed hole (`?`) in the file, with the type its
+// context expects there and the bindings in scope that have that type
+// JSON: { holes: { line: number, column: number, type: string | null,
+//                 candidates: string[] }[] }
+const holes = (file: string): Answer => {
+	const fullPath = path.resolve(file);
+	const code = fs.readFileSync(fullPath, 'utf8');
+	const found: Hole[] = typeHoles(fullPath, code).map(
+		({ expr, expected, environment, state }) => ({
+			...expr.location.start,
+			type: showType(expected, state),
+			candidates: expected
+				? fittingBindings(expected, environment, state).candidates
+				: [],
+		})
+	);
+	return {
+		text: [
+			'Holes:',
+			...found.flatMap(h => [
+				`  ${h.line}:${h.column} ${h.type ?? '<no type information>'}`,
+				...(h.candidates.length
+					? [`    fits: ${h.candidates.join(', ')}`]
+					: []),
+			]),
+		],
+		json: { holes: found },
+	};
+};
+
+// --expected-type <file> <line> <column> [<line text>]: the type expected
+// at a position, for completion there: the file is typed with a hole in
+// place of the word (or hole) being typed at the position, or inserted at
+// it. `line text`, when given, stands in for that line of the file, which
+// an editor may not have saved yet. Also the bindings that fit there, and
+// (`templates`) those that fit once applied to holes.
+// JSON: { type: string | null, candidates: string[], templates: string[] }
+const expectedType = (
+	file: string,
+	line: number,
+	column: number,
+	lineText?: string
+): Answer => {
+	const fullPath = path.resolve(file);
+	const lines = fs.readFileSync(fullPath, 'utf8').split('\n');
+	const text = lineText ?? lines[line - 1] ?? '';
+	const before = text.slice(0, column - 1).replace(/(\?+|\w+)$/, '');
+	const after = text.slice(column - 1).replace(/^(\?+|\w+)/, '');
+	lines[line - 1] = `${before}?${after}`;
+	const hole = typeHoles(fullPath, lines.join('\n')).find(
+		({ expr }) =>
+			expr.location.start.line === line &&
+			expr.location.start.column === before.length + 1
+	);
+	const type = hole ? showType(hole.expected, hole.state) : null;
+	const { candidates, templates } = hole?.expected
+		? fittingBindings(hole.expected, hole.environment, hole.state)
+		: { candidates: [], templates: [] };
+	return {
+		text: [
+			`Expected type: ${type ?? '<no type information>'}`,
+			...(candidates.length ? [`  fits: ${candidates.join(', ')}`] : []),
+			...(templates.length ? [`  applied: ${templates.join(', ')}`] : []),
+		],
+		json: { type, candidates, templates },
+	};
+};
+
+type PatternConstructor = { name: string; arity: number };
+
+const within = (
+	{ start, end }: Expression['location'],
+	line: number,
+	column: number
+) =>
+	(line > start.line || (line === start.line && column >= start.column)) &&
+	(line < end.line || (line === end.line && column <= end.column));
+
+// The scrutinee of the innermost match whose text contains the position,
+// typed in `code`; undefined when there's none, or `code` doesn't parse
+const scrutineeAt = (
+	fullPath: string,
+	code: string,
+	line: number,
+	column: number
+) => {
+	const initial: TypeState = { ...createTypeState(), scrutinees: [] };
+	let final: TypeState | undefined;
+	try {
+		final = typeFile(fullPath, code, initial).state;
+	} catch {
+		// A type error later on (a match missing the case being written, say)
+		// leaves the scrutinees recorded before it
+	}
+	const containing = (initial.scrutinees ?? []).filter(({ expr }) =>
+		within(expr.location, line, column)
+	);
+	const found = containing[containing.length - 1];
+	// The whole file's substitution knows most about the scrutinee's type
+	return found && { ...found, state: final ?? found.state };
+};
+
+// --pattern-type <file> <line> <column> [<line text>]: the type of the value
+// matched by the match whose cases the position is in, and the constructors
+// a pattern there can use, for completion. Typed with a `_ => ?;` case at
+// the position, for a pattern still being written (on the line as `line
+// text` has it, when given), or failing that the file as it is.
+// JSON: { type: string | null,
+//         constructors: { name: string, arity: number }[] }
+const patternType = (
+	file: string,
+	line: number,
+	column: number,
+	lineText?: string
+): Answer => {
+	const fullPath = path.resolve(file);
+	const code = fs.readFileSync(fullPath, 'utf8');
+	const lines = code.split('\n');
+	const text = lineText ?? lines[line - 1] ?? '';
+	const before = text.slice(0, column - 1).replace(/\w+$/, '');
+	lines[line - 1] = `${before}_ => ?;${text.slice(column - 1)}`;
+	const found =
+		scrutineeAt(fullPath, lines.join('\n'), line, before.length + 1) ??
+		scrutineeAt(fullPath, code, line, column);
+	const resolved = found && substitute(found.type, found.state.substitution);
+	const adt =
+		resolved?.kind === 'variant'
+			? found?.state.adtRegistry.get(resolved.name)
+			: undefined;
+	const constructors: PatternConstructor[] = [
+		...(adt?.constructors ?? []),
+	].map(([name, args]) => ({ name, arity: args.length }));
+	const type = found ? showType(resolved, found.state) : null;
+	return {
+		text: [
+			`Pattern type: ${type ?? '<no type information>'}`,
+			...constructors.map(c => `  ${c.name} ${c.arity}`),
+		],
+		json: { type, constructors },
+	};
+};
+
+// Every flag the CLI answers. Clients check this list (--capabilities)
+// before relying on a flag, rather than failing on an older CLI.
+export const CLI_FLAGS = [
+	'--eval',
+	'--tokens',
+	'--tokens-file',
+	'--ast',
+	'--ast-file',
+	'--ast-partial',
+	'--types',
+	'--types-file',
+	'--types-detailed',
+	'--types-env',
+	'--type-ast',
+	'--type-ast-file',
+	'--symbol-type',
+	'--symbol-types',
+	'--expr-type',
+	'--type-at',
+	'--pipeline-types',
+	'--holes',
+	'--expected-type',
+	'--pattern-type',
+	'--eval-expr',
+	'--benchmark',
+	'--test-file',
+	'--verbose',
+	'--capabilities',
+	'--json',
+	'--import-base',
+];
+
+// --capabilities: the CLI's version, the flags it supports and its lexer's
+// keywords and operators (which the language server lexes with), as JSON
+// (in both modes)
+const capabilities = (): Answer => {
+	let version: string | null = null;
+	try {
+		const pkg = path.join(__dirname, '..', 'package.json');
+		version = JSON.parse(fs.readFileSync(pkg, 'utf8')).version ?? null;
+	} catch {
+		// Running outside a package checkout; the flag list is what matters
+	}
+	const json = { version, flags: CLI_FLAGS, keywords: KEYWORDS, operators: OPERATORS };
+	return { text: ['Capabilities:', JSON.stringify(json)], json };
+};
+
+// Returns undefined when the arguments aren't a query, so the CLI can go on
+// to its other modes.
+export const runQuery = (args: string[]): QueryResult | undefined => {
+	const based = args[0] === '--import-base';
+	const base = based ? args[1] : undefined;
+	const options = based ? args.slice(2) : args;
+	const json = options[0] === '--json';
+	const rest = json ? options.slice(1) : options;
+	const [flag, first, second] = rest;
+	const run = (query: () => Answer): QueryResult => {
+		importBase = base && path.resolve(base);
+		try {
+			const answer = query();
+			const lines = json ? [JSON.stringify(answer.json)] : answer.text;
+			return {
+				stdout: lines.map(line => `${line}\n`).join(''),
+				stderr: '',
+				status: 0,
+			};
+		} catch (err) {
+			const message = (err as Error).message;
+			const error = { message, detail: typeErrorDetail(err) };
+			return {
+				stdout: json ? `${JSON.stringify({ error })}\n` : '',
+				stderr: `Error: ${message}\n`,
+				status: 1,
+			};
+		} finally {
+			importBase = undefined;
+		}
+	};
+	if (flag === '--capabilities') return run(capabilities);
+	if (!first) return undefined;
+	switch (flag) {
+		case '--types':
+			return run(() => types(first));
+		case '--types-file':
+			return run(() => typesFile(first));
+		case '--ast-partial':
+			return run(() => astPartial(first));
+		case '--symbol-types':
+			return run(() => symbolTypes(first, rest.slice(2)));
+		case '--symbol-type':
+			return second ? run(() => symbolType(first, second)) : undefined;
+		case '--expr-type':
+			return second ? run(() => exprType(first, second)) : undefined;
+		case '--pipeline-types':
+			return run(() => pipelineTypes(first));
+		case '--holes':
+			return run(() => holes(first));
+		case '--expected-type': {
+			const [line, column] = rest.slice(2).map(Number);
+			return line > 0 && column > 0
+				? run(() => expectedType(first, line, column, rest[4]))
+				: undefined;
+		}
+		case '--pattern-type': {
+			const [line, column] = rest.slice(2).map(Number);
+			return line > 0 && column > 0
+				? run(() => patternType(first, line, column, rest[4]))
+				: undefined;
+		}
+		case '--type-at': {
+			const [line, column] = rest.slice(2).map(Number);
+			return line > 0 && column > 0
+				? run(() => typeAt(first, line, column))
+				: undefined;
+		}
+		default:
+			return undefined;
+	}
+};
+
+// Splits a token stream at `;` outside brackets into separately parseable
+// segments, each ending in its own EOF token. Broken code often has an
+// unclosed bracket, so a `;` followed by a token in column 1 on a later line
+// is taken as a top-level boundary too, whatever the bracket depth.
+function splitTopLevel(tokens: Token[]): Token[][] {
+	const eof = tokens[tokens.length - 1];
+	const segments: Token[][] = [];
+	let current: Token[] = [];
+	let depth = 0;
+	tokens.slice(0, -1).forEach((token, i) => {
+		const punct = token.type === 'PUNCTUATION' ? token.value : '';
+		if (punct && '([{'.includes(punct)) depth++;
+		if (punct && ')]}'.includes(punct)) depth--;
+		const next = tokens[i + 1].location.start;
+		const startsLine =
+			next.line > token.location.end.line && next.column === 1;
+		if (punct === ';' && (depth <= 0 || startsLine)) {
+			if (current.length > 0) segments.push([...current, eof]);
+			current = [];
+			depth = 0;
+		} else {
+			current.push(token);
+		}
+	});
+	if (current.length > 0) segments.push([...current, eof]);
+	return segments;
+}
@@ -1,5 +1,6 @@
 import { test, expect } from 'bun:test';
 import { parseAndType, runCode } from '../../../test/utils';
+import { typeErrorDetail } from '../type-errors';
 
 // A match on a concrete variant must cover every constructor or have a
 // catch-all; otherwise it is a type error.
@@ -22,6 +23,21 @@ test('non-exhaustive custom variant reports every missing constructor', () => {
 	).toThrow(/missing cases G, B/);
 });
 
+test('the error carries the type and missing constructors for tools', () => {
+	let thrown: unknown;
+	try {
+		parseAndType('variant C = R | G | B;\nmatch R (R => 1)');
+	} catch (err) {
+		thrown = err;
+	}
+	expect(typeErrorDetail(thrown)).toEqual({
+		kind: 'non-exhaustive-match',
+		typeName: 'C',
+		missing: ['G', 'B'],
+		location: { line: 2, column: 1 },
+	});
+});
+
 test('non-exhaustive Bool match is rejected', () => {
 	expect(() => parseAndType('fn b => match b (True => 1)')).toThrow(
 		/missing case False/
@@ -0,0 +1,33 @@
+import { test, expect } from 'bun:test';
+import { parseAndType } from '../../../test/utils';
+import { typeErrorDetail } from '../type-errors';
+
+// Applying a function to more arguments than it takes is reported at the
+// first argument too many, rather than as applying a non-function.
+
+test('an argument too many names the function and what it takes', () => {
+	expect(() => parseAndType('plus = fn a b => a + b;\nplus 1 2 3')).toThrow(
+		/Too many arguments: plus takes 2 arguments, and its result \(Float\)/
+	);
+});
+
+test('the error carries the extra argument and the call for tools', () => {
+	let thrown: unknown;
+	try {
+		parseAndType('plus = fn a b => a + b;\nplus 1 2 3');
+	} catch (err) {
+		thrown = err;
+	}
+	expect(typeErrorDetail(thrown)).toEqual({
+		kind: 'over-application',
+		callee: 'plus',
+		arity: 2,
+		location: { line: 2, column: 10 },
+		end: { line: 2, column: 11 },
+		call: { line: 2, column: 1 },
+	});
+});
+
+test('applying what was never a function keeps the general error', () => {
+	expect(() => parseAndType('5 3')).toThrow(/Cannot apply non-function type/);
+});
@@ -11,6 +11,7 @@ import {
 	typeRecord,
 	typeTuple,
 	typeAccessor,
+	typeHole,
 	typeDefinition,
 	typeUserDefinedType,
 	typeTupleDestructuring,
@@ -36,8 +37,11 @@ export const typeExpression = (
 		case 'literal':
 			return typeLiteral(expr, state);
 
-		case 'variable':
-			return typeVariableExpr(expr, state);
+		case 'variable': {
+			const result = typeVariableExpr(expr, state);
+			state.uses?.set(expr, { type: result.type });
+			return result;
+		}
 
 		case 'function':
 			return typeFunction(expr, state);
@@ -86,6 +90,9 @@ export const typeExpression = (
 			// type (normalized in unify), so no polymorphism is needed here
 			return createPureTypeResult(unitType(), state);
 
+		case 'hole':
+			return typeHole(expr, state);
+
 		case 'type-definition':
 			return typeTypeDefinition(expr, state);
 			
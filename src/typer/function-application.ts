@@ -2,8 +2,13 @@ import {
 	type ApplicationExpression,
 	type PipelineExpression,
 	type Type,
+	functionType,
 } from '../ast';
-import { nonFunctionApplicationError, formatTypeError } from './type-errors';
+import {
+	nonFunctionApplicationError,
+	overApplicationError,
+	formatTypeError,
+} from './type-errors';
 import {
 	type TypeState,
 	type TypeResult,
@@ -13,7 +18,7 @@ import {
 import { typeExpression } from './expression-dispatcher';
 import { unify } from './unify';
 import { substitute } from './substitute';
-import { typeToString } from './helpers';
+import { throwTypeError, typeToString } from './helpers';
 import { handleTraitFunctionApplication } from './trait-function-handling';
 import { extractFunctionConstraints } from './constraint-resolution';
 import { handleRegularFunctionApplication } from './regular-function-application';
@@ -45,6 +50,9 @@ export const typeApplication = (
 
 	// Try trait function resolution if we have arguments
 	if (argTypes.length > 0) {
+		const uses = currentState.uses;
+		const recorded =
+			expr.func.kind === 'variable' ? uses?.get(expr.func) : undefined;
 		const traitResult = handleTraitFunctionApplication(
 			expr,
 			funcType,
@@ -53,6 +61,22 @@ export const typeApplication = (
 			allEffects
 		);
 		if (traitResult) {
+			// Trait functions are resolved against a fresh copy of their
+			// signature, so unless resolving recorded the implementation's
+			// type, the reference's type (for --type-at) is rebuilt from this
+			// call's arguments and result
+			if (
+				uses &&
+				expr.func.kind === 'variable' &&
+				uses.get(expr.func) === recorded
+			) {
+				uses.set(expr.func, {
+					type: argTypes.reduceRight<Type>(
+						(result, argType) => functionType([argType], result),
+						traitResult.type
+					),
+				});
+			}
 			return traitResult;
 		}
 	}
@@ -79,6 +103,25 @@ export const typeApplication = (
 			allEffects
 		);
 	} else {
+		// A chain of applications that ran out of parameters before it ran out
+		// of arguments: reported at the first argument too many
+		let head = expr.func;
+		let arity = 0;
+		while (head.kind === 'application') {
+			arity += head.args.length;
+			head = head.func;
+		}
+		if (arity > 0) {
+			const callee = head.kind === 'variable' ? head.name : null;
+			const result = typeToString(funcType, currentState.substitution);
+			const { start, end } = expr.args[0].location;
+			const call = head.location.start;
+			throwTypeError(
+				location => overApplicationError(callee, arity, result, location),
+				start,
+				{ kind: 'over-application', callee, arity, location: start, end, call }
+			);
+		}
 		throw new Error(
 			formatTypeError(
 				nonFunctionApplicationError(funcType, {
@@ -121,6 +164,8 @@ export const typePipeline = (
 	let composedType = typeExpression(steps[0], currentState);
 	currentState = composedType.state;
 	let allEffects = composedType.effects;
+	// What each step passes to the next, in composition order
+	const stageTypes: Type[] = [];
 
 	// Compose with each subsequent function type
 	for (let i = 1; i < steps.length; i++) {
@@ -138,6 +183,7 @@ export const typePipeline = (
 				throw new Error(`Pipeline function must take exactly one parameter`);
 			}
 
+			stageTypes.push(composedType.type.return);
 			currentState = unify(
 				composedType.type.return,
 				nextFuncType.type.params[0],
@@ -205,6 +251,8 @@ export const typePipeline = (
 		}
 	}
 
+	expr.stageTypes = isRightToLeft ? stageTypes.reverse() : stageTypes;
+
 	return createTypeResult(
 		substitute(composedType.type, currentState.substitution),
 		allEffects,
@@ -19,7 +19,7 @@ import {
 	type HasStructureConstraint,
 	type RecordStructure,
 } from '../ast';
-import { formatTypeError } from './type-errors';
+import { formatTypeError, type TypeErrorDetail } from './type-errors';
 import { NoolangError } from '../errors';
 import { substitute } from './substitute';
 import { formatEffectsString } from './effects-utils';
@@ -37,13 +37,17 @@ export const getExprLocation = (expr: {
 	column: expr.location?.start.column || 1,
 });
 
-// Helper: Throw formatted type error with consistent pattern
+// Helper: Throw formatted type error with consistent pattern, and the
+// error's detail for tools when there is one
 export function throwTypeError(
 	errorFactory: (location: CodeLocation) => NoolangError,
-	location?: CodeLocation
+	location?: CodeLocation,
+	detail?: TypeErrorDetail
 ): never {
 	const loc = location || { line: 1, column: 1 };
-	throw new Error(formatTypeError(errorFactory(loc)));
+	throw Object.assign(new Error(formatTypeError(errorFactory(loc))), {
+		detail,
+	});
 }
 
 // Helper: Create common function types
@@ -249,6 +249,9 @@ export const typeMatch = (
 	// Type the expression being matched
 	const exprResult = typeExpression(expr.expression, state);
 	let currentState = exprResult.state;
+	// Updated to the state after the cases, which may pin the scrutinee's type
+	const scrutinee = { expr, type: exprResult.type, state: exprResult.state };
+	state.scrutinees?.push(scrutinee);
 
 	// Type each case and ensure they all return the same type
 	if (expr.cases.length === 0) {
@@ -288,6 +291,7 @@ export const typeMatch = (
 		resultType = substitute(resultType, currentState.substitution);
 	}
 
+	scrutinee.state = currentState;
 	checkExhaustiveness(expr, exprResult.type, currentState);
 
 	return createTypeResult(resultType, allEffects, currentState);
@@ -320,6 +324,7 @@ const checkExhaustiveness = (
 	const allConstructors = state.adtRegistry.get(resolved.name)!.constructors;
 	const missing = [...allConstructors.keys()].filter(name => !covered.has(name));
 	if (missing.length > 0) {
+		const at = getExprLocation(expr);
 		throwTypeError(
 			location =>
 				createTypeError(
@@ -331,7 +336,13 @@ const checkExhaustiveness = (
 					{},
 					location
 				),
-			getExprLocation(expr)
+			at,
+			{
+				kind: 'non-exhaustive-match',
+				typeName: resolved.name,
+				missing,
+				location: at,
+			}
 		);
 	}
 };
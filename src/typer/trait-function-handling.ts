@@ -418,6 +418,18 @@ function handleFullTraitFunctionApplication(
 				);
 			}
 
+			// The type this call instantiated the reference to, and the
+			// implementation it dispatches to, for --type-at
+			if (expr.func.kind === 'variable') {
+				currentState.uses?.set(expr.func, {
+					type: freshenedFuncType,
+					implementation: {
+						trait: resolution.traitName!,
+						typeName: resolution.typeName!,
+					},
+				});
+			}
+
 			// Return the function's return type with effects
 			let resultType = substitute(
 				freshenedFuncType.return,
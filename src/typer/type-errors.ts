@@ -14,6 +14,33 @@ export interface TypeErrorContext {
 	codeSnippet?: string;
 }
 
+// What a tool can read off a type error besides its message, for errors
+// whose parts it wants to show separately: a match's missing constructors,
+// or the arguments a call has too many of
+export type TypeErrorDetail =
+	| {
+			kind: 'non-exhaustive-match';
+			typeName: string;
+			missing: string[];
+			location: ErrorLocation;
+	  }
+	| {
+			kind: 'over-application';
+			// The function's name, when it's applied by name
+			callee: string | null;
+			// The arguments it took before its result wasn't a function
+			arity: number;
+			// Where the first argument too many starts and ends
+			location: ErrorLocation;
+			end: ErrorLocation;
+			// Where the call starts
+			call: ErrorLocation;
+	  };
+
+// The detail carried by an error thrown with one (see throwTypeError)
+export const typeErrorDetail = (err: unknown): TypeErrorDetail | undefined =>
+	(err as { detail?: TypeErrorDetail } | undefined)?.detail;
+
 export function createTypeError(
 	message: string,
 	context?: TypeErrorContext,
@@ -160,6 +187,27 @@ export function nonFunctionApplicationError(
 	);
 }
 
+// `f a b c` where `f a b` is already a value: the chain has run out of
+// parameters before it ran out of arguments
+export function overApplicationError(
+	callee: string | null,
+	arity: number,
+	result: string,
+	location?: ErrorLocation
+): NoolangError {
+	const takes = `${arity} argument${arity === 1 ? '' : 's'}`;
+	const message =
+		`Too many arguments: ${callee ?? 'the function'} takes ${takes}, ` +
+		`and its result (${result}) is not a function`;
+	return createTypeError(
+		message,
+		{
+			suggestion: `Arguments apply one after another, so \`f a b c\` passes c to the result of \`f a b\`. If an argument belongs to another call, put that call in parentheses: \`f a (g b c)\`.`,
+		},
+		location
+	);
+}
+
 export function operatorTypeError(
 	operator: string,
 	expectedType: Type,
@@ -21,6 +21,7 @@ import {
 	type ImportExpression,
 	type RecordExpression,
 	type AccessorExpression,
+	type HoleExpression,
 	type ListExpression,
 	type TupleExpression,
 	type WhereExpression,
@@ -279,6 +280,9 @@ const collectFreeVars = (
 			case 'unit':
 				// Unit expressions don't have sub-expressions to walk
 				break;
+			case 'hole':
+				// Holes don't have sub-expressions to walk
+				break;
 			case 'pipeline':
 				e.steps.forEach(step => walk(step, bound));
 				break;
@@ -1071,6 +1075,9 @@ export const typeBinary = (
 	currentState = rightResult.state;
 
 	// Special operators
+	if (expr.operator === '|' || expr.operator === '|?') {
+		expr.inputType = leftResult.type;
+	}
 	if (expr.operator === '|') return handleThrush(expr, currentState);
 	if (expr.operator === '$') return handleDollar(expr, currentState);
 
@@ -1359,6 +1366,18 @@ export const typeRecord = (
 	return createTypeResult(recordType(fields), allEffects, currentState);
 };
 
+// A hole checks as whatever its context needs: a fresh variable, left for
+// unification to pin down
+export const typeHole = (
+	expr: HoleExpression,
+	state: TypeState
+): TypeResult => {
+	const [type, nextState] = freshTypeVariable(state);
+	expr.type = type;
+	state.holes?.push({ expr, environment: new Map(state.environment) });
+	return createPureTypeResult(type, nextState);
+};
+
 // Type inference for accessors
 export const typeAccessor = (
 	expr: AccessorExpression,
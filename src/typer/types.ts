@@ -1,4 +1,11 @@
-import type { Constraint, Type, Effect } from '../ast';
+import type {
+	Constraint,
+	Type,
+	Effect,
+	HoleExpression,
+	MatchExpression,
+	VariableExpression,
+} from '../ast';
 import type { TraitRegistry } from './trait-system';
 import type { ConstraintStore } from './constraint-store';
 
@@ -66,6 +73,31 @@ export type TypeState = {
 	 * (e.g., two modules defining the same (trait, type) instance).
 	 */
 	importerManifest?: import('./module-manifest').Manifest;
+	/**
+	 * When present, every typed hole checked is recorded here with the
+	 * environment in scope at it (for --holes). Shared rather than copied as
+	 * the state is threaded through, so the caller's array sees them all.
+	 */
+	holes?: { expr: HoleExpression; environment: TypeEnvironment }[];
+	/**
+	 * When present, the scrutinee of every match checked is recorded here with
+	 * its type and the state it was typed in (for --pattern-type), shared like
+	 * `holes`.
+	 */
+	scrutinees?: { expr: MatchExpression; type: Type; state: TypeState }[];
+	/**
+	 * When present, every variable reference checked is recorded here with the
+	 * type this use instantiated it to and, for a trait function, the
+	 * implementation it dispatches to (for --type-at), shared like `holes`.
+	 */
+	uses?: Map<VariableExpression, VariableUse>;
+};
+
+// The type a variable reference was instantiated to where it's used, and the
+// implementation a trait function reference dispatches to there
+export type VariableUse = {
+	type: Type;
+	implementation?: { trait: string; typeName: string };
 };
 
 // Type inference result with separated effects
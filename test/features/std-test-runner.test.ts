@@ -91,3 +91,17 @@ test('run_all prints report plus summary and returns counts', () => {
 	expect(out).toContain('✗ breaks');
 	expect(out).toContain('1 passed, 1 failed');
 });
+
+test('std/test-entry builds the program that runs one suite', () => {
+	expectSuccess(
+		`entry_source = import "std/test-entry";
+entry_source "./demo.test.noo"`,
+		[
+			'{@run_all} = import "std/test";',
+			'suite = import "./demo.test.noo";',
+			'result = run_all [{@path "./demo.test.noo", @suite suite}];',
+			'{@failed failed} = result;',
+			'exit (if failed > 0 then 1 else 0)',
+		].join('\n')
+	);
+});
@@ -0,0 +1,242 @@
+// The queries the language server makes (src/queries.ts), called the way its
+// in-process backend does. Output must match what the CLI prints for the
+// same flags, since the server parses both the same way.
+import { afterAll, test, expect } from 'bun:test';
+import { mkdirSync, mkdtempSync, writeFileSync, rmSync } from 'node:fs';
+import { tmpdir } from 'node:os';
+import { join } from 'node:path';
+import { runQuery } from '../../src/queries';
+import { KEYWORDS, OPERATORS } from '../../src/lexer/lexer';
+
+const dir = mkdtempSync(join(tmpdir(), 'noo-lsp-queries-'));
+const file = join(dir, 'main.noo');
+writeFileSync(
+	file,
+	'plus = fn x y => x + y;\nshout = fn s => s + "!";\nplus 1 2'
+);
+const broken = join(dir, 'broken.noo');
+writeFileSync(
+	broken,
+	'plus = fn x y => x + y;\nbad = (1 + ;\nshout = fn s => s + "!";\nplus 1 2'
+);
+
+afterAll(() => rmSync(dir, { recursive: true, force: true }));
+
+const lastLine = (stdout: string) => stdout.trim().split('\n').pop() ?? '';
+
+test('--symbol-types reports every top-level definition by default', () => {
+	const result = runQuery(['--symbol-types', file]);
+	expect(result?.status).toBe(0);
+	expect(JSON.parse(lastLine(result!.stdout))).toEqual({
+		plus: 'a -> a -> a given a implements Add',
+		shout: 'String -> String',
+	});
+});
+
+test('--symbol-types maps names that do not resolve to null', () => {
+	const result = runQuery(['--symbol-types', file, 'shout', 'missing']);
+	expect(JSON.parse(lastLine(result!.stdout))).toEqual({
+		shout: 'String -> String',
+		missing: null,
+	});
+});
+
+test('--ast-partial keeps the statements that parse', () => {
+	const result = runQuery(['--ast-partial', broken]);
+	expect(result?.status).toBe(0);
+	const ast = JSON.parse(result!.stdout.slice(result!.stdout.indexOf('{')));
+	expect(ast.errors).toHaveLength(1);
+	expect(ast.statements.map((s: { name?: string }) => s.name)).toEqual([
+		'plus',
+		'shout',
+		undefined,
+	]);
+});
+
+test('--expr-type types an expression against the file', () => {
+	expect(runQuery(['--expr-type', file, 'shout "hi"'])?.stdout).toBe(
+		'Expression has type: String\n'
+	);
+});
+
+test('--type-at gives a reference the type it has at that use', () => {
+	const at = (line: number, column: number) =>
+		JSON.parse(
+			runQuery(['--json', '--type-at', file, `${line}`, `${column}`])!.stdout
+		);
+	expect(at(3, 1)).toEqual({
+		name: 'plus',
+		type: 'Float -> Float -> Float',
+		resolutions: [{ trait: 'Add', type: 'Float' }],
+	});
+	expect(at(3, 6)).toEqual({ name: null, type: null, resolutions: [] });
+});
+
+test('--pipeline-types gives the type crossing each pipeline operator', () => {
+	const piped = join(dir, 'piped.noo');
+	writeFileSync(
+		piped,
+		'inc = fn x => x + 1;\nr = [1, 2] | map inc | length;\nh = inc |> toString;\nr'
+	);
+	const result = runQuery(['--json', '--pipeline-types', piped]);
+	expect(JSON.parse(result!.stdout).pipelines).toEqual([
+		{ line: 2, column: 5, types: ['List Float', 'List Float'] },
+		{ line: 3, column: 5, types: ['Float'] },
+	]);
+});
+
+test('--holes gives what each hole needs and the bindings that fit', () => {
+	const holey = join(dir, 'holey.noo');
+	writeFileSync(
+		holey,
+		'greeting = "hi";\nshout = fn s => concat s ?;\ny = if ??? then 1 else 2'
+	);
+	const result = runQuery(['--json', '--holes', holey]);
+	const [string, bool] = JSON.parse(result!.stdout).holes;
+	expect(string).toMatchObject({ line: 2, column: 26, type: 'String' });
+	expect(string.candidates.slice(0, 2)).toEqual(['s', 'greeting']);
+	expect(bool).toMatchObject({ line: 3, column: 8, type: 'Bool' });
+	expect(bool.candidates).toContain('True');
+});
+
+test('--expected-type puts a hole where the cursor is and types it', () => {
+	const typing = join(dir, 'typing.noo');
+	writeFileSync(typing, 'greeting = "hi";\nloud = greeting');
+	const expected = (line: number, column: number, text?: string) =>
+		JSON.parse(
+			runQuery([
+				'--json',
+				'--expected-type',
+				typing,
+				String(line),
+				String(column),
+				...(text === undefined ? [] : [text]),
+			])!.stdout
+		);
+	// The unsaved line stands in for the saved one, word being typed and all
+	const loud = expected(2, 17, 'loud = toUpper gr');
+	expect(loud.type).toBe('String');
+	expect(loud.candidates).toContain('greeting');
+	expect(loud.templates).toContain('toUpper ?');
+	// Nothing constrains the saved `loud = greeting`, so nothing is proposed
+	expect(expected(2, 8)).toEqual({ type: 'a', candidates: [], templates: [] });
+});
+
+test('--pattern-type gives the constructors of the matched type', () => {
+	const shapes = join(dir, 'shapes.noo');
+	writeFileSync(
+		shapes,
+		[
+			'variant Shape = Circle Float | Rect Float Float;',
+			'area = fn s => match s (',
+			'  Circle r => r;',
+			');',
+			'area (Circle 1)',
+		].join('\n')
+	);
+	const pattern = (line: number, column: number, text?: string) =>
+		JSON.parse(
+			runQuery([
+				'--json',
+				'--pattern-type',
+				shapes,
+				String(line),
+				String(column),
+				...(text === undefined ? [] : [text]),
+			])!.stdout
+		);
+	const constructors = [
+		{ name: 'Circle', arity: 1 },
+		{ name: 'Rect', arity: 2 },
+	];
+	// A case being written on an unsaved line, after the saved ones
+	expect(pattern(3, 21, '  Circle r => r; Re')).toEqual({
+		type: 'Shape',
+		constructors,
+	});
+	expect(pattern(3, 3)).toEqual({ type: 'Shape', constructors });
+	// Not in a match
+	expect(pattern(5, 1)).toEqual({ type: null, constructors: [] });
+});
+
+test('--json answers each query with one line of JSON', () => {
+	const json = (args: string[]) =>
+		JSON.parse(runQuery(['--json', ...args])!.stdout);
+	expect(json(['--types-file', file])).toEqual({ types: ['Float'] });
+	expect(json(['--symbol-types', file, 'shout'])).toEqual({
+		types: { shout: 'String -> String' },
+	});
+	expect(json(['--symbol-type', file, 'plus'])).toEqual({
+		symbol: 'plus',
+		type: 'a -> a -> a given a implements Add',
+	});
+	expect(json(['--expr-type', file, 'shout "hi"'])).toEqual({
+		type: 'String',
+	});
+	expect(json(['--ast-partial', broken]).errors).toHaveLength(1);
+});
+
+test('errors come back as CLI-style stderr with a non-zero status', () => {
+	const result = runQuery(['--types-file', join(dir, 'nope.noo')]);
+	expect(result?.status).toBe(1);
+	expect(result?.stderr).toStartWith('Error: ');
+});
+
+test('--json errors come back on stdout too, with any structured detail', () => {
+	const partial = join(dir, 'partial.noo');
+	writeFileSync(
+		partial,
+		'variant C = R | G;\nf = fn c => match c (R => 1);\nf R'
+	);
+	const result = runQuery(['--json', '--types-file', partial]);
+	expect(result?.status).toBe(1);
+	expect(result?.stderr).toStartWith('Error: ');
+	expect(JSON.parse(result!.stdout).error.detail).toEqual({
+		kind: 'non-exhaustive-match',
+		typeName: 'C',
+		missing: ['G'],
+		location: { line: 2, column: 13 },
+	});
+});
+
+test('flags that are not queries are left to the CLI', () => {
+	expect(runQuery(['--eval-expr', file, '1'])).toBeUndefined();
+	expect(runQuery(['--symbol-type', file])).toBeUndefined();
+});
+
+test('--capabilities lists the flags the CLI answers', () => {
+	const result = runQuery(['--capabilities']);
+	const { version, flags } = JSON.parse(lastLine(result!.stdout));
+	expect(typeof version).toBe('string');
+	expect(flags).toContain('--symbol-types');
+	expect(flags).toContain('--capabilities');
+});
+
+test('--capabilities reports the lexer\'s keywords and operators', () => {
+	const result = runQuery(['--capabilities']);
+	const { keywords, operators } = JSON.parse(lastLine(result!.stdout));
+	expect(keywords).toEqual([...KEYWORDS]);
+	expect(operators).toEqual([...OPERATORS]);
+	expect(keywords).toContain('mut!');
+	expect(operators).toContain('|?');
+});
+
+test('--import-base resolves relative imports from another directory', () => {
+	const base = join(dir, 'notebook');
+	mkdirSync(base);
+	writeFileSync(
+		join(base, 'lib.noo'),
+		'double = fn n => n * 2;\n{ @double double }'
+	);
+	const combined = join(dir, 'combined.noo');
+	writeFileSync(combined, 'lib = import "./lib";\n(@double lib) 3');
+	expect(runQuery(['--types-file', combined])?.status).toBe(1);
+	const result = runQuery([
+		'--import-base',
+		base,
+		'--json',
+		'--types-file',
+		combined,
+	]);
+	expect(JSON.parse(lastLine(result!.stdout))).toEqual({ types: ['Float'] });
+});
@@ -93,3 +93,45 @@ test('no test files found: says so and exits 0', () => {
 	expect(status).toBe(0);
 	rmSync(dir, { recursive: true, force: true });
 });
+
+// `noo --test-file`: one suite, in process, for the language server's lenses
+function runTestFile(
+	cwd: string,
+	file: string
+): { status: number; out: string } {
+	try {
+		const out = execFileSync('bun', [cli, '--test-file', file], {
+			cwd,
+			encoding: 'utf8',
+			stdio: 'pipe',
+			env: { ...process.env, NO_COLOR: '1' },
+		});
+		return { status: 0, out };
+	} catch (error: any) {
+		return {
+			status: error.status ?? -1,
+			out: String(error.stdout ?? '') + String(error.stderr ?? ''),
+		};
+	}
+}
+
+test('--test-file runs only the given suite', () => {
+	const dir = makeProject({
+		'math.test.noo': passing,
+		'sub/broken.test.noo': failing,
+	});
+	const { status, out } = runTestFile(dir, 'math.test.noo');
+	expect(out).toContain('✓ adds');
+	expect(out).not.toContain('✗ wrong');
+	expect(status).toBe(0);
+	rmSync(dir, { recursive: true, force: true });
+});
+
+test('--test-file exits nonzero when a case fails', () => {
+	const dir = makeProject({ 'sub/broken.test.noo': failing });
+	const { status, out } = runTestFile(dir, join(dir, 'sub', 'broken.test.noo'));
+	expect(out).toContain('✗ wrong');
+	expect(out).toContain('expected: 7');
+	expect(status).not.toBe(0);
+	rmSync(dir, { recursive: true, force: true });
+});